@@ -1,22 +1,126 @@
-use std::{collections::HashMap, fs::File, io::BufReader};
+use std::{collections::HashMap, fs::File, io::BufReader, str::FromStr};
 
-type Record = HashMap<String, String>;
+use crate::{error::Error, time};
+
+pub type Record = HashMap<String, String>;
 
 /// read CSV file into a vector of HashMaps
 ///
 /// each entry in the vec corresponds to one line, where entry is <fieldname> -> <value>
-pub fn read_to_maps(filepath: &str) -> Vec<HashMap<String, String>> {
-    let reader =
-        BufReader::new(File::open(filepath).expect(&format!("Could not open file {}", filepath)));
+pub fn read_to_maps(filepath: &str) -> Result<Vec<Record>, Error> {
+    let reader = BufReader::new(File::open(filepath).map_err(|source| Error::Io {
+        file: filepath.to_string(),
+        source,
+    })?);
 
     let mut rows: Vec<Record> = Vec::new();
 
     // Build the CSV reader and iterate over each record.
     let mut csv_reader = csv::Reader::from_reader(reader);
-    for (row_index, result) in csv_reader.deserialize().enumerate() {
+    for (record_index, result) in csv_reader.deserialize().enumerate() {
         // The iterator yields Result<StringRecord, Error>, so we check the error here.
-        rows.push(result.expect(&format!("Failed to read record in line {}", row_index)));
+        rows.push(result.map_err(|source| Error::Csv {
+            file: filepath.to_string(),
+            row: row(record_index),
+            source,
+        })?);
+    }
+
+    Ok(rows)
+}
+
+/// returns the row (line number in the file, header is row 1) of the record with record_index
+#[inline]
+pub fn row(record_index: usize) -> usize {
+    record_index + 2
+}
+
+/// returns the value of column in record
+pub fn field<'r>(
+    record: &'r Record,
+    filepath: &str,
+    record_index: usize,
+    column: &str,
+) -> Result<&'r str, Error> {
+    match record.get(column) {
+        Some(value) => Ok(value),
+        None => Err(Error::MissingColumn {
+            file: filepath.to_string(),
+            row: row(record_index),
+            column: column.to_string(),
+        }),
+    }
+}
+
+/// returns the parsed value of column in record
+pub fn parse_field<T: FromStr>(
+    record: &Record,
+    filepath: &str,
+    record_index: usize,
+    column: &str,
+) -> Result<T, Error> {
+    let value = field(record, filepath, record_index, column)?;
+
+    value.parse().map_err(|_| Error::InvalidValue {
+        file: filepath.to_string(),
+        row: row(record_index),
+        column: column.to_string(),
+        value: value.to_string(),
+    })
+}
+
+/// returns the parsed time of column in record (see `time::parse_time()`)
+pub fn parse_time_field(
+    record: &Record,
+    filepath: &str,
+    record_index: usize,
+    column: &str,
+    reference_date: Option<i64>,
+) -> Result<u64, Error> {
+    let value = field(record, filepath, record_index, column)?;
+
+    time::parse_time(value, reference_date).ok_or_else(|| Error::InvalidValue {
+        file: filepath.to_string(),
+        row: row(record_index),
+        column: column.to_string(),
+        value: value.to_string(),
+    })
+}
+
+/// returns the value of an optional column in record, None if the column is missing or empty
+pub fn optional_field<'r>(record: &'r Record, column: &str) -> Option<&'r str> {
+    record.get(column).map(|value| value.as_str()).filter(|value| !value.trim().is_empty())
+}
+
+/// returns the parsed value of an optional column in record, None if the column is missing or empty
+pub fn parse_optional_field<T: FromStr>(
+    record: &Record,
+    filepath: &str,
+    record_index: usize,
+    column: &str,
+) -> Result<Option<T>, Error> {
+    match optional_field(record, column) {
+        Some(_) => parse_field(record, filepath, record_index, column).map(Some),
+        None => Ok(None),
     }
+}
 
-    rows
+/// returns the boolean value (`1`/`true` or `0`/`false`) of an optional column in record, None if the column is missing or empty
+pub fn parse_optional_bool_field(
+    record: &Record,
+    filepath: &str,
+    record_index: usize,
+    column: &str,
+) -> Result<Option<bool>, Error> {
+    match optional_field(record, column).map(|value| value.trim()) {
+        None => Ok(None),
+        Some("1") | Some("true") => Ok(Some(true)),
+        Some("0") | Some("false") => Ok(Some(false)),
+        Some(value) => Err(Error::InvalidValue {
+            file: filepath.to_string(),
+            row: row(record_index),
+            column: column.to_string(),
+            value: value.to_string(),
+        }),
+    }
 }
@@ -0,0 +1,84 @@
+use std::{fmt, io};
+
+/// errors that can occur while reading the input files and building the model
+///
+/// every variant of input files carries the file name, variants of CSV files also the row (line number in the file, the header is row 1)
+#[derive(Debug)]
+pub enum Error {
+    Io { // file could not be opened
+        file: String,
+        source: io::Error,
+    },
+
+    Csv { // record could not be read by the CSV parser
+        file: String,
+        row: usize,
+        source: csv::Error,
+    },
+
+    MissingColumn { // record does not contain a required column
+        file: String,
+        row: usize,
+        column: String,
+    },
+
+    InvalidValue { // value of a column could not be parsed
+        file: String,
+        row: usize,
+        column: String,
+        value: String,
+    },
+
+    UnknownStation { // referenced station id is not part of stations.csv
+        file: String,
+        row: usize,
+        station_id: u64,
+    },
+
+    UnknownTripStation { // station id of a trip added to an existing model is not part of the model
+        trip_id: u64,
+        station_id: u64,
+    },
+
+    DuplicateId { // id that has to be unique is used more than once
+        file: String,
+        row: usize,
+        id: u64,
+    },
+
+    InvalidConfig { // configuration file could not be parsed or contains invalid values
+        file: String,
+        message: String,
+    },
+
+    InvalidFeed { // realtime feed (GTFS-RT protobuf or JSON) could not be decoded
+        file: String,
+        message: String,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io {file, source} => write!(f, "{}: could not open file ({})", file, source),
+            Self::Csv {file, row, source} => write!(f, "{}:{}: could not read record ({})", file, row, source),
+            Self::MissingColumn {file, row, column} => write!(f, "{}:{}: missing column '{}'", file, row, column),
+            Self::InvalidValue {file, row, column, value} => write!(f, "{}:{}: invalid value '{}' in column '{}'", file, row, value, column),
+            Self::UnknownStation {file, row, station_id} => write!(f, "{}:{}: unknown station {}", file, row, station_id),
+            Self::UnknownTripStation {trip_id, station_id} => write!(f, "trip {}: unknown station {}", trip_id, station_id),
+            Self::DuplicateId {file, row, id} => write!(f, "{}:{}: duplicate id {}", file, row, id),
+            Self::InvalidConfig {file, message} => write!(f, "{}: invalid config ({})", file, message),
+            Self::InvalidFeed {file, message} => write!(f, "{}: invalid realtime feed ({})", file, message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io {file: _, source} => Some(source),
+            Self::Csv {file: _, row: _, source} => Some(source),
+            _ => None,
+        }
+    }
+}
@@ -1,11 +1,19 @@
-use std::{env, fs::OpenOptions, io::prelude::*};
-
-use model::{group::Group, Model};
+use std::{env, fs::OpenOptions, io::prelude::*, process::exit, time::Duration};
+
+use model::{cost_model::CostModel, group::Group, gtfs::GtfsConfig, path::PathSearch, Model};
+use optimization::{
+    cooling_schedule::{AnnealingParameters, ScheduleKind, StoppingCriteria},
+    large_neighborhood_search::LnsParameters,
+    tabu_search::{Neighborhood, TabuParameters},
+    GroupOrder, InitialState,
+};
 use petgraph::{EdgeDirection::Outgoing, graph::NodeIndex};
 
 mod csv_reader;
+mod error;
 mod model;
 mod optimization;
+mod time;
 use clap::{App, Arg, SubCommand, Values};
 
 /// main entry point of the program, configurable by CLI parameters
@@ -26,18 +34,97 @@ fn main() {
             .help("folder path of the input CSV files")
             .value_name("FOLDER"))
 
+        .arg(Arg::with_name("gtfs_folder_path")
+            .long("gtfs")
+            .help("folder path of a GTFS static feed, used instead of the input CSV files for stations, trips and footpaths")
+            .value_name("FOLDER")
+            .conflicts_with("input_folder_path")
+            .requires("service_date"))
+
+        .arg(Arg::with_name("service_date")
+            .long("service_date")
+            .help("operating day (YYYYMMDD) of the GTFS feed, only trips running on this day are imported")
+            .value_name("YYYYMMDD"))
+
+        .arg(Arg::with_name("default_capacity")
+            .long("default_capacity")
+            .help("capacity of GTFS trips without an entry in the capacities file")
+            .default_value("500")
+            .value_name("INTEGER"))
+
+        .arg(Arg::with_name("default_transfer_time")
+            .long("default_transfer_time")
+            .help("transfer time (in minutes) of GTFS stops without a same-stop entry in transfers.txt")
+            .default_value("2")
+            .value_name("INTEGER"))
+
+        .arg(Arg::with_name("capacities_filepath")
+            .long("capacities")
+            .help("CSV file with the capacity of GTFS trips or routes (columns: trip_id or route_id, capacity)")
+            .value_name("FILE"))
+
+        .arg(Arg::with_name("groups_filepath")
+            .long("groups")
+            .help("filepath of the groups CSV file (default=<input or gtfs folder>/groups.csv)")
+            .value_name("FILE"))
+
+        .arg(Arg::with_name("cost_model_filepath")
+            .long("cost_model")
+            .help("TOML or JSON file with the weights of the cost model (default=original hard-coded costs)")
+            .value_name("FILE"))
+
+        .arg(Arg::with_name("scenario_filepath")
+            .long("scenario")
+            .help("TOML or JSON file with disruptions (cancelled trips, delays, closed stations, capacity reductions). If specified, the model is updated after the optimization and the selection of simulated annealing on path is re-optimized.")
+            .value_name("FILE"))
+
+        .arg(Arg::with_name("realtime_folder_path")
+            .long("realtime")
+            .help("Folder with realtime updates as GTFS-RT TripUpdates protobuf (.pb, .bin) or JSON (.json) files. If specified, the updates are replayed in order of their file names after the optimization and the selection is re-optimized after each update.")
+            .value_name("FOLDER")
+            .conflicts_with("scenario_filepath"))
+
         .arg(Arg::with_name("export_as_dot_filepath")
             .short("e")
             .long("export_as_dot_filepath")
             .help("If specified, exports the time-expanded timetable graph as GraphViz DOT-Code to filepath")
             .value_name("FILE"))
 
+        .arg(Arg::with_name("export_lp_filepath")
+            .long("export_lp")
+            .help("If specified, exports the path selection problem as MIP in CPLEX LP format to filepath")
+            .value_name("FILE"))
+
+        .arg(Arg::with_name("export_mps_filepath")
+            .long("export_mps")
+            .help("If specified, exports the path selection problem as MIP in free MPS format to filepath")
+            .value_name("FILE"))
+
+        .arg(Arg::with_name("n_overflow_segments")
+            .long("n_overflow_segments")
+            .help("Specifies the maximum number of piecewise-linear overflow segments per trip in the exported MIP (exact if no trip can be overcrowded by more passengers)")
+            .default_value("100")
+            .value_name("INTEGER"))
+
         .arg(Arg::with_name("output_folder_path")
             .short("o")
             .long("output")
             .help("folder path for the output CSV file (default='.' aka. current directory)")
             .value_name("FOLDER"))
 
+        .arg(Arg::with_name("path_search")
+            .long("path_search")
+            .help("Specifies the algorithm searching the routes of each group: iterative-deepening-depth-first search (iddfs), the k cheapest routes (k_shortest_paths) or the Pareto set regarding arrival time, transfers and walking time (pareto).")
+            .possible_values(&["iddfs", "k_shortest_paths", "pareto"])
+            .default_value("iddfs")
+            .value_name("MODE"))
+
+        .arg(Arg::with_name("k_shortest_paths")
+            .long("k")
+            .help("Specifies the number of routes the k_shortest_paths search returns for each group.")
+            .default_value("50")
+            .value_name("INTEGER"))
+
         .arg(Arg::with_name("search_budgets")
             .short("b")
             .long("search_budgets")
@@ -73,13 +160,168 @@ fn main() {
             .default_value("500")
             .value_name("INTEGER"))
 
+        .arg(Arg::with_name("initial_state")
+            .long("initial_state")
+            .help("Specifies the state the optimizers start with: the best route of each group (best_path) or the cheapest route regarding the residual capacity of the trips, assigned one group after another (greedy).")
+            .possible_values(&["best_path", "greedy"])
+            .default_value("best_path")
+            .value_name("STATE"))
+
+        .arg(Arg::with_name("greedy_order")
+            .long("greedy_order")
+            .help("Specifies the order in which the greedy initial state assigns the groups: largest groups first (passengers), earliest departure first (departure) or smallest slack between planned and earliest possible arrival first (slack).")
+            .possible_values(&["passengers", "departure", "slack"])
+            .default_value("passengers")
+            .value_name("ORDER"))
+
+        .arg(Arg::with_name("split_groups")
+            .long("split_groups")
+            .help("Enables the hard capacity mode, which distributes the passengers of each group over several of its routes, so that no trip exceeds its capacity (groups in the order of --greedy_order)."))
+
+        .arg(Arg::with_name("cooling_schedule")
+            .long("cooling_schedule")
+            .help("Specifies the cooling schedule of simulated annealing: initial / time (hyperbolic), geometric, linear, logarithmic or geometric with a targeted acceptance rate (adaptive).")
+            .possible_values(&["hyperbolic", "geometric", "linear", "logarithmic", "adaptive"])
+            .default_value("hyperbolic")
+            .value_name("SCHEDULE"))
+
+        .arg(Arg::with_name("initial_temperature_sa1")
+            .long("initial_temperature_sa1")
+            .help("Specifies the initial temperature of simulated annealing (default: n_optimization_iterations_sa1).")
+            .value_name("FLOAT"))
+
+        .arg(Arg::with_name("final_temperature_sa1")
+            .long("final_temperature_sa1")
+            .help("Specifies the temperature below which simulated annealing stops.")
+            .default_value("1")
+            .value_name("FLOAT"))
+
+        .arg(Arg::with_name("initial_temperature_sa2")
+            .long("initial_temperature_sa2")
+            .help("Specifies the initial temperature of simulated annealing on path (default: 50 * n_optimization_iterations_sa2).")
+            .value_name("FLOAT"))
+
+        .arg(Arg::with_name("final_temperature_sa2")
+            .long("final_temperature_sa2")
+            .help("Specifies the temperature below which simulated annealing on path stops.")
+            .default_value("50")
+            .value_name("FLOAT"))
+
+        .arg(Arg::with_name("target_acceptance")
+            .long("target_acceptance")
+            .help("Specifies the initial target acceptance rate of the adaptive cooling schedule, which decreases linearly to zero.")
+            .default_value("0.5")
+            .value_name("FLOAT"))
+
+        .arg(Arg::with_name("reheat_window")
+            .long("reheat_window")
+            .help("Specifies the number of iterations without a new best state after which the cooling schedule is restarted at half its former temperature (0 = no reheating).")
+            .default_value("0")
+            .value_name("INTEGER"))
+
+        .arg(Arg::with_name("max_duration")
+            .long("max_duration")
+            .help("Specifies the wall clock limit (in seconds) of each simulated annealing step (0 = unlimited).")
+            .default_value("0")
+            .value_name("INTEGER"))
+
+        .arg(Arg::with_name("no_improvement_window")
+            .long("no_improvement_window")
+            .help("Specifies the number of iterations without a new best state after which simulated annealing stops (0 = disabled).")
+            .default_value("0")
+            .value_name("INTEGER"))
+
+        .arg(Arg::with_name("n_chains")
+            .long("n_chains")
+            .help("Specifies the number of simulated annealing chains that run in parallel threads in the first optimization step (1 = single chain).")
+            .default_value("1")
+            .value_name("INTEGER"))
+
+        .arg(Arg::with_name("exchange_interval")
+            .long("exchange_interval")
+            .help("Specifies the number of iterations between state exchanges of neighboring chains with increasing temperatures (0 = independent chains).")
+            .default_value("0")
+            .value_name("INTEGER"))
+
+        .arg(Arg::with_name("seed")
+            .long("seed")
+            .help("Specifies the seed of the random number generators of all randomized optimization algorithms, so runs can be reproduced (random if not given).")
+            .value_name("INTEGER"))
+
+        .arg(Arg::with_name("n_iterations_lagrangian")
+            .long("n_iterations_lagrangian")
+            .help("Specifies the number of subgradient iterations of the lagrangian relaxation, which computes a lower bound and appends new routes to the groups (0 = disabled).")
+            .default_value("0")
+            .value_name("INTEGER"))
+
+        .arg(Arg::with_name("n_iterations_tabu")
+            .long("n_iterations_tabu")
+            .help("Specifies the number of iterations of tabu search over the selected routes of the groups (0 = disabled).")
+            .default_value("0")
+            .value_name("INTEGER"))
+
+        .arg(Arg::with_name("tabu_tenure")
+            .long("tabu_tenure")
+            .help("Specifies the number of iterations tabu search must not select the former route of a group again.")
+            .default_value("10")
+            .value_name("INTEGER"))
+
+        .arg(Arg::with_name("diversification_window")
+            .long("diversification_window")
+            .help("Specifies the number of iterations without a new best state after which tabu search restarts with the least selected routes (0 = no restarts).")
+            .default_value("100")
+            .value_name("INTEGER"))
+
+        .arg(Arg::with_name("tabu_neighborhood")
+            .long("tabu_neighborhood")
+            .help("Specifies the neighborhood of tabu search: each other route of each group (all) or the previous and next route of each group (direct).")
+            .possible_values(&["all", "direct"])
+            .default_value("all")
+            .value_name("NEIGHBORHOOD"))
+
+        .arg(Arg::with_name("n_iterations_lns")
+            .long("n_iterations_lns")
+            .help("Specifies the number of destroy and repair iterations of the adaptive large neighborhood search, which appends new routes to the groups (0 = disabled).")
+            .default_value("0")
+            .value_name("INTEGER"))
+
+        .arg(Arg::with_name("lns_trips")
+            .long("lns_trips")
+            .help("Specifies the number of most overloaded trip edges whose groups are removed by the overloaded trips destroy operator of the large neighborhood search.")
+            .default_value("3")
+            .value_name("INTEGER"))
+
+        .arg(Arg::with_name("lns_time_window")
+            .long("lns_time_window")
+            .help("Specifies the width (in minutes) of the time window whose groups are removed by the time window destroy operator of the large neighborhood search.")
+            .default_value("30")
+            .value_name("INTEGER"))
+
         .get_matches();
 
     // parse config values from cli args
     let input_folder_path_option = matches.value_of("input_folder_path");
 
+    let gtfs_folder_path_option = matches.value_of("gtfs_folder_path");
+
+    let groups_filepath_option = matches.value_of("groups_filepath");
+
     let export_as_dot_option = matches.value_of("export_as_dot_filepath");
 
+    let export_lp_option = matches.value_of("export_lp_filepath");
+
+    let export_mps_option = matches.value_of("export_mps_filepath");
+
+    let scenario_option = matches.value_of("scenario_filepath");
+
+    let realtime_option = matches.value_of("realtime_folder_path");
+
+    let n_overflow_segments: u64 = matches
+        .value_of("n_overflow_segments")
+        .unwrap()
+        .parse()
+        .expect("n_overflow_segments has to be a positive integer");
+
     let output_folder_path = matches.value_of("output_folder_path").unwrap_or(".");
 
     let mut search_budgets: Vec<u64> = matches
@@ -96,6 +338,23 @@ fn main() {
         .parse()
         .expect("min_paths has to be a positive integer");
 
+    let path_search = match matches.value_of("path_search").unwrap() {
+        "k_shortest_paths" => PathSearch::KShortestPaths {
+            k: matches
+                .value_of("k_shortest_paths")
+                .unwrap()
+                .parse()
+                .ok()
+                .filter(|k| *k > 0)
+                .expect("k has to be a positive integer"),
+        },
+        "pareto" => PathSearch::Pareto,
+        _ => PathSearch::Iddfs {
+            search_budgets,
+            min_paths,
+        },
+    };
+
     let n_search_threads: usize = matches
         .value_of("n_search_threads")
         .unwrap()
@@ -114,29 +373,230 @@ fn main() {
         .parse()
         .expect("n_optimization_iterations has to be a positive integer");
 
+    let greedy_order = GroupOrder::from_name(matches.value_of("greedy_order").unwrap()).unwrap();
 
+    let initial_state = match matches.value_of("initial_state").unwrap() {
+        "greedy" => InitialState::Greedy(greedy_order),
+        _ => InitialState::BestPath,
+    };
 
+    let split_groups = matches.is_present("split_groups");
 
-    // EXPLANATION OF input_folder_path:
-    // if <input_folder_path> specified, the program will try to read all CSVs from there + create a new model + search paths for all groups + create a snapshot of current model and continue with best path selection
-    // if <input_folder_path> is NOT specified, the proram will try to load a snapshot from a previous run and directly continue with best path selection
+    let schedule = ScheduleKind::from_name(matches.value_of("cooling_schedule").unwrap()).unwrap();
 
-    let (mut model, groups) = if let Some(input_folder_path) = input_folder_path_option {
-        // load model and groups from CSV files
+    let parse_temperature = |name: &str, default: f64| -> f64 {
+        matches
+            .value_of(name)
+            .map(|value| value.parse().unwrap_or_else(|_| panic!("{} has to be a positive number", name)))
+            .unwrap_or(default)
+    };
 
-        println!(
-            "creating new model with_stations_trips_and_footpaths({}) and groups",
-            input_folder_path
-        );
+    let target_acceptance: f64 = matches
+        .value_of("target_acceptance")
+        .unwrap()
+        .parse()
+        .expect("target_acceptance has to be a number between 0 and 1");
+
+    let reheat_window: u64 = matches
+        .value_of("reheat_window")
+        .unwrap()
+        .parse()
+        .expect("reheat_window has to be a positive integer");
 
-        let model = Model::with_stations_trips_and_footpaths(input_folder_path);
-        let groups = model
+    let max_duration: u64 = matches
+        .value_of("max_duration")
+        .unwrap()
+        .parse()
+        .expect("max_duration has to be a positive integer");
+
+    let no_improvement_window: u64 = matches
+        .value_of("no_improvement_window")
+        .unwrap()
+        .parse()
+        .expect("no_improvement_window has to be a positive integer");
+
+    // parameters of both simulated annealing steps only differ in temperatures and iterations
+    let annealing_parameters = |initial_temperature: f64, final_temperature: f64, n_iterations: u64| AnnealingParameters {
+        initial_state: initial_state.clone(),
+        schedule,
+        initial_temperature,
+        final_temperature,
+        target_acceptance,
+        reheat_window: Some(reheat_window).filter(|window| *window > 0),
+        stopping: StoppingCriteria {
+            max_duration: Some(Duration::from_secs(max_duration)).filter(|duration| duration.as_secs() > 0),
+            no_improvement_window: Some(no_improvement_window).filter(|window| *window > 0),
+            ..AnnealingParameters::with_iterations(n_iterations).stopping
+        },
+        movable_groups: None,
+    };
+
+    // detours of simulated annealing on path change the cost by larger deltas, so its default temperatures are 50 times higher
+    let annealing_parameters_sa1 = annealing_parameters(
+        parse_temperature("initial_temperature_sa1", n_optimization_iterations_sa1 as f64),
+        parse_temperature("final_temperature_sa1", 1.0),
+        n_optimization_iterations_sa1,
+    );
+
+    let annealing_parameters_sa2 = annealing_parameters(
+        parse_temperature("initial_temperature_sa2", 50.0 * n_optimization_iterations_sa2 as f64),
+        parse_temperature("final_temperature_sa2", 50.0),
+        n_optimization_iterations_sa2,
+    );
+
+    let n_chains: usize = matches
+        .value_of("n_chains")
+        .unwrap()
+        .parse()
+        .expect("n_chains has to be a positive integer");
+
+    let exchange_interval: u64 = matches
+        .value_of("exchange_interval")
+        .unwrap()
+        .parse()
+        .expect("exchange_interval has to be a positive integer");
+
+    let seed: u64 = matches
+        .value_of("seed")
+        .map(|seed| seed.parse().expect("seed has to be a positive integer"))
+        .unwrap_or_else(rand::random);
+
+    println!("seed={} (pass --seed {} to reproduce this run)", seed, seed);
+
+    let n_iterations_lagrangian: u64 = matches
+        .value_of("n_iterations_lagrangian")
+        .unwrap()
+        .parse()
+        .expect("n_iterations_lagrangian has to be a positive integer");
+
+    let tabu_parameters = TabuParameters {
+        n_iterations: matches
+            .value_of("n_iterations_tabu")
+            .unwrap()
+            .parse()
+            .expect("n_iterations_tabu has to be a positive integer"),
+        tabu_tenure: matches
+            .value_of("tabu_tenure")
+            .unwrap()
+            .parse()
+            .expect("tabu_tenure has to be a positive integer"),
+        diversification_window: matches
+            .value_of("diversification_window")
+            .unwrap()
+            .parse()
+            .expect("diversification_window has to be a positive integer"),
+        neighborhood: match matches.value_of("tabu_neighborhood").unwrap() {
+            "direct" => Neighborhood::Direct,
+            _ => Neighborhood::All,
+        },
+        initial_state: initial_state.clone(),
+    };
+
+    let lns_parameters = LnsParameters {
+        n_iterations: matches
+            .value_of("n_iterations_lns")
+            .unwrap()
+            .parse()
+            .expect("n_iterations_lns has to be a positive integer"),
+        n_trips: matches
+            .value_of("lns_trips")
+            .unwrap()
+            .parse()
+            .expect("lns_trips has to be a positive integer"),
+        time_window: matches
+            .value_of("lns_time_window")
+            .unwrap()
+            .parse()
+            .expect("lns_time_window has to be a positive integer"),
+        segment_length: 100,
+        reaction_factor: 0.1,
+        initial_state,
+    };
+
+    let cost_model = match matches.value_of("cost_model_filepath") {
+        Some(cost_model_filepath) => match CostModel::from_file(cost_model_filepath) {
+            Ok(cost_model) => cost_model,
+            Err(error) => {
+                eprintln!("could not load cost model: {}", error);
+                exit(1);
+            }
+        },
+        None => CostModel::default(),
+    };
+
+    let gtfs_config_option = gtfs_folder_path_option.map(|_| GtfsConfig {
+        service_date: matches
+            .value_of("service_date")
+            .unwrap()
+            .parse()
+            .expect("service_date has to be a date formatted as YYYYMMDD"),
+        default_capacity: matches
+            .value_of("default_capacity")
+            .unwrap()
+            .parse()
+            .expect("default_capacity has to be a positive integer"),
+        default_transfer_time: matches
+            .value_of("default_transfer_time")
+            .unwrap()
+            .parse()
+            .expect("default_transfer_time has to be a positive integer"),
+        capacities_filepath: matches.value_of("capacities_filepath").map(String::from),
+    });
+
+
+
+
+    // EXPLANATION OF input_folder_path:
+    // if <input_folder_path> (or <gtfs_folder_path>) specified, the program will try to read all CSVs from there + create a new model + search paths for all groups + create a snapshot of current model and continue with best path selection
+    // if <input_folder_path> is NOT specified, the proram will try to load a snapshot from a previous run and directly continue with best path selection
+
+    let (mut model, groups) = if let Some(input_folder_path) = input_folder_path_option.or(gtfs_folder_path_option) {
+        // load model and groups from CSV files (or GTFS feed)
+
+        let model_result = match &gtfs_config_option {
+            Some(gtfs_config) => {
+                println!(
+                    "creating new model with_gtfs_feed({}) for service date {} and groups",
+                    input_folder_path,
+                    gtfs_config.service_date
+                );
+                Model::with_gtfs_feed(input_folder_path, gtfs_config)
+            },
+            None => {
+                println!(
+                    "creating new model with_stations_trips_and_footpaths({}) and groups",
+                    input_folder_path
+                );
+                Model::with_stations_trips_and_footpaths(input_folder_path)
+            }
+        };
+
+        let model = match model_result {
+            Ok(model) => model,
+            Err(error) => {
+                eprintln!("could not create model: {}", error);
+                exit(1);
+            }
+        };
+
+        let groups_filepath = match groups_filepath_option {
+            Some(groups_filepath) => groups_filepath.to_string(),
+            None => format!("{}/groups.csv", input_folder_path),
+        };
+
+        let groups = match model
             .find_paths_for_groups(
-                &format!("{}/groups.csv", input_folder_path),
-                &search_budgets,
-                n_search_threads,
-                min_paths
-        );
+                &groups_filepath,
+                &cost_model,
+                &path_search,
+                n_search_threads
+        ) {
+            Ok(groups) => groups,
+            Err(error) => {
+                eprintln!("could not create groups: {}", error);
+                exit(1);
+            }
+        };
 
         println!("create snapshot of model and groups for next run");
         model.save_to_file();
@@ -146,10 +606,15 @@ fn main() {
     } else {
         // load model and groups from snpashot
 
-        (
-            Model::load_from_file(),
-            Group::load_from_file(),
-        )
+        let model = Model::load_from_file();
+        let mut groups = Group::load_from_file();
+
+        // the snapshot's path costs may have been calculated with another cost model
+        for group in groups.iter_mut() {
+            group.recalculate_paths_cost(&model.graph, &cost_model);
+        }
+
+        (model, groups)
     };
 
     if let Some(export_as_dot_filepath) = export_as_dot_option {
@@ -159,9 +624,29 @@ fn main() {
         Model::save_dot_code_to(&model, export_as_dot_filepath);
     }
 
+    // trace the trips expanded from periodic trips back to their pattern
+    if !model.trip_patterns.is_empty() {
+        model.save_trip_patterns_to_csv(&format!("{}/trip_patterns.csv", output_folder_path));
+    }
+
+
+    // report groups without any path and the reason
+    Group::save_unserved_groups_to_csv(&groups, &format!("{}/unserved_groups.csv", output_folder_path));
 
     let groups_len = groups.len();
-    let groups_with_at_least_one_path: Vec<Group> = groups.into_iter().filter(|g| !g.paths.is_empty()).collect();
+    let groups_without_path: Vec<&Group> = groups.iter().filter(|g| g.paths.is_empty()).collect();
+    let n_groups_without_path = groups_without_path.len();
+    let n_passengers_without_path: u64 = groups_without_path.iter().map(|g| g.passengers).sum();
+
+    // with an unserved_penalty each group gets the virtual "no service" path, so groups without any path are kept
+    let groups_with_at_least_one_path: Vec<Group> = groups
+        .into_iter()
+        .map(|mut g| {
+            g.add_unserved_path(&cost_model);
+            g
+        })
+        .filter(|g| !g.paths.is_empty())
+        .collect();
     
     let avg_paths_per_group = 
     groups_with_at_least_one_path.iter().map(|g| g.paths.len() as u64).sum::<u64>() /
@@ -169,61 +654,215 @@ fn main() {
     
     // at this state we can start with group's paths selection
     println!(
-        "state-space: {} group(s) with an average of {} path(s) each\n{} groups ({}%) without known path", 
+        "state-space: {} group(s) with an average of {} path(s) each\n{} groups ({}%) with {} passengers without known path (see unserved_groups.csv)", 
         groups_with_at_least_one_path.len(), 
         avg_paths_per_group,
-        groups_len - groups_with_at_least_one_path.len(),
-        100 * (groups_len - groups_with_at_least_one_path.len()) / groups_len
+        n_groups_without_path,
+        100 * n_groups_without_path / groups_len,
+        n_passengers_without_path
     );
     
-    // // 1. Optimize with simulated annealing
-    let selection_state = optimization::simulated_annealing::simulated_annealing(
-        &mut model.graph, 
-        &groups_with_at_least_one_path, 
-        &format!("{}/simulated_annealing", output_folder_path),
-        n_optimization_iterations_sa1
-    );
+    if export_lp_option.is_some() || export_mps_option.is_some() {
+        // if set, export the path selection problem as MIP for external solvers
+
+        let program = optimization::mip_export::PathSelectionProgram::from_groups(
+            &model.graph,
+            &cost_model,
+            &groups_with_at_least_one_path,
+            n_overflow_segments
+        );
 
-    // save results
-    selection_state.save_strained_trip_edges_to_csv(&mut model.graph, &format!("{}/simulated_annealing_edges.csv", output_folder_path));
-    selection_state.save_groups_to_csv(&mut model.graph, &format!("{}/simulated_annealing_groups.csv", output_folder_path));
+        if let Some(export_lp_filepath) = export_lp_option {
+            println!("exporting path selection problem as LP to '{}'", export_lp_filepath);
+            program.save_lp_to_file(export_lp_filepath);
+        }
+
+        if let Some(export_mps_filepath) = export_mps_option {
+            println!("exporting path selection problem as MPS to '{}'", export_mps_filepath);
+            program.save_mps_to_file(export_mps_filepath);
+        }
+    }
+
+    // // 1. Optimize with simulated annealing (multiple chains in parallel if n_chains > 1)
+    let selection_state = if n_chains > 1 {
+        let selection_state = optimization::parallel_simulated_annealing::parallel_simulated_annealing(
+            &model.graph,
+            &cost_model,
+            &groups_with_at_least_one_path,
+            &format!("{}/parallel_simulated_annealing", output_folder_path),
+            &annealing_parameters_sa1,
+            n_chains,
+            exchange_interval,
+            seed
+        );
+
+        // save results
+        selection_state.save_strained_trip_edges_to_csv(&model.graph, &format!("{}/parallel_simulated_annealing_edges.csv", output_folder_path));
+        selection_state.save_groups_to_csv(&model.graph, &format!("{}/parallel_simulated_annealing_groups.csv", output_folder_path));
+
+        selection_state
+    } else {
+        let selection_state = optimization::simulated_annealing::simulated_annealing(
+            &model.graph, 
+            &cost_model,
+            &groups_with_at_least_one_path, 
+            &format!("{}/simulated_annealing", output_folder_path),
+            &annealing_parameters_sa1,
+            seed
+        );
+
+        // save results
+        selection_state.save_strained_trip_edges_to_csv(&model.graph, &format!("{}/simulated_annealing_edges.csv", output_folder_path));
+        selection_state.save_groups_to_csv(&model.graph, &format!("{}/simulated_annealing_groups.csv", output_folder_path));
+
+        selection_state
+    };
 
     // // 2. Optimize with simulated annealing on path
     let mut groups_cloned = groups_with_at_least_one_path.clone();
     let selection_state = optimization::simulated_annealing_on_path::simulated_annealing(
-        &mut model.graph, 
+        &model.graph, 
+        &model.inaccessible_stations,
         &mut groups_cloned, 
         selection_state, 
         &format!("{}/simulated_annealing_on_path", output_folder_path), 
-        n_optimization_iterations_sa2
+        &annealing_parameters_sa2,
+        seed
     );
 
     // save results
-    selection_state.save_strained_trip_edges_to_csv(&mut model.graph, &format!("{}/simulated_annealing_on_path_edges.csv", output_folder_path));
-    selection_state.save_groups_to_csv(&mut model.graph, &format!("{}/simulated_annealing_on_path_groups.csv", output_folder_path));
+    selection_state.save_strained_trip_edges_to_csv(&model.graph, &format!("{}/simulated_annealing_on_path_edges.csv", output_folder_path));
+    selection_state.save_groups_to_csv(&model.graph, &format!("{}/simulated_annealing_on_path_groups.csv", output_folder_path));
+
+    // 3. Compute lower bound (and new routes) with lagrangian relaxation
+    if n_iterations_lagrangian > 0 {
+        let mut groups_cloned = groups_with_at_least_one_path.clone();
+        let selection_state = optimization::lagrangian_relaxation::lagrangian_relaxation(
+            &model.graph,
+            &model.inaccessible_stations,
+            &cost_model,
+            &mut groups_cloned,
+            &format!("{}/lagrangian_relaxation", output_folder_path),
+            n_iterations_lagrangian
+        );
+
+        // save results
+        selection_state.save_strained_trip_edges_to_csv(&model.graph, &format!("{}/lagrangian_relaxation_edges.csv", output_folder_path));
+        selection_state.save_groups_to_csv(&model.graph, &format!("{}/lagrangian_relaxation_groups.csv", output_folder_path));
+    }
+
+    // 4. Optimize with tabu search
+    if tabu_parameters.n_iterations > 0 {
+        let selection_state = optimization::tabu_search::tabu_search(
+            &model.graph,
+            &cost_model,
+            &groups_with_at_least_one_path,
+            &format!("{}/tabu_search", output_folder_path),
+            &tabu_parameters
+        );
+
+        // save results
+        selection_state.save_strained_trip_edges_to_csv(&model.graph, &format!("{}/tabu_search_edges.csv", output_folder_path));
+        selection_state.save_groups_to_csv(&model.graph, &format!("{}/tabu_search_groups.csv", output_folder_path));
+    }
+
+    // 5. Optimize (and find new routes) with adaptive large neighborhood search
+    if lns_parameters.n_iterations > 0 {
+        let mut groups_cloned = groups_with_at_least_one_path.clone();
+        let selection_state = optimization::large_neighborhood_search::large_neighborhood_search(
+            &model.graph,
+            &model.inaccessible_stations,
+            &cost_model,
+            &mut groups_cloned,
+            &format!("{}/large_neighborhood_search", output_folder_path),
+            &lns_parameters,
+            seed
+        );
+
+        // save results
+        selection_state.save_strained_trip_edges_to_csv(&model.graph, &format!("{}/large_neighborhood_search_edges.csv", output_folder_path));
+        selection_state.save_groups_to_csv(&model.graph, &format!("{}/large_neighborhood_search_groups.csv", output_folder_path));
+    }
+
+    // 6. Distribute groups over several routes with hard capacities
+    if split_groups {
+        let selection_state = optimization::split_assignment::split_assignment(
+            &model.graph,
+            &cost_model,
+            &groups_with_at_least_one_path,
+            &format!("{}/split_assignment", output_folder_path),
+            greedy_order
+        );
+
+        // save results
+        selection_state.save_strained_trip_edges_to_csv(&model.graph, &format!("{}/split_assignment_edges.csv", output_folder_path));
+        selection_state.save_groups_to_csv(&model.graph, &format!("{}/split_assignment_groups.csv", output_folder_path));
+    }
+
+    // selection of simulated annealing on path, re-optimized after disruptions or realtime updates
+    let selected_groups_path_index = selection_state.groups_path_index.clone();
+
+    // 7. Re-optimize after disruptions, starting with the selection of simulated annealing on path
+    if let Some(scenario_filepath) = scenario_option {
+        if let Err(error) = model.reoptimize_scenario(
+            scenario_filepath,
+            &groups_cloned,
+            selected_groups_path_index.clone(),
+            &cost_model,
+            &path_search,
+            &annealing_parameters_sa1,
+            &format!("{}/disruption", output_folder_path),
+            seed
+        ) {
+            eprintln!("could not read scenario: {}", error);
+            exit(1);
+        }
+    }
+
+
+    // 8. Replay realtime updates, re-optimizing after each update starting with the previous selection
+    if let Some(realtime_folder_path) = realtime_option {
+        if let Err(error) = model.replay_feed(
+            realtime_folder_path,
+            &mut groups_cloned,
+            selected_groups_path_index,
+            &cost_model,
+            &path_search,
+            &annealing_parameters_sa1,
+            &format!("{}/realtime_replay", output_folder_path),
+            seed
+        ) {
+            eprintln!("could not replay realtime updates: {}", error);
+            exit(1);
+        }
+    }
 
 
-    // 3. Optimize with randomized best
+    // 9. Optimize with randomized best
     // let selection_state = optimization::randomized_best::randomized_best(
-    //     &mut model.graph, 
+    //     &model.graph, 
+    //     &cost_model,
     //     &groups_with_at_least_one_path, 
     //     10000, 
     //     &format!("{}/randomized_best", output_folder_path), 
+    //     seed
     // );
-    // selection_state.save_strained_trip_edges_to_csv(&mut model.graph, &format!("{}/randomized_best_edges.csv", output_folder_path));
-    // selection_state.save_groups_to_csv(&mut model.graph, &format!("{}/randomized_best_groups.csv", output_folder_path);
+    // selection_state.save_strained_trip_edges_to_csv(&model.graph, &format!("{}/randomized_best_edges.csv", output_folder_path));
+    // selection_state.save_groups_to_csv(&model.graph, &format!("{}/randomized_best_groups.csv", output_folder_path);
 
 
-    // 4. Optimize with randomized_hillclimb
+    // 10. Optimize with randomized_hillclimb
     // let selection_state = optimization::randomized_hillclimb::randomized_hillclimb(
-    //     &mut model.graph, 
+    //     &model.graph, 
+    //     &cost_model,
     //     &groups_with_at_least_one_path, 
     //     10,  
     //     10000, 
-    //     &format!("{}/randomized_hillclimb", output_folder_path)
+    //     &format!("{}/randomized_hillclimb", output_folder_path),
+    //     seed
     // );
-    // selection_state.save_strained_trip_edges_to_csv(&mut model.graph, &format!("{}/randomized_hillclimb_edges.csv", output_folder_path));
-    // selection_state.save_groups_to_csv(&mut model.graph, &format!("{}/randomized_hillclimb_groups.csv", output_folder_path));
+    // selection_state.save_strained_trip_edges_to_csv(&model.graph, &format!("{}/randomized_hillclimb_edges.csv", output_folder_path));
+    // selection_state.save_groups_to_csv(&model.graph, &format!("{}/randomized_hillclimb_groups.csv", output_folder_path));
 
     println!("done with main() -> terminating")
 }
@@ -242,7 +881,7 @@ fn main() {
 
 //     model.create_subgraph_from_edges(edges, &format!("graphs/groups/group_{}.dot", group.id));
 // }
-//optimization::analyze_neighborhood(&mut model.graph, &groups_with_at_least_one_path, "eval/benchmark_neighbors/", 10);
+//optimization::analyze_neighborhood(&mut model.graph, &cost_model, &groups_with_at_least_one_path, "eval/benchmark_neighbors/", 10);
 
 
 
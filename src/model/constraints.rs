@@ -0,0 +1,148 @@
+use std::collections::HashSet;
+
+use petgraph::graph::{DiGraph, EdgeIndex};
+use serde::{Deserialize, Serialize};
+
+use super::{TimetableEdge, TimetableNode};
+use crate::{csv_reader::{self, Record}, error::Error, time};
+
+/// hard constraints of a group on its paths, paths violating them are never assigned to the group
+///
+/// all fields are optional columns of groups.csv, a group without them is unconstrained
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GroupConstraints {
+    pub max_transfers: Option<u64>,    // maximum number of transfers (see `Path::get_transfers()`)
+    pub max_walking: Option<u64>,      // maximum summed duration of footpaths (minutes)
+    pub avoid_stations: HashSet<u64>,  // stations the path must not stop at (separated by `;`)
+    pub step_free: bool,               // boards and alights only at stations with step-free access (see `Model::inaccessible_stations`)
+    pub latest_arrival: Option<u64>,   // latest acceptable arrival at the destination
+}
+
+impl GroupConstraints {
+
+    /// returns the constraints of a group from its record read from filepath
+    ///
+    /// latest_arrival is parsed like the arrival of the group (a time of day before departure_time lies on the next day),
+    /// avoid_stations must neither contain the start nor the destination of the group
+    pub fn from_map(
+        group_map: &Record,
+        filepath: &str,
+        index: usize,
+        reference_date: Option<i64>,
+        departure_time: u64,
+        start_and_destination: [u64; 2],
+    ) -> Result<Self, Error> {
+        let invalid_value = |column: &str, value: &str| Error::InvalidValue {
+            file: filepath.to_string(),
+            row: csv_reader::row(index),
+            column: column.to_string(),
+            value: value.to_string(),
+        };
+
+        let mut avoid_stations = HashSet::new();
+
+        if let Some(value) = csv_reader::optional_field(group_map, "avoid_stations") {
+            for station in value.split(';').map(|station| station.trim()).filter(|station| !station.is_empty()) {
+                match station.parse() {
+                    Ok(station_id) if !start_and_destination.contains(&station_id) => avoid_stations.insert(station_id),
+                    _ => return Err(invalid_value("avoid_stations", value)),
+                };
+            }
+        }
+
+        let latest_arrival = match csv_reader::optional_field(group_map, "latest_arrival") {
+            Some(value) if time::is_time_of_day(value) => {
+                Some(time::roll_over(csv_reader::parse_time_field(group_map, filepath, index, "latest_arrival", reference_date)?, departure_time))
+            }
+            Some(_) => Some(csv_reader::parse_time_field(group_map, filepath, index, "latest_arrival", reference_date)?),
+            None => None,
+        };
+
+        Ok(Self {
+            max_transfers: csv_reader::parse_optional_field(group_map, filepath, index, "max_transfers")?,
+            max_walking: csv_reader::parse_optional_field(group_map, filepath, index, "max_walking")?,
+            avoid_stations,
+            step_free: csv_reader::parse_optional_bool_field(group_map, filepath, index, "step_free")?.unwrap_or(false),
+            latest_arrival,
+        })
+    }
+
+    /// returns true if no constraint is set
+    pub fn is_unconstrained(&self) -> bool {
+        self.max_transfers.is_none() && self.max_walking.is_none() && self.avoid_stations.is_empty() && !self.step_free && self.latest_arrival.is_none()
+    }
+
+    /// returns true if the group may board or alight at station (inaccessible_stations are the stations without step-free access)
+    fn is_accessible(&self, inaccessible_stations: &HashSet<u64>, station_id: u64) -> bool {
+        !self.step_free || !inaccessible_stations.contains(&station_id)
+    }
+
+    /// returns true if next_edge does not violate the constraints after a path with `boardings` Board edges and
+    /// `walking_time` minutes of walking (starts_in_trip if the path starts at an arrival, so its first boarding is a transfer)
+    ///
+    /// path searches carry boardings and walking_time along their paths, so each edge is checked in constant time
+    pub fn admits_edge(
+        &self,
+        graph: &DiGraph<TimetableNode, TimetableEdge>,
+        inaccessible_stations: &HashSet<u64>,
+        next_edge: EdgeIndex,
+        starts_in_trip: bool,
+        boardings: u64,
+        walking_time: u64,
+    ) -> bool {
+        let (source, target) = graph.edge_endpoints(next_edge).unwrap();
+
+        // times never decrease along a path
+        if self.avoid_stations.contains(&graph[target].station_id()) || self.latest_arrival.is_some_and(|latest_arrival| graph[target].time() > latest_arrival) {
+            return false;
+        }
+
+        match graph[next_edge] {
+            TimetableEdge::Board => {
+                let transfers = if starts_in_trip { boardings + 1 } else { boardings };
+
+                self.is_accessible(inaccessible_stations, graph[target].station_id()) && self.max_transfers.is_none_or(|max_transfers| transfers <= max_transfers)
+            }
+            TimetableEdge::Alight { duration: _ } => self.is_accessible(inaccessible_stations, graph[source].station_id()),
+            TimetableEdge::Walk { duration } => {
+                self.is_accessible(inaccessible_stations, graph[source].station_id()) && self.max_walking.is_none_or(|max_walking| walking_time + duration <= max_walking)
+            }
+            _ => true,
+        }
+    }
+
+    /// returns true if the path of edges satisfies all constraints (the unserved path without edges always does)
+    pub fn admits_path(&self, graph: &DiGraph<TimetableNode, TimetableEdge>, inaccessible_stations: &HashSet<u64>, edges: &[EdgeIndex]) -> bool {
+        if self.is_unconstrained() {
+            return true;
+        }
+
+        // a path ending in a trip alights at the destination
+        let alights_at_destination = edges.last().is_none_or(|edge| {
+            let destination = graph.edge_endpoints(*edge).unwrap().1;
+            !graph[destination].is_arrival() || self.is_accessible(inaccessible_stations, graph[destination].station_id())
+        });
+
+        if !alights_at_destination {
+            return false;
+        }
+
+        let starts_in_trip = edges.first().is_some_and(|edge| graph[graph.edge_endpoints(*edge).unwrap().0].is_arrival());
+        let mut boardings = 0;
+        let mut walking_time = 0;
+
+        for edge in edges.iter() {
+            if !self.admits_edge(graph, inaccessible_stations, *edge, starts_in_trip, boardings, walking_time) {
+                return false;
+            }
+
+            match graph[*edge] {
+                TimetableEdge::Board => boardings += 1,
+                TimetableEdge::Walk { duration } => walking_time += duration,
+                _ => {}
+            }
+        }
+
+        true
+    }
+}
@@ -0,0 +1,252 @@
+use std::{fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+
+/// weights of all cost components used by path search, paths and selection states
+///
+/// can be loaded from a TOML or JSON file, missing fields keep their default value
+///
+/// the defaults reproduce the original hard-coded costs: trips cost `5 / (duration + 1)`,
+/// alighting and walking cost 6, waiting at a station costs its duration, delay is added as is
+/// and overcrowded trips cost the squared number of passengers over capacity (plus 1 per standing passenger if trips have fewer seats)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CostModel {
+    pub trip: EdgeKindWeight,
+    pub trip_inverse_duration: u64, // adds trip_inverse_duration / (duration + 1) to each Trip edge (prefers long rides)
+    pub wait_in_train: EdgeKindWeight,
+    pub board: EdgeKindWeight,
+    pub alight: EdgeKindWeight,
+    pub wait_at_station: EdgeKindWeight,
+    pub walk: EdgeKindWeight,
+
+    pub transfer_penalty: u64, // added to each Alight and Walk edge (leaving a train before the destination)
+
+    pub delay: DelayWeight,
+
+    // cost per passenger without a seat on a Trip edge (up to its capacity, see seats in trips.csv),
+    // must not exceed overload_penalty.cost(1) (lower it as well when lowering the default overload_penalty)
+    pub standing_penalty: u64,
+    pub overload_penalty: OverloadPenalty, // cost of the passengers over the capacity of a Trip edge (in addition to standing_penalty)
+
+    pub unserved_penalty: Option<u64>, // cost per passenger of not transporting a group (virtual "no service" path), disabled if not set
+}
+
+/// cost of an edge kind: fixed + per_minute * duration
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EdgeKindWeight {
+    pub fixed: u64,
+    pub per_minute: u64,
+}
+
+/// weight of each minute of delay at the destination, early arrivals have a negative delay
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DelayWeight {
+    pub early: i64, // weight per minute arriving before the planned arrival (cost = early * delay, delay < 0)
+    pub late: i64,  // weight per minute arriving after the planned arrival (cost = late * delay, delay > 0)
+}
+
+/// penalty of a Trip edge for the passengers exceeding its capacity (overflow)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+pub enum OverloadPenalty {
+    Linear { // factor * overflow
+        factor: u64,
+    },
+
+    Quadratic { // factor * overflow^2
+        factor: u64,
+    },
+
+    Piecewise { // each segment costs slope per passenger from its start up to the start of the next segment
+        segments: Vec<PenaltySegment>,
+    },
+}
+
+/// segment of a piecewise-linear overload penalty
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PenaltySegment {
+    pub from: u64, // overflow at which this segment starts
+    pub slope: u64, // cost per passenger in this segment
+}
+
+impl Default for CostModel {
+    fn default() -> Self {
+        Self {
+            trip: EdgeKindWeight::default(),
+            trip_inverse_duration: 5,
+            wait_in_train: EdgeKindWeight::default(),
+            board: EdgeKindWeight::default(),
+            alight: EdgeKindWeight::default(),
+            wait_at_station: EdgeKindWeight {
+                fixed: 0,
+                per_minute: 1,
+            },
+            walk: EdgeKindWeight::default(),
+
+            transfer_penalty: 6,
+
+            delay: DelayWeight::default(),
+
+            standing_penalty: 1,
+            overload_penalty: OverloadPenalty::default(),
+
+            unserved_penalty: None,
+        }
+    }
+}
+
+impl Default for DelayWeight {
+    fn default() -> Self {
+        Self { early: 1, late: 1 }
+    }
+}
+
+impl Default for OverloadPenalty {
+    fn default() -> Self {
+        Self::Quadratic { factor: 1 }
+    }
+}
+
+impl CostModel {
+
+    /// loads a cost model from a TOML (`.toml`) or JSON (any other extension) file
+    pub fn from_file(filepath: &str) -> Result<Self, Error> {
+        let content = fs::read_to_string(filepath).map_err(|source| Error::Io {
+            file: filepath.to_string(),
+            source,
+        })?;
+
+        let invalid_config = |message: String| Error::InvalidConfig {
+            file: filepath.to_string(),
+            message,
+        };
+
+        let cost_model: Self = if Path::new(filepath).extension().is_some_and(|extension| extension == "toml") {
+            toml::from_str(&content).map_err(|error| invalid_config(error.to_string()))?
+        } else {
+            serde_json::from_str(&content).map_err(|error| invalid_config(error.to_string()))?
+        };
+
+        if let OverloadPenalty::Piecewise { segments } = &cost_model.overload_penalty {
+            if segments.is_empty() {
+                return Err(invalid_config("piecewise overload_penalty requires at least one segment".to_string()));
+            }
+
+            if segments.windows(2).any(|segment_pair| segment_pair[0].from >= segment_pair[1].from) {
+                return Err(invalid_config("segments of piecewise overload_penalty have to be ordered by strictly increasing 'from'".to_string()));
+            }
+
+            // the MIP export and the lagrangian relaxation rely on a convex penalty
+            if segments.windows(2).any(|segment_pair| segment_pair[0].slope > segment_pair[1].slope) {
+                return Err(invalid_config("slopes of piecewise overload_penalty must not decrease".to_string()));
+            }
+        }
+
+        // standing passengers must not cost more than the first passenger over capacity (convex penalty)
+        let first_overload_cost = cost_model.overload_penalty.cost(1);
+        if cost_model.standing_penalty > first_overload_cost {
+            let default_standing_penalty = Self::default().standing_penalty;
+
+            return Err(invalid_config(format!(
+                "standing_penalty ({}{}) must not exceed the overload_penalty of the first passenger over capacity ({} with {:?}), set standing_penalty to at most {}",
+                cost_model.standing_penalty,
+                if cost_model.standing_penalty == default_standing_penalty { ", the default" } else { "" },
+                first_overload_cost,
+                cost_model.overload_penalty,
+                first_overload_cost
+            )));
+        }
+
+        Ok(cost_model)
+    }
+
+    /// returns the weighted cost of delay (real arrival - planned arrival)
+    #[inline]
+    pub fn delay_cost(&self, delay: i64) -> i64 {
+        if delay < 0 {
+            self.delay.early * delay
+        } else {
+            self.delay.late * delay
+        }
+    }
+}
+
+impl EdgeKindWeight {
+
+    /// returns the cost of an edge with duration
+    #[inline]
+    pub fn cost(&self, duration: u64) -> u64 {
+        self.fixed + self.per_minute * duration
+    }
+}
+
+impl OverloadPenalty {
+
+    /// returns the penalty for overflow passengers over capacity
+    pub fn cost(&self, overflow: u64) -> u64 {
+        match self {
+            Self::Linear { factor } => factor * overflow,
+            Self::Quadratic { factor } => factor * overflow.pow(2),
+            Self::Piecewise { segments } => {
+                let mut cost = 0;
+
+                for (segment_index, segment) in segments.iter().enumerate() {
+                    if overflow <= segment.from {
+                        break;
+                    }
+
+                    let to = match segments.get(segment_index + 1) {
+                        Some(next_segment) => overflow.min(next_segment.from),
+                        None => overflow,
+                    };
+                    cost += segment.slope * (to - segment.from);
+                }
+
+                cost
+            }
+        }
+    }
+
+    /// returns the largest multiplier for which `cost(overflow) - multiplier * overflow` is bounded from below (None if unlimited)
+    pub fn max_multiplier(&self) -> Option<f64> {
+        match self {
+            Self::Linear { factor } => Some(*factor as f64),
+            Self::Quadratic { factor } if *factor == 0 => Some(0.0),
+            Self::Quadratic { factor: _ } => None,
+            Self::Piecewise { segments } => Some(segments.last().unwrap().slope as f64),
+        }
+    }
+
+    /// returns (minimum, argmin) of `cost(overflow) - multiplier * overflow` over all (continuous) overflow >= 0
+    ///
+    /// requires multiplier <= max_multiplier()
+    pub fn lagrangian_minimum(&self, multiplier: f64) -> (f64, f64) {
+        match self {
+            Self::Linear { factor: _ } => (0.0, 0.0),
+            Self::Quadratic { factor } if *factor == 0 => (0.0, 0.0),
+            Self::Quadratic { factor } => {
+                let overflow = multiplier / (2.0 * *factor as f64);
+                (-multiplier * overflow / 2.0, overflow)
+            }
+            Self::Piecewise { segments } => {
+                // piecewise linear -> minimum is at zero or at the start of a segment
+                let mut minimum = (0.0, 0.0);
+
+                for segment in segments.iter() {
+                    let value = self.cost(segment.from) as f64 - multiplier * segment.from as f64;
+                    if value < minimum.0 {
+                        minimum = (value, segment.from as f64);
+                    }
+                }
+
+                minimum
+            }
+        }
+    }
+}
@@ -1,10 +1,11 @@
-use std::collections::HashMap;
-
 use petgraph::graph::{DiGraph, NodeIndex};
+use serde::{Deserialize, Serialize};
 
 use super::{TimetableEdge, TimetableNode};
+use crate::{csv_reader::{self, Record}, error::Error};
 
 /// footpath from a station to another station
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Footpath {
     pub from_station: u64,
     pub to_station: u64,
@@ -13,26 +14,26 @@ pub struct Footpath {
 
 impl Footpath {
 
-    /// returns footpaths from maps
-    pub fn from_maps_to_vec(footpath_maps: &Vec<HashMap<String, String>>) -> Vec<Self> {
+    /// returns footpaths from maps read from filepath
+    pub fn from_maps_to_vec(footpath_maps: &[Record], filepath: &str) -> Result<Vec<Self>, Error> {
         println!("parsing {} footpath(s)", footpath_maps.len());
 
         let mut footpaths_vec = Vec::with_capacity(footpath_maps.len());
 
-        for footpath_map in footpath_maps.iter() {
+        for (index, footpath_map) in footpath_maps.iter().enumerate() {
             footpaths_vec.push(Self {
-                from_station: footpath_map.get("from_station").unwrap().parse().unwrap(),
-                to_station: footpath_map.get("to_station").unwrap().parse().unwrap(),
-                duration: footpath_map.get("duration").unwrap().parse().unwrap(),
+                from_station: csv_reader::parse_field(footpath_map, filepath, index, "from_station")?,
+                to_station: csv_reader::parse_field(footpath_map, filepath, index, "to_station")?,
+                duration: csv_reader::parse_field(footpath_map, filepath, index, "duration")?,
             });
         }
 
-        footpaths_vec
+        Ok(footpaths_vec)
     }
 
     /// connects all arrivals of a station with the earliest-reachable transfers at the footpath's destination station
     pub fn connect(
-        self,
+        &self,
         graph: &mut DiGraph<TimetableNode, TimetableEdge>,
         from_station_arrivals: &Vec<NodeIndex>,
         to_station_transfers: &Vec<NodeIndex>,
@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use super::cost_model::CostModel;
+
 
 /// Node Type of the DiGraph
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -67,6 +69,26 @@ impl TimetableNode {
         }
     }
 
+    /// delays the time of node by minutes
+    #[inline]
+    pub fn delay(&mut self, minutes: u64) {
+        match self {
+            Self::Departure {trip_id: _, time, station_id: _, station_name: _} => *time += minutes,
+            Self::Arrival {trip_id: _, time, station_id: _, station_name: _} => *time += minutes,
+            Self::Transfer {time, station_id: _, station_name: _} => *time += minutes,
+        }
+    }
+
+    /// sets the time of node
+    #[inline]
+    pub fn set_time(&mut self, new_time: u64) {
+        match self {
+            Self::Departure {trip_id: _, time, station_id: _, station_name: _} => *time = new_time,
+            Self::Arrival {trip_id: _, time, station_id: _, station_name: _} => *time = new_time,
+            Self::Transfer {time, station_id: _, station_name: _} => *time = new_time,
+        }
+    }
+
     /// returns true if node is Arrival and its station id equals target_station_id
     #[inline]
     pub fn is_arrival_at_station(&self, target_station_id: u64) -> bool {
@@ -117,12 +139,13 @@ impl TimetableNode {
 
 
 /// Edge Type of the DiGraph
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum TimetableEdge {
     Trip { // edge between departure and arrival
         duration: u64,
-        capacity: u64, // number of passangers that should not be exceeded (but can)
-        utilization: u64, // number of passengers on this ride
+        capacity: u64, // number of passangers that should not be exceeded (but can), crush load including standing passengers
+        seats: u64, // number of seated passengers (up to capacity), all others stand
+        first_class_seats: u64, // seats reserved for first class passengers (part of seats)
     },
 
     WaitInTrain { // edge between arrival and departure in the same train (stay in the train)
@@ -149,33 +172,39 @@ impl TimetableEdge {
 
     /// maps edge to some virtual cost for improved DFS (aka. effort/expense to "take" the edge)
     #[inline]
-    pub fn travel_cost(&self) -> u64 {
+    pub fn travel_cost(&self, cost_model: &CostModel) -> u64 {
         match self {
-            Self::Trip {duration, capacity: _, utilization: _} => 5 / (*duration + 1),
-            Self::WaitInTrain {duration: _} => 0,
-            Self::Alight {duration: _} => 6,
-            Self::WaitAtStation {duration} => *duration,
-            Self::Walk {duration: _} => 6,
-            Self::Board => 0,
+            Self::Trip {duration, capacity: _, seats: _, first_class_seats: _} => cost_model.trip.cost(*duration) + cost_model.trip_inverse_duration / (*duration + 1),
+            Self::WaitInTrain {duration} => cost_model.wait_in_train.cost(*duration),
+            Self::Alight {duration} => cost_model.alight.cost(*duration) + cost_model.transfer_penalty,
+            Self::WaitAtStation {duration} => cost_model.wait_at_station.cost(*duration),
+            Self::Walk {duration} => cost_model.walk.cost(*duration) + cost_model.transfer_penalty,
+            Self::Board => cost_model.board.cost(0),
         }
     }
 
-    /// calculates the utilization cost for edge
+    /// calculates the utilization cost for edge if it was utilized by `utilization` passengers
+    ///
+    /// all passengers may take any seat (first class seats are not reserved), which never costs more than with classes
     #[inline]
-    pub fn utilization_cost(&self) -> u64 {
-        match self {
+    pub fn utilization_cost_with(&self, cost_model: &CostModel, utilization: u64) -> u64 {
+        self.utilization_cost_by_class(cost_model, utilization, utilization)
+    }
 
-            // penalize utilization over capacity
-            Self::Trip {duration: _, capacity, utilization} => {
+    /// calculates the utilization cost for edge if it was utilized by `utilization` passengers,
+    /// `first_class_utilization` of them in first class
+    ///
+    /// the cost rises in stages: free while all passengers are seated, standing_penalty per standing passenger up to the capacity
+    /// and additionally the overload_penalty for the passengers over capacity
+    #[inline]
+    pub fn utilization_cost_by_class(&self, cost_model: &CostModel, utilization: u64, first_class_utilization: u64) -> u64 {
+        match self {
 
-                if utilization < capacity {
-                    0
-                } else {
-                    // calculate penalty as quadratic diff
+            // penalize standing passengers and utilization over capacity
+            Self::Trip {duration: _, capacity, seats: _, first_class_seats: _} => {
+                let standing = utilization.min(*capacity).saturating_sub(self.seated(utilization, first_class_utilization));
 
-                    let diff = *utilization - *capacity;
-                    diff.pow(2)
-                }
+                cost_model.standing_penalty * standing + cost_model.overload_penalty.cost(utilization.saturating_sub(*capacity))
             },
 
             // for every other edge type return zero
@@ -183,6 +212,20 @@ impl TimetableEdge {
         }
     }
 
+    /// returns the number of seated passengers if edge was utilized by `utilization` passengers, `first_class_utilization` of them
+    /// in first class (first class passengers take first class seats first, second class passengers never take first class seats)
+    #[inline]
+    pub fn seated(&self, utilization: u64, first_class_utilization: u64) -> u64 {
+        match self {
+            Self::Trip {duration: _, capacity: _, seats: _, first_class_seats: _} => {
+                let first_class_seated = first_class_utilization.min(utilization).min(self.first_class_seats());
+
+                first_class_seated + (utilization - first_class_seated).min(self.seats() - self.first_class_seats())
+            },
+            _ => utilization,
+        }
+    }
+
 
     /// returns true if edge is Trip
     #[inline]
@@ -191,7 +234,8 @@ impl TimetableEdge {
             Self::Trip {
                 duration: _, 
                 capacity: _, 
-                utilization: _
+                seats: _, 
+                first_class_seats: _
             } => true,
             _ => false,
         }
@@ -254,7 +298,7 @@ impl TimetableEdge {
     #[inline]
     pub fn duration(&self) -> u64 {
         match self {
-            Self::Trip{duration, capacity: _, utilization: _} => *duration,
+            Self::Trip{duration, capacity: _, seats: _, first_class_seats: _} => *duration,
             Self::WaitInTrain{duration} => *duration,
             Self::Alight{duration} => *duration,
             Self::WaitAtStation{duration} => *duration,
@@ -267,35 +311,26 @@ impl TimetableEdge {
     #[inline]
     pub fn capacity(&self) -> u64 {
         match self {
-            Self::Trip{duration: _, capacity, utilization: _} => *capacity,
+            Self::Trip{duration: _, capacity, seats: _, first_class_seats: _} => *capacity,
             _ => std::u64::MAX, // all other edge types are not limited in terms of capacity
         }
     }
 
-    /// increases utilization of self if self is Trip
+    /// returns the seats of self (at most its capacity), defaults to MAX
     #[inline]
-    pub fn increase_utilization(&mut self, addend: u64) {
+    pub fn seats(&self) -> u64 {
         match self {
-            Self::Trip{duration: _, capacity: _, utilization} => *utilization += addend,
-            _ => {} // no need to track utilization on other edges, as they have unlimited capacity
+            Self::Trip{duration: _, capacity, seats, first_class_seats: _} => (*seats).min(*capacity),
+            _ => u64::MAX,
         }
     }
 
-    /// decreases utilization of self if self is Trip
+    /// returns the first class seats of self (at most its seats), defaults to 0
     #[inline]
-    pub fn decrease_utilization(&mut self, subtrahend: u64) {
+    pub fn first_class_seats(&self) -> u64 {
         match self {
-            Self::Trip{duration: _, capacity: _, utilization} => *utilization -= subtrahend,
-            _ => {} // no need to track utilization on other edges, as they have unlimited capacity
-        }
-    }
-
-    /// returns utilization of self if self is Trip, defaults to 0
-    #[inline]
-    pub fn utilization(&self) -> u64 {
-        match self {
-            Self::Trip{duration: _, capacity: _, utilization} => *utilization,
-            _ => 0 // other edges always return 0 utilization as they have unlimited capacity
+            Self::Trip{duration: _, capacity: _, seats: _, first_class_seats} => (*first_class_seats).min(self.seats()),
+            _ => 0,
         }
     }
 
@@ -303,7 +338,7 @@ impl TimetableEdge {
     #[inline]
     pub fn kind_as_str(&self) -> &str {
         match self {
-            Self::Trip {duration: _, capacity: _, utilization: _}  => "Trip",
+            Self::Trip {duration: _, capacity: _, seats: _, first_class_seats: _}  => "Trip",
             Self::WaitInTrain {duration: _} => "WaitInTrain",
             Self::Board => "Board",
             Self::Alight {duration: _} => "Alight",
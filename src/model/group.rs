@@ -1,11 +1,19 @@
-use petgraph::graph::NodeIndex;
+use petgraph::graph::{DiGraph, EdgeIndex, NodeIndex};
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, fs::File, io::{BufReader, BufWriter}, process::exit, time::Instant};
+use std::{collections::HashSet, fmt, fs::File, io::{BufReader, BufWriter, Write}, process::exit, time::Instant};
 
 use colored::Colorize;
 
+use crate::{csv_reader::{self, Record}, error::Error, time};
+
 use super::{
-    path::{self, Path},
+    constraints::GroupConstraints,
+    cost_model::CostModel,
+    graph_weight::{TimetableEdge, TimetableNode},
+    k_shortest_paths,
+    pareto_paths,
+    path::{self, Path, PathSearch},
+    update::ModelChanges,
     Model,
 };
 
@@ -25,42 +33,106 @@ pub struct Group {
     // Two possibilities:
     // if value is empty, the group is at start station
     // if value is not empty, the trip id determines the trip in which the group is located
+    // (the group starts at the arrival of the trip at start station closest to departure_time, which moves with delays)
     pub in_trip: Option<u64>,
 
+    pub first_class: bool, // travels in first class (optional class column: 1 or 2, second class if empty)
+
+    pub constraints: GroupConstraints, // hard constraints on the paths of the group (optional columns)
+
     pub paths: Vec<Path>, // possible paths for this group
+
+    pub unserved_reason: Option<UnservedReason>, // set by search_paths() if no path was found
+}
+
+/// reason why no path was found for a group
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum UnservedReason {
+    NoStartTransfer,                // no departure after departure_time at the start station (or no arrival of in_trip)
+    DestinationWithoutArrivals,     // the destination station is never reached by any trip
+    ArrivalBeforeDeparture,         // arrival_time is before departure_time
+    DestinationUnreachableInBudget, // the path search did not reach the destination within its budget
+}
+
+impl fmt::Display for UnservedReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Self::NoStartTransfer => "no_start_transfer",
+            Self::DestinationWithoutArrivals => "destination_without_arrivals",
+            Self::ArrivalBeforeDeparture => "arrival_before_departure",
+            Self::DestinationUnreachableInBudget => "destination_unreachable_in_budget",
+        };
+        write!(f, "{}", name)
+    }
 }
 
 impl Group {
 
-    /// returns groups from maps
-    pub fn from_maps_to_vec(group_maps: &Vec<HashMap<String, String>>) -> Vec<Self> {
+    /// returns groups from maps read from filepath
+    ///
+    /// times are parsed with `time::parse_time()` relative to reference_date, an arrival given as time of day
+    /// before the departure lies on the next day
+    pub fn from_maps_to_vec(group_maps: &[Record], filepath: &str, reference_date: Option<i64>) -> Result<Vec<Self>, Error> {
         println!("parsing {} group(s)", group_maps.len());
 
         let mut groups = Vec::with_capacity(group_maps.len());
+        let mut ids = HashSet::with_capacity(group_maps.len());
 
-        for group_map in group_maps.iter() {
-            let id = group_map.get("id").unwrap().parse().unwrap();
+        for (index, group_map) in group_maps.iter().enumerate() {
+            let id = csv_reader::parse_field(group_map, filepath, index, "id")?;
+
+            if !ids.insert(id) {
+                return Err(Error::DuplicateId {
+                    file: filepath.to_string(),
+                    row: csv_reader::row(index),
+                    id,
+                });
+            }
 
-            let in_trip_value = group_map.get("in_trip").unwrap();
-            let in_trip: Option<u64> = if in_trip_value.is_empty() {
+            let in_trip: Option<u64> = if csv_reader::field(group_map, filepath, index, "in_trip")?.is_empty() {
                 None
             } else {
-                Some(in_trip_value.parse().unwrap())
+                Some(csv_reader::parse_field(group_map, filepath, index, "in_trip")?)
             };
 
+            let first_class = match csv_reader::parse_optional_field::<u8>(group_map, filepath, index, "class")? {
+                None | Some(2) => false,
+                Some(1) => true,
+                Some(class) => return Err(Error::InvalidValue {
+                    file: filepath.to_string(),
+                    row: csv_reader::row(index),
+                    column: "class".to_string(),
+                    value: class.to_string(),
+                }),
+            };
+
+            let departure_time = csv_reader::parse_time_field(group_map, filepath, index, "departure", reference_date)?;
+            let mut arrival_time = csv_reader::parse_time_field(group_map, filepath, index, "arrival", reference_date)?;
+
+            if time::is_time_of_day(csv_reader::field(group_map, filepath, index, "arrival")?) {
+                arrival_time = time::roll_over(arrival_time, departure_time);
+            }
+
+            let start_station_id = csv_reader::parse_field(group_map, filepath, index, "start")?;
+            let destination_station_id = csv_reader::parse_field(group_map, filepath, index, "destination")?;
+            let constraints = GroupConstraints::from_map(group_map, filepath, index, reference_date, departure_time, [start_station_id, destination_station_id])?;
+
             groups.push(Self {
                 id,
-                start_station_id: group_map.get("start").unwrap().parse().unwrap(),
-                destination_station_id: group_map.get("destination").unwrap().parse().unwrap(),
-                departure_time: group_map.get("departure").unwrap().parse().unwrap(),
-                arrival_time: group_map.get("arrival").unwrap().parse().unwrap(),
-                passengers: group_map.get("passengers").unwrap().parse().unwrap(),
+                start_station_id,
+                destination_station_id,
+                departure_time,
+                arrival_time,
+                passengers: csv_reader::parse_field(group_map, filepath, index, "passengers")?,
                 in_trip,
+                first_class,
+                constraints,
                 paths: Vec::new(),
+                unserved_reason: None,
             });
         }
 
-        groups
+        Ok(groups)
     }
 
     /// saves the groups into a snapshot
@@ -101,12 +173,86 @@ impl Group {
         groups
     }
         
+    /// recalculates the cost of all paths with cost_model (e.g. for paths loaded from a snapshot), keeps the paths sorted
+    pub fn recalculate_paths_cost(&mut self, graph: &DiGraph<TimetableNode, TimetableEdge>, cost_model: &CostModel) {
+        let passengers = self.passengers;
+        let arrival_time = self.arrival_time;
+        let first_class = self.first_class;
+
+        for path in self.paths.iter_mut() {
+            *path = Path::new(graph, cost_model, path.edges.iter().cloned().collect(), passengers, arrival_time).with_first_class(first_class);
+        }
+
+        self.paths.sort_unstable();
+    }
+
+    /// appends the virtual "no service" path (if the cost model has an unserved_penalty),
+    /// so the optimizers may leave the group untransported and groups without any path are kept
+    pub fn add_unserved_path(&mut self, cost_model: &CostModel) {
+        if let Some(unserved_penalty) = cost_model.unserved_penalty {
+            self.paths.push(Path::unserved(unserved_penalty, self.passengers).with_first_class(self.first_class));
+        }
+    }
+
+    /// repairs the edge indices of the paths after updates of the model (see `Model::update_groups()`)
+    ///
+    /// returns false (and keeps the paths unchanged) if any path contains a removed or modified edge
+    pub fn repair_paths(&mut self, changes: &ModelChanges) -> bool {
+        if self.paths.iter().any(|path| path.edges.iter().any(|edge| changes.invalid_edges.contains(edge))) {
+            return false;
+        }
+
+        for path in self.paths.iter_mut() {
+            path.edges = path.edges.iter().map(|edge| *changes.moved_edges.get(edge).unwrap_or(edge)).collect();
+        }
+
+        true
+    }
+
+    /// saves the groups without any path found and the reason to a pipe-separated CSV file
+    pub fn save_unserved_groups_to_csv(groups: &[Group], filepath: &str) {
+        let mut writer = BufWriter::new(
+            File::create(filepath).unwrap_or_else(|_| panic!("Could not create file \"{}\"", filepath)),
+        );
+
+        writeln!(writer, "group_id|passengers|start|departure|destination|arrival|in_trip|reason").unwrap();
+
+        for group in groups.iter() {
+            if let Some(unserved_reason) = group.unserved_reason {
+                writeln!(
+                    writer,
+                    "{}|{}|{}|{}|{}|{}|{}|{}",
+                    group.id,
+                    group.passengers,
+                    group.start_station_id,
+                    time::format_time(group.departure_time),
+                    group.destination_station_id,
+                    time::format_time(group.arrival_time),
+                    group.in_trip.map(|in_trip| in_trip.to_string()).unwrap_or_default(),
+                    unserved_reason
+                )
+                .unwrap();
+            }
+        }
+    }
+
+    /// returns true if path satisfies the constraints of the group (inaccessible_stations see `Model::inaccessible_stations`)
+    pub fn admits_path(&self, graph: &DiGraph<TimetableNode, TimetableEdge>, inaccessible_stations: &HashSet<u64>, edges: &[EdgeIndex]) -> bool {
+        self.constraints.admits_path(graph, inaccessible_stations, edges)
+    }
+
     /// searches for paths in given model with its graph limited by search budgets
-    pub fn search_paths(&mut self, model: &Model, search_budget: &[u64], min_edge_vecs: usize) {
+    ///
+    /// only paths satisfying the constraints of the group are kept (the depth-first searches prune violating paths,
+    /// the paths of the other searches are filtered)
+    pub fn search_paths(&mut self, model: &Model, cost_model: &CostModel, path_search: &PathSearch) {
+        self.paths.clear();
+        self.unserved_reason = None;
+
         // find next start node at station with specified id from this start_time
         // returns the first timely reachable transfer at the station_id
         // returns None if no transfer reachable
-        let start: NodeIndex = match self.in_trip {
+        let start_option: Option<NodeIndex> = match self.in_trip {
             Some(in_trip) => {
                 // in_trip is set -> start at arrival of current trip
 
@@ -114,26 +260,15 @@ impl Group {
 
                 // FIRST: get all arrival nodes of the start station
                 let start_station_arrivals =
-                    model.stations_arrivals.get(&self.start_station_id).unwrap();
-
-                // SECOND: search all arrivals for trip_id == in_trip AND time == start at start station
-                let mut selected_station_arrival = None;
-                for start_station_arrival in start_station_arrivals.iter() {
-                    let arrival = &model.graph[*start_station_arrival];
-
-                    if arrival.trip_id().unwrap() == in_trip
-                        && arrival.time() == self.departure_time
-                    {
-                        selected_station_arrival = Some(*start_station_arrival);
-                        // println!("Found arrival={:?}", arrival);
-                        break;
-                    }
-                }
-
-                selected_station_arrival.expect(&format!(
-                    "Could not find arrival for in_trip={} and departure={}",
-                    in_trip, self.departure_time
-                ))
+                    model.stations_arrivals.get(&self.start_station_id).map(|arrivals| arrivals.as_slice()).unwrap_or(&[]);
+
+                // SECOND: search all arrivals for trip_id == in_trip at start station, closest to departure_time
+                // (delays and realtime updates shift the arrival of in_trip away from departure_time)
+                start_station_arrivals
+                    .iter()
+                    .copied()
+                    .filter(|arrival| model.graph[*arrival].trip_id() == Some(in_trip))
+                    .min_by_key(|arrival| (model.graph[*arrival].time().abs_diff(self.departure_time), model.graph[*arrival].time()))
             }
             None => {
                 // in_trip is not set -> start at station transfer
@@ -154,13 +289,36 @@ impl Group {
                     None => {}
                 }
 
-                selected_station_transfer.expect("Could not find departure at from_station")
+                selected_station_transfer
+            }
+        };
+
+        let start = match start_option {
+            Some(start) => start,
+            None => {
+                // no arrival of in_trip or no departure after departure_time at start station
+                println!(
+                    "could not find start at station {} (in_trip={:?}, departure={}) -> skipping",
+                    self.start_station_id, self.in_trip, self.departure_time
+                );
+                self.unserved_reason = Some(UnservedReason::NoStartTransfer);
+                return;
             }
         };
 
-        let destination_station_name = model.graph
-            [model.stations_arrivals.get(&self.destination_station_id).unwrap()[0]]
-            .station_name();
+        let destination_station_name = match model.stations_arrivals.get(&self.destination_station_id).and_then(|arrivals| arrivals.first()) {
+            Some(arrival) => model.graph[*arrival].station_name(),
+            None => {
+                // destination is never reached by any trip
+                println!(
+                    "{} -> station {} ... destination has no arrivals -> skipping",
+                    model.graph[start].station_name(),
+                    self.destination_station_id
+                );
+                self.unserved_reason = Some(UnservedReason::DestinationWithoutArrivals);
+                return;
+            }
+        };
 
         if self.departure_time > self.arrival_time {
             // invalid time
@@ -170,6 +328,7 @@ impl Group {
                 model.graph[start].station_name(),
                 destination_station_name
             );
+            self.unserved_reason = Some(UnservedReason::ArrivalBeforeDeparture);
             return;
         }
 
@@ -185,16 +344,47 @@ impl Group {
             destination_station_name,
         );
 
-        // use iterative deepening search to find edge paths
-        let edge_sets = path::Path::all_paths_iddfs(
-            &model.graph,
-            start,
-            self.destination_station_id,
-            min_edge_vecs,
-
-            2 * travel_time + 120,
-            search_budget,
-        );
+        let edge_sets = match path_search {
+            PathSearch::Iddfs { search_budgets, min_paths } => {
+                // use iterative deepening search to find edge paths
+                path::Path::all_paths_iddfs(
+                    &model.graph,
+                    cost_model,
+                    start,
+                    self.destination_station_id,
+                    *min_paths,
+                    &self.constraints,
+                    &model.inaccessible_stations,
+                    2 * travel_time + 120,
+                    search_budgets,
+                )
+            }
+            PathSearch::KShortestPaths { k } => {
+                print!("k={} ... ", k);
+                k_shortest_paths::k_shortest_paths(
+                    &model.graph,
+                    cost_model,
+                    start,
+                    self.destination_station_id,
+                    *k,
+                    2 * travel_time + 120,
+                    &self.constraints,
+                    &model.inaccessible_stations,
+                )
+            }
+            PathSearch::Pareto => {
+                print!("pareto ... ");
+                pareto_paths::pareto_paths(
+                    &model.graph,
+                    cost_model,
+                    start,
+                    self.destination_station_id,
+                    2 * travel_time + 120,
+                    &self.constraints,
+                    &model.inaccessible_stations,
+                )
+            }
+        };
 
         // let edge_sets = path::bfs(
         //     &model.graph,
@@ -230,27 +420,35 @@ impl Group {
         self.paths = edge_sets
             .into_iter()
             .filter(|edge_set| edge_set.len() != 0) // filter out empty edge_sets (paths that don't have a single edge)
-            .map(|edge_set| Path::new(&model.graph, edge_set, self.passengers, self.arrival_time))
+            .filter(|edge_set| self.constraints.admits_path(&model.graph, &model.inaccessible_stations, edge_set))
+            .map(|edge_set| Path::new(&model.graph, cost_model, edge_set, self.passengers, self.arrival_time))
             .collect();
 
+        // no path found within the search limits, fall back to the depth-first search
         if self.paths.len() == 0 {
-
             self.paths = path::Path::dfs_visitor_search(
                 &model.graph,
+                cost_model,
                 start,
                 self.destination_station_id,
                 self.passengers as u64,
                 self.arrival_time,
+                &self.constraints,
+                &model.inaccessible_stations,
                 0,
             );
         }
 
         print!("done in {}ms, ", start_instant.elapsed().as_millis());
 
+        let first_class = self.first_class;
+        self.paths = self.paths.drain(..).map(|path| path.with_first_class(first_class)).collect();
+
         // sort lowest travel_cost first
         self.paths.sort_unstable();
 
         if self.paths.len() == 0 {
+            self.unserved_reason = Some(UnservedReason::DestinationUnreachableInBudget);
             println!("{}", format!("no path found").red());
         } else {
             println!(
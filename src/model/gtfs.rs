@@ -0,0 +1,376 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+};
+
+use super::{footpath::Footpath, station::Station, trip::Trip};
+use crate::{csv_reader::{self, Record}, error::Error, time};
+
+/// configuration of the GTFS static feed import
+#[derive(Debug, Clone)]
+pub struct GtfsConfig {
+    pub service_date: u32,          // operating day (YYYYMMDD), only trips running on this day are imported
+    pub default_capacity: u64,      // capacity of trips without an entry in the capacities file
+    pub default_transfer_time: u64, // transfer time (minutes) of stations without an entry in transfers.txt
+    pub capacities_filepath: Option<String>, // optional CSV with columns `trip_id` or `route_id`, and `capacity`
+}
+
+/// stations, trips and footpaths of an imported feed
+pub type Feed = (HashMap<u64, Station>, Vec<Trip>, Vec<Footpath>);
+
+/// (trip_id -> capacity, route_id -> capacity)
+type Capacities = (HashMap<String, u64>, HashMap<String, u64>);
+
+/// one row of stop_times.txt
+struct StopTime {
+    stop_sequence: u64,
+    station_id: u64,
+    arrival: u64,
+    departure: u64,
+}
+
+/// reads a GTFS static feed folder and returns the stations, trips and footpaths of the configured service date
+///
+/// required files: `stops.txt`, `trips.txt`, `stop_times.txt`, `calendar.txt` and/or `calendar_dates.txt`,
+/// optional file: `transfers.txt`
+///
+/// numeric stop_ids/trip_ids are used as station/trip ids, non-numeric ones are numbered after the highest numeric id (in file order)
+pub fn read_feed(
+    gtfs_folder_path: &str,
+    config: &GtfsConfig,
+) -> Result<Feed, Error> {
+    let filepath = |filename: &str| format!("{}/{}", gtfs_folder_path, filename);
+
+    let service_day = service_day(config.service_date).ok_or_else(|| Error::InvalidConfig {
+        file: gtfs_folder_path.to_string(),
+        message: format!("service date {} is not an existing date formatted as YYYYMMDD", config.service_date),
+    })?;
+
+    // FIRST: stops become stations
+    let stops_filepath = filepath("stops.txt");
+    let stop_maps = csv_reader::read_to_maps(&stops_filepath)?;
+    let stop_ids = assign_ids(&stop_maps, &stops_filepath, "stop_id")?;
+
+    let mut stations = HashMap::with_capacity(stop_maps.len());
+    for (index, stop_map) in stop_maps.iter().enumerate() {
+        let id = stop_ids[csv_reader::field(stop_map, &stops_filepath, index, "stop_id")?];
+        let name = csv_reader::field(stop_map, &stops_filepath, index, "stop_name")?.to_string();
+
+        if stations.contains_key(&id) {
+            return Err(Error::DuplicateId {
+                file: stops_filepath,
+                row: csv_reader::row(index),
+                id,
+            });
+        }
+
+        // wheelchair_boarding 2: boarding is not possible for wheelchairs
+        let mut station = Station::new(id, config.default_transfer_time, name);
+        station.step_free = csv_reader::optional_field(stop_map, "wheelchair_boarding").map(|value| value.trim()) != Some("2");
+
+        stations.insert(id, station);
+    }
+
+    // SECOND: only keep trips whose service is running on service_date
+    let service_ids = active_service_ids(gtfs_folder_path, config.service_date, service_day)?;
+
+    let trips_filepath = filepath("trips.txt");
+    let trip_maps = csv_reader::read_to_maps(&trips_filepath)?;
+    let trip_ids = assign_ids(&trip_maps, &trips_filepath, "trip_id")?;
+
+    // trip_id -> route_id of all active trips
+    let mut active_trips: HashMap<&str, &str> = HashMap::new();
+    let mut seen_trip_ids = HashSet::with_capacity(trip_maps.len());
+    for (index, trip_map) in trip_maps.iter().enumerate() {
+        let trip_id = csv_reader::field(trip_map, &trips_filepath, index, "trip_id")?;
+
+        if !seen_trip_ids.insert(trip_id) {
+            return Err(Error::DuplicateId {
+                file: trips_filepath,
+                row: csv_reader::row(index),
+                id: trip_ids[trip_id],
+            });
+        }
+
+        if service_ids.contains(csv_reader::field(trip_map, &trips_filepath, index, "service_id")?) {
+            active_trips.insert(
+                trip_id,
+                csv_reader::field(trip_map, &trips_filepath, index, "route_id")?,
+            );
+        }
+    }
+
+    let (trips_capacity, routes_capacity) = match &config.capacities_filepath {
+        Some(capacities_filepath) => read_capacities(capacities_filepath)?,
+        None => (HashMap::new(), HashMap::new()),
+    };
+
+    // THIRD: collect stop_times of all active trips
+    let stop_times_filepath = filepath("stop_times.txt");
+    let stop_time_maps = csv_reader::read_to_maps(&stop_times_filepath)?;
+
+    let mut trips_stop_times: HashMap<&str, Vec<StopTime>> = HashMap::with_capacity(active_trips.len());
+    for (index, stop_time_map) in stop_time_maps.iter().enumerate() {
+        let trip_id = csv_reader::field(stop_time_map, &stop_times_filepath, index, "trip_id")?;
+
+        if !trip_ids.contains_key(trip_id) {
+            return Err(invalid_value(&stop_times_filepath, index, "trip_id", trip_id));
+        }
+
+        if !active_trips.contains_key(trip_id) {
+            // trip is not running on service_date
+            continue;
+        }
+
+        let stop_id = csv_reader::field(stop_time_map, &stop_times_filepath, index, "stop_id")?;
+        let station_id = match stop_ids.get(stop_id) {
+            Some(station_id) => *station_id,
+            None => return Err(invalid_value(&stop_times_filepath, index, "stop_id", stop_id)),
+        };
+
+        let arrival = csv_reader::field(stop_time_map, &stop_times_filepath, index, "arrival_time")?;
+        let departure = csv_reader::field(stop_time_map, &stop_times_filepath, index, "departure_time")?;
+
+        if arrival.trim().is_empty() || departure.trim().is_empty() {
+            // stop without timepoint -> trip segment is connected to the next stop with times
+            continue;
+        }
+
+        trips_stop_times.entry(trip_id).or_default().push(StopTime {
+            stop_sequence: csv_reader::parse_field(stop_time_map, &stop_times_filepath, index, "stop_sequence")?,
+            station_id,
+            arrival: time::parse_time_of_day(arrival.trim()).ok_or_else(|| invalid_value(&stop_times_filepath, index, "arrival_time", arrival))?,
+            departure: time::parse_time_of_day(departure.trim()).ok_or_else(|| invalid_value(&stop_times_filepath, index, "departure_time", departure))?,
+        });
+    }
+
+    // FOURTH: consecutive stop_times of a trip become trip segments (in order of trips.txt)
+    let mut trips = Vec::with_capacity(stop_time_maps.len());
+    for (index, trip_map) in trip_maps.iter().enumerate() {
+        let trip_id = csv_reader::field(trip_map, &trips_filepath, index, "trip_id")?;
+
+        let stop_times = match trips_stop_times.get_mut(trip_id) {
+            Some(stop_times) => stop_times,
+            None => continue, // trip is not running on service_date
+        };
+        stop_times.sort_unstable_by_key(|stop_time| stop_time.stop_sequence);
+
+        let capacity = *trips_capacity
+            .get(trip_id)
+            .or_else(|| routes_capacity.get(active_trips[trip_id]))
+            .unwrap_or(&config.default_capacity);
+
+        for stop_time_pair in stop_times.windows(2) {
+            trips.push(Trip {
+                id: trip_ids[trip_id],
+                from_station: stop_time_pair[0].station_id,
+                departure: stop_time_pair[0].departure,
+                to_station: stop_time_pair[1].station_id,
+                arrival: stop_time_pair[1].arrival,
+                capacity,
+                seats: capacity,
+                first_class_seats: 0,
+                from_platform: None,
+                to_platform: None,
+            });
+        }
+    }
+
+    // FIFTH: transfers at the same stop become transfer times, transfers between stops become footpaths
+    let mut footpaths = Vec::new();
+
+    let transfers_filepath = filepath("transfers.txt");
+    if Path::new(&transfers_filepath).exists() {
+        for (index, transfer_map) in csv_reader::read_to_maps(&transfers_filepath)?.iter().enumerate() {
+            if csv_reader::field(transfer_map, &transfers_filepath, index, "transfer_type")?.trim() == "3" {
+                // transfers are not possible between these stops
+                continue;
+            }
+
+            let from_stop_id = csv_reader::field(transfer_map, &transfers_filepath, index, "from_stop_id")?;
+            let to_stop_id = csv_reader::field(transfer_map, &transfers_filepath, index, "to_stop_id")?;
+
+            let from_station = *stop_ids.get(from_stop_id).ok_or_else(|| invalid_value(&transfers_filepath, index, "from_stop_id", from_stop_id))?;
+            let to_station = *stop_ids.get(to_stop_id).ok_or_else(|| invalid_value(&transfers_filepath, index, "to_stop_id", to_stop_id))?;
+
+            // min_transfer_time is optional and given in seconds
+            let min_transfer_time = match transfer_map.get("min_transfer_time") {
+                Some(value) if !value.trim().is_empty() => {
+                    let seconds: u64 = csv_reader::parse_field(transfer_map, &transfers_filepath, index, "min_transfer_time")?;
+                    Some(seconds.div_ceil(60))
+                }
+                _ => None,
+            };
+
+            if from_station == to_station {
+                // without min_transfer_time the station keeps the default transfer time
+                if let Some(min_transfer_time) = min_transfer_time {
+                    stations.get_mut(&from_station).unwrap().transfer_time = min_transfer_time;
+                }
+            } else {
+                // without min_transfer_time walking between the stops takes the default transfer time (instead of no time at all)
+                footpaths.push(Footpath {
+                    from_station,
+                    to_station,
+                    duration: min_transfer_time.unwrap_or(config.default_transfer_time),
+                });
+            }
+        }
+    }
+
+    println!(
+        "[read_feed()]: service_date={}, {} station(s), {} active trip(s), {} trip segment(s), {} footpath(s)",
+        config.service_date,
+        stations.len(),
+        trips_stop_times.len(),
+        trips.len(),
+        footpaths.len()
+    );
+
+    Ok((stations, trips, footpaths))
+}
+
+/// returns the service_ids running on service_date (YYYYMMDD, service_day in days since 1970-01-01) from `calendar.txt` and `calendar_dates.txt`
+fn active_service_ids(gtfs_folder_path: &str, service_date: u32, service_day: i64) -> Result<HashSet<String>, Error> {
+    const WEEKDAYS: [&str; 7] = ["sunday", "monday", "tuesday", "wednesday", "thursday", "friday", "saturday"];
+
+    let calendar_filepath = format!("{}/calendar.txt", gtfs_folder_path);
+    let calendar_dates_filepath = format!("{}/calendar_dates.txt", gtfs_folder_path);
+
+    let calendar_exists = Path::new(&calendar_filepath).exists();
+    let calendar_dates_exists = Path::new(&calendar_dates_filepath).exists();
+
+    let mut service_ids = HashSet::new();
+
+    if calendar_exists || !calendar_dates_exists {
+        // read calendar.txt (also if no calendar exists at all, to report the missing file)
+        // 1970-01-01 was a thursday
+        let weekday = WEEKDAYS[(service_day + 4).rem_euclid(7) as usize];
+
+        for (index, calendar_map) in csv_reader::read_to_maps(&calendar_filepath)?.iter().enumerate() {
+            let start_date: u32 = csv_reader::parse_field(calendar_map, &calendar_filepath, index, "start_date")?;
+            let end_date: u32 = csv_reader::parse_field(calendar_map, &calendar_filepath, index, "end_date")?;
+
+            if start_date <= service_date
+                && service_date <= end_date
+                && csv_reader::field(calendar_map, &calendar_filepath, index, weekday)?.trim() == "1"
+            {
+                service_ids.insert(csv_reader::field(calendar_map, &calendar_filepath, index, "service_id")?.to_string());
+            }
+        }
+    }
+
+    if calendar_dates_exists {
+        // exceptions: 1 = service added, 2 = service removed
+        for (index, calendar_date_map) in csv_reader::read_to_maps(&calendar_dates_filepath)?.iter().enumerate() {
+            let date: u32 = csv_reader::parse_field(calendar_date_map, &calendar_dates_filepath, index, "date")?;
+
+            if date != service_date {
+                continue;
+            }
+
+            let service_id = csv_reader::field(calendar_date_map, &calendar_dates_filepath, index, "service_id")?;
+
+            match csv_reader::field(calendar_date_map, &calendar_dates_filepath, index, "exception_type")?.trim() {
+                "1" => {
+                    service_ids.insert(service_id.to_string());
+                }
+                "2" => {
+                    service_ids.remove(service_id);
+                }
+                value => return Err(invalid_value(&calendar_dates_filepath, index, "exception_type", value)),
+            }
+        }
+    }
+
+    Ok(service_ids)
+}
+
+/// reads the capacities side-car CSV
+///
+/// returns (trip_id -> capacity, route_id -> capacity), trip entries take precedence over route entries
+fn read_capacities(capacities_filepath: &str) -> Result<Capacities, Error> {
+    let mut trips_capacity = HashMap::new();
+    let mut routes_capacity = HashMap::new();
+
+    for (index, capacity_map) in csv_reader::read_to_maps(capacities_filepath)?.iter().enumerate() {
+        let capacity = csv_reader::parse_field(capacity_map, capacities_filepath, index, "capacity")?;
+
+        match (capacity_map.get("trip_id"), capacity_map.get("route_id")) {
+            (Some(trip_id), _) if !trip_id.is_empty() => {
+                trips_capacity.insert(trip_id.clone(), capacity);
+            }
+            (_, Some(route_id)) if !route_id.is_empty() => {
+                routes_capacity.insert(route_id.clone(), capacity);
+            }
+            _ => {
+                return Err(Error::MissingColumn {
+                    file: capacities_filepath.to_string(),
+                    row: csv_reader::row(index),
+                    column: "trip_id".to_string(),
+                })
+            }
+        }
+    }
+
+    Ok((trips_capacity, routes_capacity))
+}
+
+/// maps the GTFS ids in column to numeric ids
+///
+/// numeric values are kept, all others are numbered after the highest numeric value (in order of records),
+/// if distinct values have the same numeric value (e.g. "007" and "7"), all values are numbered in order of records
+fn assign_ids(records: &[Record], filepath: &str, column: &str) -> Result<HashMap<String, u64>, Error> {
+    let mut ids = HashMap::with_capacity(records.len());
+    let mut values = Vec::with_capacity(records.len());
+    let mut non_numeric = Vec::new();
+
+    // numeric id -> value it was parsed from
+    let mut numeric_values: HashMap<u64, &str> = HashMap::with_capacity(records.len());
+    let mut collision = false;
+
+    for (index, record) in records.iter().enumerate() {
+        let value = csv_reader::field(record, filepath, index, column)?;
+        values.push(value);
+
+        match value.parse::<u64>() {
+            Ok(id) => {
+                collision |= *numeric_values.entry(id).or_insert(value) != value;
+                ids.insert(value.to_string(), id);
+            }
+            Err(_) => non_numeric.push(value),
+        }
+    }
+
+    if collision {
+        println!("[assign_ids()]: distinct values of {} in {} have the same numeric id -> numbering all in order of records", column, filepath);
+
+        ids.clear();
+        non_numeric = values;
+    }
+
+    let mut next_id = ids.values().max().map_or(0, |max_id| max_id + 1);
+    for value in non_numeric {
+        if !ids.contains_key(value) {
+            ids.insert(value.to_string(), next_id);
+            next_id += 1;
+        }
+    }
+
+    Ok(ids)
+}
+
+/// returns the days since 1970-01-01 of date (YYYYMMDD), None if the date does not exist
+pub fn service_day(date: u32) -> Option<i64> {
+    time::days_from_civil((date / 10000) as i64, (date / 100) % 100, date % 100)
+}
+
+/// returns InvalidValue error for the record with index
+fn invalid_value(filepath: &str, index: usize, column: &str, value: &str) -> Error {
+    Error::InvalidValue {
+        file: filepath.to_string(),
+        row: csv_reader::row(index),
+        column: column.to_string(),
+        value: value.to_string(),
+    }
+}
@@ -0,0 +1,208 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet},
+};
+
+use petgraph::{
+    graph::{DiGraph, EdgeIndex, NodeIndex},
+    visit::EdgeRef,
+};
+
+use super::{
+    constraints::GroupConstraints,
+    cost_model::CostModel,
+    graph_weight::{TimetableEdge, TimetableNode},
+};
+
+/// Yen's algorithm: returns the (up to) k cheapest loopless edge paths (by travel_cost) from start to the destination station
+///
+/// each path ends at its first node at the destination station (destination nodes are not expanded),
+/// nodes later than `max_duration` after start are not reached
+///
+/// paths are ordered by travel_cost, ties are broken by their edge indices, no paths are returned for k = 0
+///
+/// edges violating the constraints are pruned along the cheapest path to each node,
+/// so the search may miss admissible paths whose cheaper prefixes violate them
+pub fn k_shortest_paths(
+    graph: &DiGraph<TimetableNode, TimetableEdge>,
+    cost_model: &CostModel,
+    start: NodeIndex,
+    destination_station_id: u64,
+    k: usize,
+    max_duration: u64,
+    constraints: &GroupConstraints,
+    inaccessible_stations: &HashSet<u64>, // stations without step-free access (see `GroupConstraints::step_free`)
+) -> Vec<Vec<EdgeIndex>> {
+    if k == 0 {
+        return Vec::new();
+    }
+
+    let search = SpurSearch {
+        graph,
+        cost_model,
+        destination_station_id,
+        max_time: graph[start].time() + max_duration,
+        constraints,
+        inaccessible_stations,
+        starts_in_trip: graph[start].is_arrival(),
+    };
+
+    let mut shortest_paths: Vec<Vec<EdgeIndex>> = Vec::with_capacity(k);
+
+    // candidate paths (travel_cost, edges), cheapest first
+    let mut candidates = BinaryHeap::new();
+
+    // all paths that are (or were) a candidate
+    let mut known_paths: HashSet<Vec<EdgeIndex>> = HashSet::new();
+
+    match search.cheapest_path(start, &[], &HashSet::new(), &HashSet::new()) {
+        Some((_, edges)) => {
+            known_paths.insert(edges.clone());
+            shortest_paths.push(edges);
+        }
+        None => return shortest_paths,
+    }
+
+    while shortest_paths.len() < k {
+        let previous_path = shortest_paths.last().unwrap().clone();
+
+        // nodes of the root path (without the spur node) must not be visited by the spur path
+        let mut blocked_nodes = HashSet::new();
+        let mut root_cost = 0;
+
+        // each node of the previous path (except its destination) is a spur node
+        for (spur_index, spur_edge) in previous_path.iter().enumerate() {
+            let spur_node = graph.edge_endpoints(*spur_edge).unwrap().0;
+            let root_path = &previous_path[..spur_index];
+
+            // edges leaving the spur node of all found paths sharing this root path
+            let blocked_edges: HashSet<EdgeIndex> = shortest_paths
+                .iter()
+                .filter(|path| path.len() > spur_index && path[..spur_index] == *root_path)
+                .map(|path| path[spur_index])
+                .collect();
+
+            if let Some((spur_cost, spur_path)) = search.cheapest_path(spur_node, root_path, &blocked_nodes, &blocked_edges) {
+                let mut edges = root_path.to_vec();
+                edges.extend(spur_path);
+
+                if known_paths.insert(edges.clone()) {
+                    candidates.push(Reverse((root_cost + spur_cost, edges)));
+                }
+            }
+
+            root_cost += graph[*spur_edge].travel_cost(cost_model);
+            blocked_nodes.insert(spur_node);
+        }
+
+        match candidates.pop() {
+            Some(Reverse((_, edges))) => shortest_paths.push(edges),
+            None => break, // no further paths
+        }
+    }
+
+    shortest_paths
+}
+
+/// fixed parameters of all dijkstra searches of one k_shortest_paths call
+struct SpurSearch<'a> {
+    graph: &'a DiGraph<TimetableNode, TimetableEdge>,
+    cost_model: &'a CostModel,
+    destination_station_id: u64,
+    max_time: u64,
+    constraints: &'a GroupConstraints,
+    inaccessible_stations: &'a HashSet<u64>,
+    starts_in_trip: bool, // the first node of all paths is an arrival
+}
+
+impl SpurSearch<'_> {
+
+    /// dijkstra (by travel_cost) from start (the end of root_path) to the cheapest node at the destination station
+    ///
+    /// skips blocked nodes and edges, nodes later than max_time and edges the constraints do not admit
+    /// after root_path and the cheapest path to their source
+    ///
+    /// returns the path's cost and edges (without root_path), None if the destination station is not reachable
+    fn cheapest_path(
+        &self,
+        start: NodeIndex,
+        root_path: &[EdgeIndex],
+        blocked_nodes: &HashSet<NodeIndex>,
+        blocked_edges: &HashSet<EdgeIndex>,
+    ) -> Option<(u64, Vec<EdgeIndex>)> {
+        let graph = self.graph;
+        let unconstrained = self.constraints.is_unconstrained();
+
+        // only the nodes reached within max_time are stored
+        let mut costs: HashMap<NodeIndex, u64> = HashMap::new();
+        let mut predecessors: HashMap<NodeIndex, EdgeIndex> = HashMap::new();
+        let mut queue = BinaryHeap::new();
+
+        // (boardings, walking_time) of the cheapest path to each node, including root_path
+        let mut resources: HashMap<NodeIndex, (u64, u64)> = HashMap::new();
+        resources.insert(
+            start,
+            (
+                root_path.iter().filter(|edge| graph[**edge].is_board()).count() as u64,
+                root_path.iter().filter(|edge| graph[**edge].is_walk()).map(|edge| graph[*edge].duration()).sum(),
+            ),
+        );
+
+        costs.insert(start, 0);
+        queue.push(Reverse((0, start)));
+
+        while let Some(Reverse((cost, node))) = queue.pop() {
+            if cost > costs[&node] {
+                // outdated entry
+                continue;
+            }
+
+            if graph[node].station_id() == self.destination_station_id {
+                let mut edges = Vec::new();
+                let mut current = node;
+                while let Some(edge) = predecessors.get(&current) {
+                    edges.push(*edge);
+                    current = graph.edge_endpoints(*edge).unwrap().0;
+                }
+                edges.reverse();
+
+                // first admissible destination node taken from the queue is the cheapest one
+                if unconstrained || self.constraints.admits_path(graph, self.inaccessible_stations, &[root_path, &edges].concat()) {
+                    return Some((cost, edges));
+                }
+
+                continue;
+            }
+
+            let (boardings, walking_time) = resources[&node];
+
+            for edge in graph.edges(node) {
+                let next = edge.target();
+
+                if blocked_edges.contains(&edge.id()) || blocked_nodes.contains(&next) || graph[next].time() > self.max_time {
+                    continue;
+                }
+
+                if !unconstrained && !self.constraints.admits_edge(graph, self.inaccessible_stations, edge.id(), self.starts_in_trip, boardings, walking_time) {
+                    continue;
+                }
+
+                let next_cost = cost + edge.weight().travel_cost(self.cost_model);
+                if costs.get(&next).is_none_or(|next_known_cost| next_cost < *next_known_cost) {
+                    costs.insert(next, next_cost);
+                    predecessors.insert(next, edge.id());
+                    resources.insert(
+                        next,
+                        (
+                            boardings + if edge.weight().is_board() { 1 } else { 0 },
+                            walking_time + if edge.weight().is_walk() { edge.weight().duration() } else { 0 },
+                        ),
+                    );
+                    queue.push(Reverse((next_cost, next)));
+                }
+            }
+        }
+
+        None
+    }
+}
@@ -4,12 +4,22 @@ use std::io::{BufWriter, Write};
 use std::io::BufReader;
 use crossbeam_utils::thread;
 
+pub mod constraints;
+pub mod cost_model;
 pub mod group;
 pub mod footpath;
 pub mod station;
 pub mod trip;
 pub mod path;
 pub mod graph_weight;
+pub mod gtfs;
+pub mod k_shortest_paths;
+pub mod pareto_paths;
+pub mod periodic_trip;
+pub mod realtime;
+pub mod scenario;
+pub mod transfer;
+pub mod update;
 
 use graph_weight::{TimetableNode, TimetableEdge};
 
@@ -18,7 +28,7 @@ use group::Group;
 
 use petgraph::{dot::{Dot}, graph::{DiGraph, EdgeIndex, NodeIndex}};
 
-use crate::csv_reader;
+use crate::{csv_reader, error::Error, time};
 
 /// entire combined data model
 #[derive(Serialize, Deserialize)]
@@ -30,32 +40,191 @@ pub struct Model {
     pub stations_transfers: HashMap<u64, Vec<NodeIndex>>,
 
     // required for "in_trip" column of groups (groups could start in a train instead of a station)
-    pub stations_arrivals: HashMap<u64, Vec<NodeIndex>>
+    pub stations_arrivals: HashMap<u64, Vec<NodeIndex>>,
+
+    // required to add nodes and rewire stations after updates of the graph (see update.rs)
+    pub stations_name: HashMap<u64, String>,
+    pub stations_transfer_time: HashMap<u64, u64>,
+    pub footpaths: Vec<footpath::Footpath>,
+
+    // Departure and Arrival nodes of each trip, Departure nodes of each station and footpaths (indices) from or to each station,
+    // so updates find the affected nodes without scanning the graph (cancelled nodes are removed, see update.rs)
+    pub trips_nodes: HashMap<u64, Vec<NodeIndex>>,
+    pub stations_departures: HashMap<u64, Vec<NodeIndex>>,
+    pub stations_footpaths: HashMap<u64, Vec<usize>>,
+
+    // stations without step-free access (required for groups with the step_free constraint)
+    pub inaccessible_stations: HashSet<u64>,
+
+    // day 0 of ISO-8601 timestamps in days since 1970-01-01 (earliest date of trips.csv or the GTFS service date)
+    pub reference_date: Option<i64>,
+
+    // pattern and run of the trips expanded from periodic_trips.csv
+    pub trip_patterns: HashMap<u64, periodic_trip::TripPattern>,
+
+    // transfer rules of transfers.csv by station, platforms of arrivals and departures and
+    // the transfer nodes of departures that are boarded directly (see transfer.rs)
+    pub transfer_rules: HashMap<u64, Vec<transfer::TransferRule>>,
+    pub platforms: HashMap<NodeIndex, String>,
+    pub direct_transfers: HashMap<NodeIndex, NodeIndex>,
 }
 
 impl Model {
 
     /// builds a timetable model (graph) from a folder that contains the following files:
     ///
-    /// `stations.csv`, `footpaths.csv`, `trips.csv`
-    pub fn with_stations_trips_and_footpaths(csv_folder_path: &str) -> Self {
+    /// `stations.csv`, `footpaths.csv`, `trips.csv` and optionally `periodic_trips.csv` and `transfers.csv`
+    ///
+    /// periodic trips are expanded into one trip per run, numbered after the highest id of the trips and patterns
+    ///
+    /// returns an error if an input file is missing or contains invalid records
+    pub fn with_stations_trips_and_footpaths(csv_folder_path: &str) -> Result<Self, Error> {
 
-        let start = Instant::now();
+        let stations_filepath = format!("{}/stations.csv", csv_folder_path);
+        let trips_filepath = format!("{}/trips.csv", csv_folder_path);
+        let footpaths_filepath = format!("{}/footpaths.csv", csv_folder_path);
 
         // read all input data CSVs
-        let station_maps = csv_reader::read_to_maps(&format!("{}/stations.csv", csv_folder_path));
-        let trip_maps = csv_reader::read_to_maps(&format!("{}/trips.csv", csv_folder_path));
-        let footpath_maps = csv_reader::read_to_maps(&format!("{}/footpaths.csv", csv_folder_path));
+        let station_maps = csv_reader::read_to_maps(&stations_filepath)?;
+        let trip_maps = csv_reader::read_to_maps(&trips_filepath)?;
+        let footpath_maps = csv_reader::read_to_maps(&footpaths_filepath)?;
+
+        let periodic_trips_filepath = format!("{}/periodic_trips.csv", csv_folder_path);
+        let periodic_trip_maps = if std::path::Path::new(&periodic_trips_filepath).exists() {
+            csv_reader::read_to_maps(&periodic_trips_filepath)?
+        } else {
+            Vec::new()
+        };
+
+        let transfers_filepath = format!("{}/transfers.csv", csv_folder_path);
+        let transfer_maps = if std::path::Path::new(&transfers_filepath).exists() {
+            csv_reader::read_to_maps(&transfers_filepath)?
+        } else {
+            Vec::new()
+        };
+
+        // ISO-8601 timestamps are relative to the earliest date of the trips (day 0)
+        let reference_date = time::earliest_date(
+            trip_maps.iter().chain(periodic_trip_maps.iter())
+                .flat_map(|trip_map| ["departure", "arrival"].iter().filter_map(move |column| trip_map.get(*column)).map(|value| value.as_str()))
+        );
+
+        let stations = station::Station::from_maps_to_map(&station_maps, &stations_filepath)?;
+        let mut trips = trip::Trip::from_maps_to_vec(&trip_maps, &trips_filepath, reference_date)?;
+        let periodic_trips = periodic_trip::PeriodicTrip::from_maps_to_vec(&periodic_trip_maps, &periodic_trips_filepath, reference_date)?;
+        let footpaths = footpath::Footpath::from_maps_to_vec(&footpath_maps, &footpaths_filepath)?;
+        let transfer_rules = transfer::TransferRule::from_maps_to_vec(&transfer_maps, &transfers_filepath)?;
+
+        // pattern ids must not be used by trips of trips.csv
+        let trip_ids: HashSet<u64> = trips.iter().map(|trip| trip.id).collect();
+        if let Some((index, periodic_trip)) = periodic_trips.iter().enumerate().find(|(_, periodic_trip)| trip_ids.contains(&periodic_trip.trip.id)) {
+            return Err(Error::DuplicateId {
+                file: periodic_trips_filepath,
+                row: csv_reader::row(index),
+                id: periodic_trip.trip.id,
+            });
+        }
+
+        Self::validate_station_references(&stations, &periodic_trips_filepath, periodic_trips.iter().map(|periodic_trip| [periodic_trip.trip.from_station, periodic_trip.trip.to_station]))?;
+
+        let first_id = trips.iter().map(|trip| trip.id).chain(periodic_trips.iter().map(|periodic_trip| periodic_trip.trip.id)).max().map_or(0, |id| id + 1);
+        let (expanded_trips, trip_patterns) = periodic_trip::PeriodicTrip::expand(&periodic_trips, first_id);
+
+        if !trip_patterns.is_empty() {
+            println!("expanded periodic trips into {} trip(s) (ids {} to {})", trip_patterns.len(), first_id, first_id + trip_patterns.len() as u64 - 1);
+        }
+
+        // all stations referenced by trips and footpaths have to exist
+        Self::validate_station_references(&stations, &trips_filepath, trips.iter().map(|trip| [trip.from_station, trip.to_station]))?;
+        Self::validate_station_references(&stations, &footpaths_filepath, footpaths.iter().map(|footpath| [footpath.from_station, footpath.to_station]))?;
+
+        trips.extend(expanded_trips);
+
+        // transfer rules refer to existing stations and trips
+        Self::validate_station_references(&stations, &transfers_filepath, transfer_rules.iter().map(|transfer_rule| [transfer_rule.station, transfer_rule.station]))?;
+
+        let trip_ids: HashSet<u64> = trips.iter().map(|trip| trip.id).collect();
+        for (index, transfer_rule) in transfer_rules.iter().enumerate() {
+            for (column, trip_id) in [("from_trip", transfer_rule.from_trip), ("to_trip", transfer_rule.to_trip)].iter() {
+                if let Some(trip_id) = trip_id.filter(|trip_id| !trip_ids.contains(trip_id)) {
+                    return Err(Error::InvalidValue {
+                        file: transfers_filepath,
+                        row: csv_reader::row(index),
+                        column: column.to_string(),
+                        value: trip_id.to_string(),
+                    });
+                }
+            }
+        }
+
+        let mut model = Self::with_parsed_stations_trips_and_footpaths(stations, trips, footpaths, transfer_rules);
+        model.reference_date = reference_date;
+        model.trip_patterns = trip_patterns;
+
+        Ok(model)
+    }
+
+    /// returns an error for the first record (in order of station_id_pairs) that references a station not in stations
+    fn validate_station_references(
+        stations: &HashMap<u64, station::Station>,
+        filepath: &str,
+        station_id_pairs: impl Iterator<Item = [u64; 2]>,
+    ) -> Result<(), Error> {
+        for (index, station_id_pair) in station_id_pairs.enumerate() {
+            for station_id in station_id_pair.iter() {
+                if !stations.contains_key(station_id) {
+                    return Err(Error::UnknownStation {
+                        file: filepath.to_string(),
+                        row: csv_reader::row(index),
+                        station_id: *station_id,
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// builds a timetable model (graph) from a GTFS static feed folder
+    ///
+    /// see `gtfs::read_feed()` for the required files
+    pub fn with_gtfs_feed(gtfs_folder_path: &str, config: &gtfs::GtfsConfig) -> Result<Self, Error> {
+        let (stations, trips, footpaths) = gtfs::read_feed(gtfs_folder_path, config)?;
+
+        // GTFS times are relative to the service day
+        let mut model = Self::with_parsed_stations_trips_and_footpaths(stations, trips, footpaths, Vec::new());
+        model.reference_date = gtfs::service_day(config.service_date);
+
+        Ok(model)
+    }
+
+    /// builds a timetable model (graph) from already parsed stations, trips, footpaths and transfer rules
+    ///
+    /// all stations referenced by trips, footpaths and transfer rules have to exist in stations
+    pub fn with_parsed_stations_trips_and_footpaths(
+        mut stations: HashMap<u64, station::Station>,
+        trips: Vec<trip::Trip>,
+        footpaths: Vec<footpath::Footpath>,
+        transfer_rules: Vec<transfer::TransferRule>,
+    ) -> Self {
+
+        let start = Instant::now();
 
         // initialize graph
         let mut graph = DiGraph::new();
 
-        let mut stations = station::Station::from_maps_to_map(&station_maps);
         let mut stations_transfers = HashMap::with_capacity(stations.len());
         let mut stations_arrivals = HashMap::with_capacity(stations.len());
-
-        // also save a HashMap of trips to parse group's "in_trip" column
-        let trips = trip::Trip::from_maps_to_vec(&trip_maps);
+        let mut stations_name = HashMap::with_capacity(stations.len());
+        let mut stations_transfer_time = HashMap::with_capacity(stations.len());
+        let mut inaccessible_stations = HashSet::new();
+        let mut platforms = HashMap::new();
+        let mut direct_transfers = HashMap::new();
+
+        let mut stations_transfer_rules: HashMap<u64, Vec<transfer::TransferRule>> = HashMap::new();
+        for transfer_rule in transfer_rules {
+            stations_transfer_rules.entry(transfer_rule.station).or_default().push(transfer_rule);
+        }
 
         for trip in trips {
             trip.connect(&mut graph, &mut stations);
@@ -63,7 +232,15 @@ impl Model {
 
         for (station_id, station) in stations.into_iter() {
 
-            let (transfers, arrivals) = station.connect(&mut graph);
+            stations_name.insert(station_id, station.name.clone());
+            stations_transfer_time.insert(station_id, station.transfer_time);
+            if !station.step_free {
+                inaccessible_stations.insert(station_id);
+            }
+            platforms.extend(station.platforms.iter().map(|(node, platform)| (*node, platform.clone())));
+
+            let transfer_rules = stations_transfer_rules.get(&station_id).map_or(&[][..], |transfer_rules| transfer_rules.as_slice());
+            let (transfers, arrivals) = station.connect(&mut graph, transfer_rules, &mut direct_transfers);
 
             // save references to all transfers and to arrival_main
             stations_transfers.insert(station_id, transfers);
@@ -74,7 +251,7 @@ impl Model {
         let mut failed_footpath_counter = 0;
 
         // iterate over all footpaths
-        for footpath in footpath::Footpath::from_maps_to_vec(&footpath_maps) {
+        for footpath in footpaths.iter() {
 
             let from_station_arrivals = stations_arrivals.get(&footpath.from_station).unwrap();
             let to_station_transfers = stations_transfers.get(&footpath.to_station).unwrap();
@@ -90,6 +267,25 @@ impl Model {
         }
         println!("successful_footpaths: {}, failed_footpaths: {}", successful_footpath_counter, failed_footpath_counter);
 
+        let mut trips_nodes: HashMap<u64, Vec<NodeIndex>> = HashMap::new();
+        let mut stations_departures: HashMap<u64, Vec<NodeIndex>> = HashMap::new();
+        for node in graph.node_indices() {
+            if let Some(trip_id) = graph[node].trip_id() {
+                trips_nodes.entry(trip_id).or_default().push(node);
+            }
+            if graph[node].is_departure() {
+                stations_departures.entry(graph[node].station_id()).or_default().push(node);
+            }
+        }
+
+        let mut stations_footpaths: HashMap<u64, Vec<usize>> = HashMap::new();
+        for (footpath_index, footpath) in footpaths.iter().enumerate() {
+            stations_footpaths.entry(footpath.from_station).or_default().push(footpath_index);
+            if footpath.to_station != footpath.from_station {
+                stations_footpaths.entry(footpath.to_station).or_default().push(footpath_index);
+            }
+        }
+
 
         println!(
             "[with_stations_trips_and_footpaths()]: done ({}ms), graph.node_count()={}, graph.edge_count()={}", 
@@ -102,6 +298,18 @@ impl Model {
             graph,
             stations_transfers,
             stations_arrivals,
+            stations_name,
+            stations_transfer_time,
+            footpaths,
+            trips_nodes,
+            stations_departures,
+            stations_footpaths,
+            inaccessible_stations,
+            reference_date: None,
+            trip_patterns: HashMap::new(),
+            transfer_rules: stations_transfer_rules,
+            platforms,
+            direct_transfers,
         }
     }
 
@@ -191,13 +399,26 @@ impl Model {
 
     /// creates groups, finds paths for groups, 
     /// returns groups
-    pub fn find_paths_for_groups(&self, groups_csv_filepath: &str, search_budget: &[u64], n_threads: usize, min_edge_vecs: usize) -> Vec<Group> {
+    ///
+    /// returns an error if the groups file is missing, contains invalid records or references unknown stations
+    pub fn find_paths_for_groups(&self, groups_csv_filepath: &str, cost_model: &cost_model::CostModel, path_search: &path::PathSearch, n_threads: usize) -> Result<Vec<Group>, Error> {
+
+        let groups = Group::from_maps_to_vec(&csv_reader::read_to_maps(groups_csv_filepath)?, groups_csv_filepath, self.reference_date)?;
+
+        // all referenced stations have to exist in model
+        for (index, group) in groups.iter().enumerate() {
+            for station_id in [group.start_station_id, group.destination_station_id].iter().chain(group.constraints.avoid_stations.iter()) {
+                if !self.stations_transfers.contains_key(station_id) {
+                    return Err(Error::UnknownStation {
+                        file: groups_csv_filepath.to_string(),
+                        row: csv_reader::row(index),
+                        station_id: *station_id,
+                    });
+                }
+            }
+        }
 
-        let unprocessed_groups = Arc::new(
-            Mutex::new(
-                Group::from_maps_to_vec(&csv_reader::read_to_maps(groups_csv_filepath))
-            )
-        );
+        let unprocessed_groups = Arc::new(Mutex::new(groups));
             
         let processed_groups = Arc::new(Mutex::new(Vec::with_capacity(unprocessed_groups.lock().unwrap().len())));
   
@@ -217,7 +438,7 @@ impl Model {
                         match group_option {
                             Some(mut group) => {
                                 print!("[group={}]: ", group.id);
-                                group.search_paths(&self, search_budget, min_edge_vecs);
+                                group.search_paths(&self, cost_model, path_search);
 
                                 // add processed group to processed vec
                                 processed_groups.lock().unwrap().push(group)
@@ -245,14 +466,14 @@ impl Model {
             start.elapsed().as_secs() / 60
         );
 
-        groups
+        Ok(groups)
     }
 }
 
 /// Model and Group tests
 #[cfg(test)]
 mod tests {
-    use petgraph::EdgeDirection::Outgoing;
+    use petgraph::{visit::EdgeRef, EdgeDirection::Outgoing};
 
     use super::*;
 
@@ -260,7 +481,7 @@ mod tests {
     #[test]
     fn validate_graph_integrity() {
 
-        let model = Model::with_stations_trips_and_footpaths("data");
+        let model = Model::with_stations_trips_and_footpaths("data").unwrap();
         let graph = model.graph;
 
         let start = Instant::now();
@@ -407,6 +628,1058 @@ mod tests {
         println!("[validate_graph_integrity()]: passed ({}ms)", start.elapsed().as_millis());
     }
 
+    /// writes the input CSVs of a model into a new folder in the temp dir, returns folder path
+    fn write_input_folder(name: &str, stations: &str, trips: &str, footpaths: &str, groups: &str) -> String {
+        let folder_path = std::env::temp_dir().join(name);
+        std::fs::create_dir_all(&folder_path).unwrap();
+
+        std::fs::write(folder_path.join("stations.csv"), stations).unwrap();
+        std::fs::write(folder_path.join("trips.csv"), trips).unwrap();
+        std::fs::write(folder_path.join("footpaths.csv"), footpaths).unwrap();
+        std::fs::write(folder_path.join("groups.csv"), groups).unwrap();
+
+        folder_path.to_str().unwrap().to_string()
+    }
+
+    /// tests that invalid input files are reported with file and row instead of panicking
+    #[test]
+    fn validate_input_errors() {
+        let stations = "id,transfer,name\n1,5,A\n2,5,B\n";
+        let trips = "id,from_station,departure,to_station,arrival,capacity\n1,1,10,2,20,5\n";
+        let footpaths = "from_station,to_station,duration\n";
+        let groups = "id,start,departure,destination,arrival,passengers,in_trip\n1,1,0,2,30,2,\n";
+
+        // missing column
+        let folder_path = write_input_folder("mcfp_missing_column", "id,name\n1,A\n", trips, footpaths, groups);
+        match Model::with_stations_trips_and_footpaths(&folder_path) {
+            Err(Error::MissingColumn {file: _, row, column}) => assert!(row == 2 && column == "transfer"),
+            _ => panic!("expected MissingColumn error"),
+        }
+
+        // unparsable value
+        let folder_path = write_input_folder("mcfp_invalid_value", stations, "id,from_station,departure,to_station,arrival,capacity\n1,1,10,2,20,5\n1,2,x,1,40,5\n", footpaths, groups);
+        match Model::with_stations_trips_and_footpaths(&folder_path) {
+            Err(Error::InvalidValue {file, row, column, value}) => assert!(file.ends_with("trips.csv") && row == 3 && column == "departure" && value == "x"),
+            _ => panic!("expected InvalidValue error"),
+        }
+
+        // duplicate station id
+        let folder_path = write_input_folder("mcfp_duplicate_id", "id,transfer,name\n1,5,A\n1,5,B\n", trips, footpaths, groups);
+        match Model::with_stations_trips_and_footpaths(&folder_path) {
+            Err(Error::DuplicateId {file: _, row, id}) => assert!(row == 3 && id == 1),
+            _ => panic!("expected DuplicateId error"),
+        }
+
+        // unknown station in footpaths and groups
+        let folder_path = write_input_folder("mcfp_unknown_station", stations, trips, "from_station,to_station,duration\n1,3,5\n", groups);
+        match Model::with_stations_trips_and_footpaths(&folder_path) {
+            Err(Error::UnknownStation {file, row, station_id}) => assert!(file.ends_with("footpaths.csv") && row == 2 && station_id == 3),
+            _ => panic!("expected UnknownStation error"),
+        }
+
+        let folder_path = write_input_folder("mcfp_unknown_group_station", stations, trips, footpaths, "id,start,departure,destination,arrival,passengers,in_trip\n1,1,0,2,30,2,\n2,1,0,4,30,2,\n");
+        let model = Model::with_stations_trips_and_footpaths(&folder_path).unwrap();
+        match model.find_paths_for_groups(&format!("{}/groups.csv", folder_path), &cost_model::CostModel::default(), &path::PathSearch::Iddfs { search_budgets: vec![30], min_paths: 1 }, 1) {
+            Err(Error::UnknownStation {file, row, station_id}) => assert!(file.ends_with("groups.csv") && row == 3 && station_id == 4),
+            _ => panic!("expected UnknownStation error"),
+        }
+    }
+
+    /// tests that groups without any path are reported with the reason and kept with the unserved path
+    #[test]
+    fn validate_unserved_groups() {
+        let stations = "id,transfer,name\n1,5,A\n2,5,B\n3,5,C\n4,5,D\n";
+        let trips = "id,from_station,departure,to_station,arrival,capacity\n1,1,10,2,20,5\n2,2,5,1,8,5\n3,1,0,3,4,5\n";
+        let footpaths = "from_station,to_station,duration\n";
+        let groups = "id,start,departure,destination,arrival,passengers,in_trip\n\
+            1,1,0,2,30,2,\n2,1,50,2,80,1,\n3,1,0,4,30,3,\n4,1,5,2,2,1,\n5,2,0,3,100,4,\n";
+
+        let folder_path = write_input_folder("mcfp_unserved_groups", stations, trips, footpaths, groups);
+        let model = Model::with_stations_trips_and_footpaths(&folder_path).unwrap();
+        let mut cost_model = cost_model::CostModel::default();
+        let path_search = path::PathSearch::Iddfs { search_budgets: vec![30, 100], min_paths: 1 };
+        let mut groups = model.find_paths_for_groups(&format!("{}/groups.csv", folder_path), &cost_model, &path_search, 1).unwrap();
+        groups.sort_by_key(|group| group.id);
+
+        let unserved_reasons: Vec<Option<group::UnservedReason>> = groups.iter().map(|group| group.unserved_reason).collect();
+        assert_eq!(
+            unserved_reasons,
+            vec![
+                None,
+                Some(group::UnservedReason::NoStartTransfer),
+                Some(group::UnservedReason::DestinationWithoutArrivals),
+                Some(group::UnservedReason::ArrivalBeforeDeparture),
+                Some(group::UnservedReason::DestinationUnreachableInBudget),
+            ]
+        );
+
+        let filepath = std::env::temp_dir().join("mcfp_unserved_groups.csv");
+        let filepath = filepath.to_str().unwrap();
+        Group::save_unserved_groups_to_csv(&groups, filepath);
+        let rows = std::fs::read_to_string(filepath).unwrap();
+        assert_eq!(rows.lines().count(), 5);
+        assert_eq!(rows.lines().nth(2).unwrap(), "3|3|1|00:00|4|00:30||destination_without_arrivals");
+
+        // without unserved_penalty no path is added
+        for group in groups.iter_mut() {
+            group.add_unserved_path(&cost_model);
+        }
+        assert!(groups.iter().all(|group| group.paths.iter().all(|path| !path.is_unserved())));
+
+        cost_model.unserved_penalty = Some(50);
+        for group in groups.iter_mut() {
+            group.add_unserved_path(&cost_model);
+
+            let unserved = group.paths.last().unwrap();
+            assert!(unserved.is_unserved() && unserved.cost() == 50 * group.passengers as i64, "Unserved path has not the penalty as cost!");
+        }
+        assert!(groups[0].paths.len() > 1 && !groups[0].paths[0].is_unserved(), "Served group lost its paths!");
+    }
+
+    /// returns all edges between nodes with edges as sorted strings of their kind, station, time and trip (independent of indices)
+    fn edge_signatures(model: &Model) -> Vec<String> {
+        let node_signature = |node: NodeIndex| {
+            let weight = &model.graph[node];
+            format!("{}({},{},{:?})", weight.kind_as_str(), weight.station_id(), weight.time(), weight.trip_id())
+        };
+
+        let mut edge_signatures: Vec<String> = model.graph
+            .edge_references()
+            .map(|edge| format!("{}-{:?}->{}", node_signature(edge.source()), edge.weight(), node_signature(edge.target())))
+            .collect();
+
+        edge_signatures.sort_unstable();
+        edge_signatures
+    }
+
+    /// asserts that the index of trip nodes of model has the same nodes (by kind, station and time) as the one of other
+    fn assert_trip_nodes_equal(model: &Model, other: &Model) {
+        let trip_node_signatures = |model: &Model, trip_id: u64| -> Vec<String> {
+            model.trip_nodes(trip_id)
+                .into_iter()
+                .map(|node| format!("{}({},{})", model.graph[node].kind_as_str(), model.graph[node].station_id(), model.graph[node].time()))
+                .collect()
+        };
+
+        let trip_ids: HashSet<u64> = model.trips_nodes.keys().chain(other.trips_nodes.keys()).copied().collect();
+        for trip_id in trip_ids {
+            assert_eq!(trip_node_signatures(model, trip_id), trip_node_signatures(other, trip_id), "Nodes of trip {} differ!", trip_id);
+        }
+    }
+
+    /// asserts that the paths of groups are connected and have the cost of their edges in the (updated) graph
+    fn assert_paths_valid(model: &Model, groups: &[Group], cost_model: &cost_model::CostModel) {
+        for group in groups.iter() {
+            for path in group.paths.iter() {
+                let edges: Vec<EdgeIndex> = path.edges.iter().copied().collect();
+                for edge_pair in edges.windows(2) {
+                    assert!(model.graph.edge_endpoints(edge_pair[0]).unwrap().1 == model.graph.edge_endpoints(edge_pair[1]).unwrap().0, "Path of group {} is not connected!", group.id);
+                }
+
+                let recalculated = path::Path::new(&model.graph, cost_model, edges, group.passengers, group.arrival_time);
+                assert!(recalculated.travel_cost() == path.travel_cost() && recalculated.travel_delay() == path.travel_delay(), "Path of group {} has outdated costs!", group.id);
+            }
+        }
+    }
+
+    /// stations, footpaths, groups and trips of the small network of the update tests
+    const SMALL_NETWORK_STATIONS: &str = "id,transfer,name\n1,5,A\n2,5,B\n3,5,C\n4,5,D\n5,5,E\n";
+    const SMALL_NETWORK_FOOTPATHS: &str = "from_station,to_station,duration\n2,5,3\n5,2,3\n3,5,4\n";
+    const SMALL_NETWORK_GROUPS: &str = "id,start,departure,destination,arrival,passengers,in_trip\n1,1,0,4,60,2,\n2,5,0,4,60,1,\n3,1,0,3,40,3,\n";
+    const SMALL_NETWORK_TRIPS: [&str; 6] = [
+        "1,1,10,2,20,5", "1,2,22,3,30,5", "1,3,32,4,40,5",
+        "2,2,25,4,45,5",
+        "3,5,0,3,15,5",
+        "4,1,12,3,28,5",
+    ];
+
+    /// returns the content of a trips.csv with rows
+    fn trips_csv(rows: &[&str]) -> String {
+        format!("id,from_station,departure,to_station,arrival,capacity\n{}\n", rows.join("\n"))
+    }
+
+    /// writes the small network with trip_rows instead of its trips into a new folder in the temp dir, returns folder path
+    fn write_small_network(name: &str, trip_rows: &[&str]) -> String {
+        write_input_folder(name, SMALL_NETWORK_STATIONS, &trips_csv(trip_rows), SMALL_NETWORK_FOOTPATHS, SMALL_NETWORK_GROUPS)
+    }
+
+    /// path search of the update tests
+    fn small_network_path_search() -> path::PathSearch {
+        path::PathSearch::Iddfs { search_budgets: vec![30, 60], min_paths: 1 }
+    }
+
+    /// builds the model of the small network (in a new folder in the temp dir) along with the paths of its groups
+    fn small_network_model(name: &str) -> (Model, Vec<Group>) {
+        let folder_path = write_small_network(name, &SMALL_NETWORK_TRIPS);
+
+        let model = Model::with_stations_trips_and_footpaths(&folder_path).unwrap();
+        let groups = model
+            .find_paths_for_groups(&format!("{}/groups.csv", folder_path), &cost_model::CostModel::default(), &small_network_path_search(), 1)
+            .unwrap();
+
+        (model, groups)
+    }
+
+    /// tests that disruption scenarios update the model like a full rebuild with the disrupted input and repair the paths of groups
+    #[test]
+    fn validate_scenario_updates() {
+        let cost_model = cost_model::CostModel::default();
+        let path_search = small_network_path_search();
+
+        // each scenario along with the trips of an equivalent full rebuild
+        let cases = [
+            (
+                scenario::Scenario { cancelled_trips: vec![4], ..Default::default() },
+                vec!["1,1,10,2,20,5", "1,2,22,3,30,5", "1,3,32,4,40,5", "2,2,25,4,45,5", "3,5,0,3,15,5"],
+            ),
+            (
+                scenario::Scenario { delays: vec![scenario::TripDelay { trip: 1, station: Some(2), minutes: 7 }], ..Default::default() },
+                vec!["1,1,10,2,20,5", "1,2,29,3,37,5", "1,3,39,4,47,5", "2,2,25,4,45,5", "3,5,0,3,15,5", "4,1,12,3,28,5"],
+            ),
+            (
+                scenario::Scenario { closed_stations: vec![3], ..Default::default() },
+                vec!["1,1,10,2,20,5", "2,2,25,4,45,5"],
+            ),
+            (
+                scenario::Scenario {
+                    cancelled_trips: vec![2],
+                    delays: vec![scenario::TripDelay { trip: 3, station: None, minutes: 20 }],
+                    capacity_reductions: vec![scenario::CapacityReduction { trip: 1, capacity: 1 }],
+                    ..Default::default()
+                },
+                vec!["1,1,10,2,20,1", "1,2,22,3,30,1", "1,3,32,4,40,1", "3,5,20,3,35,5", "4,1,12,3,28,5"],
+            ),
+        ];
+
+        for (case_index, (scenario, rebuild_trip_rows)) in cases.iter().enumerate() {
+            let (mut model, mut groups) = small_network_model("mcfp_scenario");
+
+            let changes = model.apply_scenario(scenario).unwrap();
+            let searched_groups = model.update_groups(&mut groups, &changes, &cost_model, &path_search);
+
+            let rebuild_folder_path = write_small_network(&format!("mcfp_scenario_rebuild_{}", case_index), rebuild_trip_rows);
+            let rebuild = Model::with_stations_trips_and_footpaths(&rebuild_folder_path).unwrap();
+
+            assert_eq!(edge_signatures(&model), edge_signatures(&rebuild), "Scenario {} does not equal a full rebuild!", case_index);
+
+            for (station_id, transfers) in rebuild.stations_transfers.iter() {
+                let times = |transfers: &Vec<NodeIndex>, graph: &DiGraph<TimetableNode, TimetableEdge>| transfers.iter().map(|transfer| graph[*transfer].time()).collect::<Vec<u64>>();
+                assert_eq!(times(&model.stations_transfers[station_id], &model.graph), times(transfers, &rebuild.graph), "Transfers of station {} differ!", station_id);
+                assert_eq!(model.stations_arrivals[station_id].len(), rebuild.stations_arrivals[station_id].len(), "Arrivals of station {} differ!", station_id);
+            }
+
+            assert_paths_valid(&model, &groups, &cost_model);
+
+            for (group_index, group) in groups.iter().enumerate() {
+                if !searched_groups.contains(&group_index) {
+                    assert!(!group.paths.is_empty(), "Group {} lost its paths without a new search!", group.id);
+                }
+            }
+        }
+
+        // a group in trip 1 (arriving at station 3 at 30) keeps its start when trip 1 is delayed
+        let folder_path = write_input_folder(
+            "mcfp_scenario_in_trip",
+            SMALL_NETWORK_STATIONS,
+            &trips_csv(&SMALL_NETWORK_TRIPS),
+            SMALL_NETWORK_FOOTPATHS,
+            "id,start,departure,destination,arrival,passengers,in_trip\n1,3,30,4,40,2,1\n",
+        );
+        let mut model = Model::with_stations_trips_and_footpaths(&folder_path).unwrap();
+        let mut groups = model.find_paths_for_groups(&format!("{}/groups.csv", folder_path), &cost_model, &path_search, 1).unwrap();
+        assert!(!groups[0].paths.is_empty(), "In-trip group has no path!");
+
+        let changes = model.apply_scenario(&scenario::Scenario { delays: vec![scenario::TripDelay { trip: 1, station: Some(2), minutes: 7 }], ..Default::default() }).unwrap();
+        assert_eq!(model.update_groups(&mut groups, &changes, &cost_model, &path_search), vec![0], "In-trip group on the delayed trip is not searched again!");
+        assert!(groups[0].unserved_reason.is_none() && !groups[0].paths.is_empty(), "In-trip group on the delayed trip lost its start!");
+
+        let first_node = model.graph.edge_endpoints(groups[0].paths[0].edges[0]).unwrap().0;
+        assert!(model.graph[first_node].trip_id() == Some(1) && model.graph[first_node].time() == 37, "In-trip group does not start at the delayed arrival!");
+        assert_paths_valid(&model, &groups, &cost_model);
+
+        // scenarios have to reference existing trips and stations
+        let scenario_filepath = std::env::temp_dir().join("mcfp_scenario.toml");
+        let scenario_filepath = scenario_filepath.to_str().unwrap();
+        let (model, _) = small_network_model("mcfp_scenario");
+
+        std::fs::write(scenario_filepath, "closed_stations = [3]\n\n[[delays]]\ntrip = 1\nstation = 2\nminutes = 7\n").unwrap();
+        let scenario = scenario::Scenario::from_file(scenario_filepath, &model).unwrap();
+        assert!(scenario.closed_stations == vec![3] && scenario.delays[0].station == Some(2));
+
+        std::fs::write(scenario_filepath, "cancelled_trips = [9]\n").unwrap();
+        assert!(matches!(scenario::Scenario::from_file(scenario_filepath, &model), Err(Error::InvalidConfig {file: _, message}) if message == "unknown trip 9"));
+
+        std::fs::write(scenario_filepath, "[[delays]]\ntrip = 2\nstation = 1\nminutes = 5\n").unwrap();
+        assert!(scenario::Scenario::from_file(scenario_filepath, &model).is_err(), "Delay at a station the trip does not depart from is accepted!");
+
+        // added trips may be referenced by the other disruptions, their stations have to exist
+        let added_trip = "[[added_trips]]\nid = 6\nfrom_station = 1\ndeparture = 50\nto_station = 4\narrival = 60\ncapacity = 5\nseats = 5\nfirst_class_seats = 0\n";
+        std::fs::write(scenario_filepath, format!("[[transfer_times]]\nstation = 2\nminutes = 3\n\n[[capacity_reductions]]\ntrip = 6\ncapacity = 2\n\n{}", added_trip)).unwrap();
+        let scenario = scenario::Scenario::from_file(scenario_filepath, &model).unwrap();
+        assert!(scenario.added_trips[0].id == 6 && scenario.added_trips[0].from_platform.is_none() && scenario.transfer_times[0].minutes == 3);
+
+        std::fs::write(scenario_filepath, added_trip.replace("to_station = 4", "to_station = 9")).unwrap();
+        assert!(matches!(scenario::Scenario::from_file(scenario_filepath, &model), Err(Error::InvalidConfig {file: _, message}) if message == "unknown station 9"));
+    }
+
+    /// tests that adding trips and footpaths and setting transfer times updates the model like a full rebuild
+    #[test]
+    fn validate_incremental_updates() {
+        let trip = |id, from_station, departure, to_station, arrival| trip::Trip { id, from_station, departure, to_station, arrival, capacity: 5, seats: 5, first_class_seats: 0, from_platform: None, to_platform: None };
+
+        let cost_model = cost_model::CostModel::default();
+        let path_search = small_network_path_search();
+
+        let (full, _) = small_network_model("mcfp_incremental_full");
+
+        // without the middle segment of trip 1, trip 4, the footpath 3 -> 5 and with another transfer time at station 2
+        let partial_folder_path = write_input_folder(
+            "mcfp_incremental_partial",
+            "id,transfer,name\n1,5,A\n2,1,B\n3,5,C\n4,5,D\n5,5,E\n",
+            &trips_csv(&["1,1,10,2,20,5", "1,3,32,4,40,5", "2,2,25,4,45,5", "3,5,0,3,15,5"]),
+            "from_station,to_station,duration\n2,5,3\n5,2,3\n",
+            SMALL_NETWORK_GROUPS,
+        );
+        let mut model = Model::with_stations_trips_and_footpaths(&partial_folder_path).unwrap();
+        let mut groups = model.find_paths_for_groups(&format!("{}/groups.csv", partial_folder_path), &cost_model, &path_search, 1).unwrap();
+
+        let changes = model.apply_scenario(&scenario::Scenario {
+            added_trips: vec![trip(1, 2, 22, 3, 30), trip(4, 1, 12, 3, 28)],
+            added_footpaths: vec![footpath::Footpath { from_station: 3, to_station: 5, duration: 4 }],
+            transfer_times: vec![scenario::TransferTime { station: 2, minutes: 5 }],
+            ..Default::default()
+        }).unwrap();
+
+        assert_eq!(edge_signatures(&model), edge_signatures(&full), "Incremental updates do not equal a full rebuild!");
+        for (station_id, transfers) in full.stations_transfers.iter() {
+            assert_eq!(model.stations_transfers[station_id].len(), transfers.len(), "Transfers of station {} differ!", station_id);
+            assert_eq!(model.stations_arrivals[station_id].len(), full.stations_arrivals[station_id].len(), "Arrivals of station {} differ!", station_id);
+            assert_eq!(model.stations_departures.get(station_id).map(Vec::len), full.stations_departures.get(station_id).map(Vec::len), "Departures of station {} differ!", station_id);
+        }
+        assert_trip_nodes_equal(&model, &full);
+
+        // paths of groups stay valid and are searched again if they became invalid
+        model.update_groups(&mut groups, &changes, &cost_model, &path_search);
+        assert_paths_valid(&model, &groups, &cost_model);
+
+        // removing a trip and adding it back restores the model
+        let mut changes = update::ModelChanges::default();
+        model.remove_trip(1, &mut changes);
+        model.add_trip(trip(1, 1, 10, 2, 20), &mut changes).unwrap();
+        model.add_trip(trip(1, 3, 32, 4, 40), &mut changes).unwrap();
+        model.add_trip(trip(1, 2, 22, 3, 30), &mut changes).unwrap();
+
+        assert_eq!(edge_signatures(&model), edge_signatures(&full), "Removing and adding a trip does not restore the model!");
+        assert_trip_nodes_equal(&model, &full);
+
+        // trips with an unknown station are rejected without changing the model
+        let result = model.add_trip(trip(6, 1, 50, 9, 60), &mut changes);
+        assert!(matches!(result, Err(Error::UnknownTripStation { trip_id: 6, station_id: 9 })));
+        let result = model.apply_scenario(&scenario::Scenario { added_trips: vec![trip(6, 1, 50, 9, 60)], ..Default::default() });
+        assert!(matches!(result, Err(Error::UnknownTripStation { trip_id: 6, station_id: 9 })));
+        assert_eq!(edge_signatures(&model), edge_signatures(&full), "Rejected trip changed the model!");
+
+        model.update_groups(&mut groups, &changes, &cost_model, &path_search);
+        assert_paths_valid(&model, &groups, &cost_model);
+    }
+
+    /// tests that realtime updates (JSON and GTFS-RT protobuf) shift the trips relative to the timetable like a full rebuild
+    #[test]
+    fn validate_realtime_updates() {
+        use prost::Message;
+        use realtime::gtfs_rt;
+
+        let cost_model = cost_model::CostModel::default();
+        let path_search = small_network_path_search();
+
+        let (mut model, mut groups) = small_network_model("mcfp_realtime");
+        let scheduled_times = model.scheduled_times();
+
+        // delays and cancellations of a GTFS-RT feed (with a trip that is not part of the model)
+        let stop_time_update = |stop_id: &str, departure_delay| gtfs_rt::StopTimeUpdate {
+            stop_id: Some(stop_id.to_string()),
+            departure: Some(gtfs_rt::StopTimeEvent { delay: Some(departure_delay), time: None }),
+            ..Default::default()
+        };
+        let entity = |trip_id: &str, schedule_relationship, delay, stop_time_update| gtfs_rt::FeedEntity {
+            trip_update: Some(gtfs_rt::TripUpdate {
+                trip: Some(gtfs_rt::TripDescriptor { trip_id: Some(trip_id.to_string()), schedule_relationship }),
+                stop_time_update,
+                delay,
+            }),
+            ..Default::default()
+        };
+        let feed_message = gtfs_rt::FeedMessage {
+            header: Some(gtfs_rt::FeedHeader { gtfs_realtime_version: Some("2.0".to_string()), timestamp: Some(1620000000) }),
+            entity: vec![
+                entity("4", Some(gtfs_rt::CANCELED), None, vec![]),
+                entity("3", None, Some(1190), vec![]),
+                entity("2", None, None, vec![stop_time_update("2", -600)]),
+                entity("t9", None, None, vec![stop_time_update("2", 60)]),
+            ],
+        };
+
+        let replay_folder_path = std::env::temp_dir().join("mcfp_realtime_updates");
+        std::fs::create_dir_all(&replay_folder_path).unwrap();
+        std::fs::write(replay_folder_path.join("1.json"), r#"{"trip_updates": [{"trip": 1, "stop_time_updates": [{"station": 2, "arrival_delay": 0, "departure_delay": 7}]}]}"#).unwrap();
+        std::fs::write(replay_folder_path.join("2.json"), r#"{"timestamp": 1620000000, "trip_updates": [{"trip": 1, "stop_time_updates": [{"station": 3, "arrival_delay": 3}]}]}"#).unwrap();
+        std::fs::write(replay_folder_path.join("3.pb"), feed_message.encode_to_vec()).unwrap();
+        std::fs::write(replay_folder_path.join("notes.txt"), "not an update").unwrap();
+
+        let updates = realtime::RealtimeUpdate::from_folder(replay_folder_path.to_str().unwrap()).unwrap();
+        assert_eq!(updates.len(), 3);
+        assert_eq!(updates[2].1.trip_updates.len(), 3, "Entity without numeric trip_id is not skipped!");
+
+        // trips of an equivalent full rebuild after each update (delays refer to the timetable, not to the previous update)
+        let rebuild_trip_rows = [
+            vec!["1,1,10,2,20,5", "1,2,29,3,37,5", "1,3,39,4,47,5", "2,2,25,4,45,5", "3,5,0,3,15,5", "4,1,12,3,28,5"],
+            vec!["1,1,10,2,20,5", "1,2,29,3,33,5", "1,3,35,4,43,5", "2,2,25,4,45,5", "3,5,0,3,15,5", "4,1,12,3,28,5"],
+            vec!["1,1,10,2,20,5", "1,2,29,3,33,5", "1,3,35,4,43,5", "2,2,15,4,35,5", "3,5,20,3,35,5"],
+        ];
+
+        for (update_index, (_, update)) in updates.iter().enumerate() {
+            let mut changes = update::ModelChanges::default();
+            assert!(model.apply_realtime_update(update, &scheduled_times, &mut changes) > 0);
+
+            let rebuild_folder_path = write_small_network(&format!("mcfp_realtime_rebuild_{}", update_index), &rebuild_trip_rows[update_index]);
+            let rebuild = Model::with_stations_trips_and_footpaths(&rebuild_folder_path).unwrap();
+
+            assert_eq!(edge_signatures(&model), edge_signatures(&rebuild), "Update {} does not equal a full rebuild!", update_index);
+
+            // removed edges are invalid as well
+            assert!(changes.removed_edges.iter().all(|edge| changes.invalid_edges.contains(edge)));
+
+            model.update_groups(&mut groups, &changes, &cost_model, &path_search);
+            assert_paths_valid(&model, &groups, &cost_model);
+        }
+
+        // delays never let a trip arrive before it departs
+        let mut changes = update::ModelChanges::default();
+        let update = realtime::RealtimeUpdate {
+            timestamp: None,
+            trip_updates: vec![realtime::TripUpdate {
+                trip: 1,
+                stop_time_updates: vec![realtime::StopTimeUpdate { station: 3, arrival_delay: Some(-20), departure_delay: None }],
+                ..Default::default()
+            }],
+        };
+        model.apply_realtime_update(&update, &scheduled_times, &mut changes);
+        assert!(model.graph.raw_edges().iter().filter(|edge| edge.weight.is_trip()).all(|edge| model.graph[edge.source()].time() <= model.graph[edge.target()].time()));
+
+        assert!(matches!(realtime::RealtimeUpdate::from_file(replay_folder_path.join("notes.txt").to_str().unwrap()), Err(Error::InvalidFeed {file: _, message: _})));
+
+        // replay: a group in the delayed trip 1 keeps its trip, groups stranded by cancellations are unserved (not rerouted)
+        // although the no-service path of unserved_penalty is left to them
+        let cost_model = cost_model::CostModel { unserved_penalty: Some(1000), ..Default::default() };
+        let folder_path = write_input_folder(
+            "mcfp_realtime_replay",
+            SMALL_NETWORK_STATIONS,
+            &trips_csv(&SMALL_NETWORK_TRIPS),
+            SMALL_NETWORK_FOOTPATHS,
+            "id,start,departure,destination,arrival,passengers,in_trip\n1,3,30,4,40,2,1\n2,1,0,3,40,3,\n",
+        );
+        let mut model = Model::with_stations_trips_and_footpaths(&folder_path).unwrap();
+        let mut groups = model.find_paths_for_groups(&format!("{}/groups.csv", folder_path), &cost_model, &path_search, 1).unwrap();
+
+        let replay_folder_path = std::env::temp_dir().join("mcfp_realtime_replay_updates");
+        std::fs::create_dir_all(&replay_folder_path).unwrap();
+        std::fs::write(replay_folder_path.join("1.json"), r#"{"trip_updates": [{"trip": 1, "stop_time_updates": [{"station": 2, "departure_delay": 7}]}]}"#).unwrap();
+        std::fs::write(replay_folder_path.join("2.json"), r#"{"trip_updates": [{"trip": 1, "cancelled": true}, {"trip": 4, "cancelled": true}]}"#).unwrap();
+
+        let filepath = std::env::temp_dir().join("mcfp_realtime_replay");
+        let filepath = filepath.to_str().unwrap();
+        let annealing_parameters = crate::optimization::cooling_schedule::AnnealingParameters::with_iterations(50);
+        let groups_path_index = model
+            .replay_feed(replay_folder_path.to_str().unwrap(), &mut groups, vec![0; 2], &cost_model, &path_search, &annealing_parameters, filepath, 0)
+            .unwrap();
+        assert!(groups.iter().zip(groups_path_index.iter()).all(|(group, path_index)| group.paths[*path_index].is_unserved()));
+
+        // update,group_id,passengers,broken,rerouted,unserved
+        let flagged_groups = std::fs::read_to_string(format!("{}_flagged_groups.csv", filepath)).unwrap();
+        let flags = |update_index: &str, group_id: &str| -> Option<(String, String)> {
+            flagged_groups.lines()
+                .map(|line| line.split(',').collect::<Vec<&str>>())
+                .find(|fields| fields[0] == update_index && fields[1] == group_id)
+                .map(|fields| (fields[4].to_string(), fields[5].to_string()))
+        };
+        assert_eq!(flags("0", "1"), None, "Group in the delayed trip is flagged!");
+        assert_eq!(flags("1", "1"), Some(("false".to_string(), "true".to_string())));
+        assert_eq!(flags("1", "2"), Some(("false".to_string(), "true".to_string())));
+    }
+
+    /// tests times of day with day offsets, ISO-8601 timestamps, trips crossing midnight and human-readable output times
+    #[test]
+    fn validate_multi_day_times() {
+        let reference_date = time::days_from_civil(2021, 5, 3);
+
+        assert_eq!(time::parse_time("480", None), Some(480));
+        assert_eq!(time::parse_time("08:00", None), Some(480));
+        assert_eq!(time::parse_time("08:00:59", None), Some(480));
+        assert_eq!(time::parse_time("00:10+1", None), Some(1450));
+        assert_eq!(time::parse_time("25:20:00", None), Some(1520));
+        assert_eq!(time::parse_time("2021-05-04T00:10", reference_date), Some(1450));
+        assert_eq!(time::parse_time("2021-05-04 00:10:30", reference_date), Some(1450));
+        assert_eq!(time::parse_time("2021-05-02T23:00", reference_date), None);
+        assert_eq!(time::parse_time("2021-05-04T00:10", None), None);
+        assert_eq!(time::parse_time("2021-05-04T00:10+02:00", reference_date), None);
+        assert_eq!(time::parse_time("2021-02-29T00:00", time::days_from_civil(2021, 2, 1)), None);
+        assert_eq!(time::parse_time("08:60", None), None);
+        assert_eq!(time::days_from_civil(1970, 1, 1), Some(0));
+        assert_eq!(time::days_from_civil(2000, 3, 1), Some(11017));
+
+        assert_eq!(time::format_time(480), "08:00");
+        assert_eq!(time::format_time(1450), "00:10+1");
+        assert_eq!(time::format_time(2 * time::MINUTES_PER_DAY + 61), "01:01+2");
+
+        // trip 1 crosses midnight with times of day, trip 2 with ISO-8601 timestamps (the earliest date is day 0)
+        let stations = "id,transfer,name\n1,5,A\n2,5,B\n3,5,C\n";
+        let trips = "id,from_station,departure,to_station,arrival,capacity\n\
+            1,1,23:50,2,00:10,5\n1,2,00:12,3,00:40,5\n2,3,2021-05-03T23:55,1,2021-05-04T00:20,5\n";
+        let groups_csv = "id,start,departure,destination,arrival,passengers,in_trip\n\
+            1,1,23:40,3,00:50,2,\n2,3,2021-05-03T23:50,1,2021-05-04T00:30,1,\n";
+        let folder_path = write_input_folder("mcfp_multi_day", stations, trips, "from_station,to_station,duration\n", groups_csv);
+
+        let model = Model::with_stations_trips_and_footpaths(&folder_path).unwrap();
+        assert_eq!(model.reference_date, reference_date);
+
+        let mut trip_times: Vec<(u64, u64)> = model.graph.raw_edges()
+            .iter()
+            .filter(|edge| edge.weight.is_trip())
+            .map(|edge| (model.graph[edge.source()].time(), model.graph[edge.target()].time()))
+            .collect();
+        trip_times.sort_unstable();
+        assert_eq!(trip_times, vec![(1430, 1450), (1435, 1460), (1452, 1480)]);
+
+        let cost_model = cost_model::CostModel::default();
+        let path_search = path::PathSearch::Iddfs { search_budgets: vec![30, 60], min_paths: 1 };
+        let mut groups = model.find_paths_for_groups(&format!("{}/groups.csv", folder_path), &cost_model, &path_search, 1).unwrap();
+        groups.sort_by_key(|group| group.id);
+
+        assert_eq!((groups[0].departure_time, groups[0].arrival_time), (1420, 1490));
+        assert_eq!((groups[1].departure_time, groups[1].arrival_time), (1430, 1470));
+        assert!(groups.iter().all(|group| !group.paths.is_empty()));
+        assert_eq!(groups[0].paths[0].to_string(&model.graph), "A$23:50$Departure->1$50$Trip->C$00:40+1$Arrival");
+
+        // a trip segment arriving before its departure is rejected (unless given as time of day)
+        let folder_path = write_input_folder("mcfp_multi_day_invalid", stations, "id,from_station,departure,to_station,arrival,capacity\n1,1,100,2,90,5\n", "from_station,to_station,duration\n", groups_csv);
+        match Model::with_stations_trips_and_footpaths(&folder_path) {
+            Err(Error::InvalidValue {file: _, row, column, value}) => assert!(row == 2 && column == "arrival" && value == "90"),
+            _ => panic!("Trip arriving before its departure is accepted!"),
+        }
+    }
+
+    /// tests that periodic trips are expanded into one trip per run within their validity window like explicit trips
+    #[test]
+    fn validate_periodic_trips() {
+        let stations = "id,transfer,name\n1,5,A\n2,5,B\n3,5,C\n";
+        let footpaths = "from_station,to_station,duration\n";
+        let groups_csv = "id,start,departure,destination,arrival,passengers,in_trip\n1,1,06:00,3,07:00,2,\n";
+        let periodic_trips_header = "id,from_station,departure,to_station,arrival,capacity,period,valid_from,valid_until\n";
+
+        let with_periodic_trips = |name: &str, periodic_trips: &str| {
+            let folder_path = write_input_folder(name, stations, "id,from_station,departure,to_station,arrival,capacity\n5,1,07:00,3,07:40,5\n", footpaths, groups_csv);
+            std::fs::write(format!("{}/periodic_trips.csv", folder_path), format!("{}{}", periodic_trips_header, periodic_trips)).unwrap();
+            Model::with_stations_trips_and_footpaths(&folder_path)
+        };
+
+        // pattern 1 runs hourly (before and after the given run), pattern 2 every 30 minutes across midnight
+        let model = with_periodic_trips(
+            "mcfp_periodic",
+            "1,1,07:10,2,07:25,5,60,06:00,08:30\n1,2,07:27,3,07:45,5,60,06:00,08:30\n2,3,23:30,1,00:10,5,30,23:00,24:00\n",
+        ).unwrap();
+
+        let explicit_trips = "id,from_station,departure,to_station,arrival,capacity\n5,1,420,3,460,5\n\
+            6,1,370,2,385,5\n6,2,387,3,405,5\n7,1,430,2,445,5\n7,2,447,3,465,5\n8,1,490,2,505,5\n8,2,507,3,525,5\n\
+            9,3,1380,1,1420,5\n10,3,1410,1,1450,5\n11,3,1440,1,1480,5\n";
+        let explicit_folder_path = write_input_folder("mcfp_periodic_explicit", stations, explicit_trips, footpaths, groups_csv);
+        let explicit = Model::with_stations_trips_and_footpaths(&explicit_folder_path).unwrap();
+
+        assert_eq!(edge_signatures(&model), edge_signatures(&explicit), "Expanded periodic trips do not equal explicit trips!");
+
+        // derived trip ids are traceable to their pattern and run
+        assert_eq!(model.trip_patterns.len(), 6);
+        assert_eq!(model.trip_patterns[&6], periodic_trip::TripPattern { pattern: 1, run: 0 });
+        assert_eq!(model.trip_patterns[&8], periodic_trip::TripPattern { pattern: 1, run: 2 });
+        assert_eq!(model.trip_patterns[&11], periodic_trip::TripPattern { pattern: 2, run: 2 });
+        assert!(!model.trip_patterns.contains_key(&5));
+
+        let filepath = std::env::temp_dir().join("mcfp_trip_patterns.csv");
+        let filepath = filepath.to_str().unwrap();
+        model.save_trip_patterns_to_csv(filepath);
+        let rows = std::fs::read_to_string(filepath).unwrap();
+        assert_eq!(rows.lines().nth(1).unwrap(), "6|1|0");
+
+        // pattern ids are unique, periods positive and all fractions of a pattern share the validity window
+        match with_periodic_trips("mcfp_periodic_duplicate", "5,1,07:10,2,07:25,5,60,06:00,08:30\n") {
+            Err(Error::DuplicateId {file, row, id}) => assert!(file.ends_with("periodic_trips.csv") && row == 2 && id == 5),
+            _ => panic!("Pattern id of trips.csv is accepted!"),
+        }
+        match with_periodic_trips("mcfp_periodic_period", "1,1,07:10,2,07:25,5,0,06:00,08:30\n") {
+            Err(Error::InvalidValue {file: _, row, column, value: _}) => assert!(row == 2 && column == "period"),
+            _ => panic!("Period 0 is accepted!"),
+        }
+        match with_periodic_trips("mcfp_periodic_window", "1,1,07:10,2,07:25,5,60,06:00,08:30\n1,2,07:27,3,07:45,5,60,06:00,09:30\n") {
+            Err(Error::InvalidValue {file: _, row, column, value}) => assert!(row == 3 && column == "valid_until" && value == "09:30"),
+            _ => panic!("Fractions of a pattern with different validity windows are accepted!"),
+        }
+    }
+
+    /// tests that transfer rules of trips and platforms replace the transfer time of a station and that guaranteed connections are held
+    #[test]
+    fn validate_transfer_rules() {
+        let stations = "id,transfer,name\n1,5,A\n2,5,B\n3,5,C\n";
+        let trips = "id,from_station,departure,to_station,arrival,capacity,from_platform,to_platform\n\
+            1,1,10,2,20,5,,1\n2,2,22,3,40,5,1,\n3,2,24,3,40,5,2,\n4,2,26,3,40,5,1,\n5,2,30,3,45,5,,\n";
+        let footpaths = "from_station,to_station,duration\n";
+        let groups_csv = "id,start,departure,destination,arrival,passengers,in_trip\n1,1,0,3,60,2,\n";
+        let transfers_header = "station,from_trip,to_trip,from_platform,to_platform,duration,guaranteed\n";
+
+        let with_transfers = |name: &str, transfers: &str| {
+            let folder_path = write_input_folder(name, stations, trips, footpaths, groups_csv);
+            std::fs::write(format!("{}/transfers.csv", folder_path), format!("{}{}", transfers_header, transfers)).unwrap();
+            Model::with_stations_trips_and_footpaths(&folder_path)
+        };
+
+        // trips departing at station 2 that can be boarded after the arrival of trip 1 (by Alight, WaitAtStation and Board edges)
+        let boardable_trips = |model: &Model| {
+            let arrival = model.trip_nodes(1).into_iter().find(|node| model.graph[*node].is_arrival()).unwrap();
+            let mut transfers: Vec<NodeIndex> = model.graph.edges_directed(arrival, Outgoing).filter(|edge| edge.weight().is_alight()).map(|edge| edge.target()).collect();
+            let mut trip_ids = HashSet::new();
+
+            while let Some(transfer) = transfers.pop() {
+                for edge in model.graph.edges_directed(transfer, Outgoing) {
+                    match edge.weight() {
+                        TimetableEdge::Board => { trip_ids.insert(model.graph[edge.target()].trip_id().unwrap()); },
+                        _ => transfers.push(edge.target()),
+                    }
+                }
+            }
+
+            let mut trip_ids: Vec<u64> = trip_ids.into_iter().collect();
+            trip_ids.sort_unstable();
+            trip_ids
+        };
+
+        let without_rules = with_transfers("mcfp_transfers_none", "").unwrap();
+        assert_eq!(boardable_trips(&without_rules), vec![4, 5]);
+        assert!(without_rules.direct_transfers.is_empty());
+
+        // 2 minutes between trains at platform 1, 8 minutes from trip 1 to trip 4, 3 minutes from trip 1 to trip 5 (guaranteed)
+        let mut model = with_transfers("mcfp_transfers_rules", "2,,,1,1,2,\n2,1,4,,,8,\n2,1,5,,,3,1\n").unwrap();
+        assert_eq!(boardable_trips(&model), vec![2, 5]);
+        assert_eq!(model.direct_transfers.len(), 1, "Only trip 2 is boarded directly!");
+        assert_eq!(model.graph.node_count(), without_rules.graph.node_count() + 1);
+
+        // trip 5 waits for trip 1 arriving at 35
+        let mut changes = update::ModelChanges::default();
+        model.delay_trip(1, None, 15, &mut changes);
+        assert_eq!(model.hold_guaranteed_connections(&mut changes), 1);
+
+        let trip_5_times: Vec<u64> = model.trip_nodes(5).into_iter().map(|node| model.graph[node].time()).collect();
+        assert_eq!(trip_5_times, vec![38, 53]);
+        assert_eq!(boardable_trips(&model), vec![5]);
+        assert_eq!(model.hold_guaranteed_connections(&mut changes), 0);
+
+        // guaranteed connections need both trips, trips have to exist
+        match with_transfers("mcfp_transfers_guaranteed", "2,1,,,,3,1\n") {
+            Err(Error::InvalidValue {file: _, row, column, value: _}) => assert!(row == 2 && column == "guaranteed"),
+            _ => panic!("Guaranteed connection without to_trip is accepted!"),
+        }
+        match with_transfers("mcfp_transfers_unknown_trip", "2,1,9,,,3,\n") {
+            Err(Error::InvalidValue {file: _, row, column, value}) => assert!(row == 2 && column == "to_trip" && value == "9"),
+            _ => panic!("Transfer rule of an unknown trip is accepted!"),
+        }
+        match with_transfers("mcfp_transfers_unknown_station", "7,1,5,,,3,\n") {
+            Err(Error::UnknownStation {file: _, row, station_id}) => assert!(row == 2 && station_id == 7),
+            _ => panic!("Transfer rule at an unknown station is accepted!"),
+        }
+    }
+
+    /// tests the staged utilization cost of seated, standing and overloaded passengers by class and the input of seats and classes
+    #[test]
+    fn validate_capacity_tiers() {
+        let cost_model = cost_model::CostModel::default();
+        let edge = TimetableEdge::Trip { duration: 4, capacity: 10, seats: 6, first_class_seats: 2 };
+
+        // second class passengers never take first class seats, first class passengers take any seat
+        assert_eq!((edge.seated(8, 0), edge.seated(8, 2), edge.seated(8, 8), edge.seated(3, 3)), (4, 6, 6, 3));
+        assert_eq!(edge.utilization_cost_by_class(&cost_model, 4, 0), 0);
+        assert_eq!(edge.utilization_cost_by_class(&cost_model, 8, 0), 4);
+        assert_eq!(edge.utilization_cost_by_class(&cost_model, 8, 2), 2);
+        assert_eq!(edge.utilization_cost_by_class(&cost_model, 13, 0), 6 + 9);
+        assert_eq!(edge.utilization_cost_with(&cost_model, 8), 2);
+
+        let stations = "id,transfer,name\n1,5,A\n2,5,B\n";
+        let footpaths = "from_station,to_station,duration\n";
+        let groups = "id,start,departure,destination,arrival,passengers,in_trip,class\n1,1,0,2,30,2,,1\n2,1,0,2,30,1,,\n";
+
+        let folder_path = write_input_folder("mcfp_capacity_tiers", stations, "id,from_station,departure,to_station,arrival,capacity,seats,first_class_seats\n1,1,10,2,20,5,3,1\n", footpaths, groups);
+        let model = Model::with_stations_trips_and_footpaths(&folder_path).unwrap();
+        let trip_edge = model.graph.edge_indices().find(|edge| model.graph[*edge].is_trip()).unwrap();
+        assert_eq!((model.graph[trip_edge].capacity(), model.graph[trip_edge].seats(), model.graph[trip_edge].first_class_seats()), (5, 3, 1));
+
+        let path_search = path::PathSearch::Iddfs { search_budgets: vec![30], min_paths: 1 };
+        let mut groups = model.find_paths_for_groups(&format!("{}/groups.csv", folder_path), &cost_model, &path_search, 1).unwrap();
+        groups.sort_by_key(|group| group.id);
+        let first_classes: Vec<bool> = groups.iter().map(|group| group.paths[0].is_first_class()).collect();
+        assert_eq!(first_classes, vec![true, false]);
+
+        // seats are limited by the capacity, first class seats by the seats, classes are 1 or 2
+        for (trips, column) in [
+            ("id,from_station,departure,to_station,arrival,capacity,seats\n1,1,10,2,20,5,6\n", "seats"),
+            ("id,from_station,departure,to_station,arrival,capacity,seats,first_class_seats\n1,1,10,2,20,5,3,4\n", "first_class_seats"),
+        ].iter() {
+            let folder_path = write_input_folder("mcfp_capacity_tiers_invalid", stations, trips, footpaths, "");
+            match Model::with_stations_trips_and_footpaths(&folder_path) {
+                Err(Error::InvalidValue {file: _, row, column: invalid_column, value: _}) => assert!(row == 2 && invalid_column == *column),
+                _ => panic!("Invalid {} are accepted!", column),
+            }
+        }
+
+        let folder_path = write_input_folder("mcfp_capacity_tiers_class", stations, "id,from_station,departure,to_station,arrival,capacity\n1,1,10,2,20,5\n", footpaths,
+            "id,start,departure,destination,arrival,passengers,in_trip,class\n1,1,0,2,30,2,,3\n");
+        let model = Model::with_stations_trips_and_footpaths(&folder_path).unwrap();
+        match model.find_paths_for_groups(&format!("{}/groups.csv", folder_path), &cost_model, &path_search, 1) {
+            Err(Error::InvalidValue {file: _, row, column, value}) => assert!(row == 2 && column == "class" && value == "3"),
+            _ => panic!("Invalid class is accepted!"),
+        }
+
+        // standing must not cost more than the first passenger over capacity
+        let filepath = std::env::temp_dir().join("mcfp_capacity_tiers_cost_model.toml");
+        std::fs::write(&filepath, "standing_penalty = 2\n").unwrap();
+        match cost_model::CostModel::from_file(filepath.to_str().unwrap()) {
+            Err(Error::InvalidConfig { file: _, message: _ }) => (),
+            result => panic!("expected InvalidConfig, got {:?}", result),
+        }
+
+        // a free overload penalty requires lowering the default standing_penalty as well
+        std::fs::write(&filepath, "[overload_penalty]\nkind = \"quadratic\"\nfactor = 0\n").unwrap();
+        match cost_model::CostModel::from_file(filepath.to_str().unwrap()) {
+            Err(Error::InvalidConfig { file: _, message }) => assert!(message.contains("the default") && message.ends_with("at most 0"), "{}", message),
+            result => panic!("expected InvalidConfig, got {:?}", result),
+        }
+
+        std::fs::write(&filepath, "standing_penalty = 0\n[overload_penalty]\nkind = \"quadratic\"\nfactor = 0\n").unwrap();
+        assert!(cost_model::CostModel::from_file(filepath.to_str().unwrap()).is_ok());
+    }
+
+    /// tests that the path search only returns paths satisfying the constraints of the groups
+    #[test]
+    fn validate_group_constraints() {
+        let stations = "id,transfer,name,step_free\n1,5,A,\n2,5,B,1\n3,5,C,0\n4,5,D,1\n5,5,E,\n";
+        let trips = "id,from_station,departure,to_station,arrival,capacity\n\
+            1,1,10,2,20,5\n2,2,30,4,40,5\n3,1,10,3,20,5\n4,3,30,4,45,5\n5,1,50,4,90,5\n";
+        let footpaths = "from_station,to_station,duration\n2,3,2\n";
+        let groups_header = "id,start,departure,destination,arrival,passengers,in_trip,max_transfers,max_walking,avoid_stations,step_free,latest_arrival\n";
+        let groups = "1,1,0,4,40,1,,,,,,\n2,1,0,4,40,1,,0,,,,\n3,1,0,4,40,1,,,,2;5,,\n4,1,0,4,40,1,,,,,1,\n\
+            5,1,0,4,40,1,,,,,,45\n6,1,0,4,40,1,,0,,,,60\n7,1,0,4,40,1,,,1,,,\n";
+
+        let folder_path = write_input_folder("mcfp_group_constraints", stations, trips, footpaths, &format!("{}{}", groups_header, groups));
+        let model = Model::with_stations_trips_and_footpaths(&folder_path).unwrap();
+        assert_eq!(model.inaccessible_stations, [3].iter().copied().collect());
+
+        let path_search = path::PathSearch::Iddfs { search_budgets: vec![200], min_paths: 1 };
+        let mut groups = model.find_paths_for_groups(&format!("{}/groups.csv", folder_path), &cost_model::CostModel::default(), &path_search, 1).unwrap();
+        groups.sort_by_key(|group| group.id);
+
+        // trips of each path of each group
+        let paths_trips = |paths: Vec<Vec<EdgeIndex>>| {
+            let mut paths_trips: Vec<Vec<u64>> = paths
+                .iter()
+                .map(|edges| edges.iter().filter(|edge| model.graph[**edge].is_trip()).map(|edge| model.graph[model.graph.edge_endpoints(*edge).unwrap().0].trip_id().unwrap()).collect())
+                .collect();
+            paths_trips.sort();
+            paths_trips
+        };
+        let groups_trips: Vec<Vec<Vec<u64>>> = groups
+            .iter()
+            .map(|group| paths_trips(group.paths.iter().map(|path| path.edges.iter().copied().collect()).collect()))
+            .collect();
+
+        assert_eq!(groups_trips[0], vec![vec![1, 2], vec![1, 4], vec![3, 4], vec![5]]);
+        assert_eq!(groups_trips[1], vec![vec![5]], "Path exceeds max_transfers!");
+        assert_eq!(groups_trips[2], vec![vec![3, 4], vec![5]], "Path stops at an avoided station!");
+        assert_eq!(groups_trips[3], vec![vec![1, 2], vec![5]], "Path boards at a station without step-free access!");
+        assert_eq!(groups_trips[4], vec![vec![1, 2], vec![1, 4], vec![3, 4]], "Path arrives after latest_arrival!");
+        assert!(groups_trips[5].is_empty() && groups[5].unserved_reason.is_some(), "Group with unsatisfiable constraints is served!");
+        assert_eq!(groups_trips[6], vec![vec![1, 2], vec![3, 4], vec![5]], "Path exceeds max_walking!");
+
+        // k shortest paths and pareto search skip inadmissible paths instead of returning none
+        let cost_model = cost_model::CostModel::default();
+        let start = model.stations_transfers[&1][0];
+        assert_eq!(paths_trips(k_shortest_paths::k_shortest_paths(&model.graph, &cost_model, start, 4, 1, 200, &groups[1].constraints, &model.inaccessible_stations)), vec![vec![5]]);
+        assert_eq!(paths_trips(k_shortest_paths::k_shortest_paths(&model.graph, &cost_model, start, 4, 2, 200, &groups[2].constraints, &model.inaccessible_stations)), vec![vec![3, 4], vec![5]]);
+        assert_eq!(paths_trips(pareto_paths::pareto_paths(&model.graph, &cost_model, start, 4, 200, &groups[1].constraints, &model.inaccessible_stations)), vec![vec![5]]);
+        assert_eq!(paths_trips(pareto_paths::pareto_paths(&model.graph, &cost_model, start, 4, 200, &groups[4].constraints, &model.inaccessible_stations)), vec![vec![1, 2]]);
+
+        // the depth-first search with visitors counts the transfers of the path so far: boarding trip 3 at station 3 after trip 1
+        // is pruned, so the departure stays undiscovered for the direct path in trip 3
+        let dfs_folder_path = write_input_folder(
+            "mcfp_group_constraints_dfs",
+            "id,transfer,name\n1,5,A\n3,5,C\n4,5,D\n",
+            "id,from_station,departure,to_station,arrival,capacity\n1,1,10,3,15,5\n3,1,5,3,20,5\n3,3,25,4,35,5\n",
+            "from_station,to_station,duration\n",
+            &format!("{}{}", groups_header, "1,1,0,4,35,1,,0,,,,\n"),
+        );
+        let dfs_model = Model::with_stations_trips_and_footpaths(&dfs_folder_path).unwrap();
+        let dfs_groups = dfs_model.find_paths_for_groups(&format!("{}/groups.csv", dfs_folder_path), &cost_model, &path_search, 1).unwrap();
+        let dfs_start = dfs_model.stations_transfers[&1][0];
+        let paths = path::Path::dfs_visitor_search(&dfs_model.graph, &cost_model, dfs_start, 4, 1, 35, &dfs_groups[0].constraints, &dfs_model.inaccessible_stations, 0);
+        assert!(paths.len() == 1 && paths[0].get_transfers(&dfs_model.graph) == 0, "Depth-first search does not prune by the transfers of the path!");
+
+        for path_search in [path::PathSearch::KShortestPaths { k: 1 }, path::PathSearch::Pareto].iter() {
+            let mut other_groups = model.find_paths_for_groups(&format!("{}/groups.csv", folder_path), &cost_model, path_search, 1).unwrap();
+            other_groups.sort_by_key(|group| group.id);
+
+            for (group, group_trips) in other_groups.iter().zip(groups_trips.iter()) {
+                let trips = paths_trips(group.paths.iter().map(|path| path.edges.iter().copied().collect()).collect());
+                assert_eq!(trips.is_empty(), group_trips.is_empty(), "{:?} serves group {} differently!", path_search, group.id);
+                assert!(trips.iter().all(|trips| group_trips.contains(trips)), "{:?} finds an inadmissible path!", path_search);
+            }
+        }
+
+        // avoided stations must exist and differ from start and destination, step_free is boolean
+        for (groups, expected) in [
+            ("1,1,0,4,40,1,,,,4,,\n", "avoid_stations"),
+            ("1,1,0,4,40,1,,,,,yes,\n", "step_free"),
+        ].iter() {
+            std::fs::write(format!("{}/groups.csv", folder_path), format!("{}{}", groups_header, groups)).unwrap();
+            match model.find_paths_for_groups(&format!("{}/groups.csv", folder_path), &cost_model::CostModel::default(), &path_search, 1) {
+                Err(Error::InvalidValue {file: _, row, column, value: _}) => assert!(row == 2 && column == *expected),
+                _ => panic!("Invalid {} is accepted!", expected),
+            }
+        }
+        std::fs::write(format!("{}/groups.csv", folder_path), format!("{}{}", groups_header, "1,1,0,4,40,1,,,,7,,\n")).unwrap();
+        match model.find_paths_for_groups(&format!("{}/groups.csv", folder_path), &cost_model::CostModel::default(), &path_search, 1) {
+            Err(Error::UnknownStation {file: _, row, station_id}) => assert!(row == 2 && station_id == 7),
+            _ => panic!("Unknown avoided station is accepted!"),
+        }
+    }
+
+    /// tests the import of a GTFS static feed (service day filtering, stop_times to trip segments, transfers, capacities)
+    #[test]
+    fn validate_gtfs_import() {
+        let folder_path = std::env::temp_dir().join("mcfp_gtfs_feed");
+        std::fs::create_dir_all(&folder_path).unwrap();
+
+        std::fs::write(folder_path.join("stops.txt"), "stop_id,stop_name\n1,A\nB,B\n3,C\n").unwrap();
+        std::fs::write(folder_path.join("trips.txt"), "route_id,service_id,trip_id\nr1,WD,t1\nr2,WE,t2\n").unwrap();
+        std::fs::write(folder_path.join("stop_times.txt"), "trip_id,arrival_time,departure_time,stop_id,stop_sequence\n\
+            t1,25:20:00,25:20:00,3,3\nt1,08:00:00,08:00:30,1,1\nt1,08:10:00,08:12:00,B,2\nt2,09:00:00,09:00:00,1,1\nt2,09:30:00,09:30:00,3,2\n").unwrap();
+        std::fs::write(folder_path.join("calendar.txt"), "service_id,monday,tuesday,wednesday,thursday,friday,saturday,sunday,start_date,end_date\n\
+            WD,1,1,1,1,1,0,0,20210101,20211231\nWE,0,0,0,0,0,1,1,20210101,20211231\n").unwrap();
+        std::fs::write(folder_path.join("calendar_dates.txt"), "service_id,date,exception_type\nWD,20210105,2\n").unwrap();
+        std::fs::write(folder_path.join("transfers.txt"), "from_stop_id,to_stop_id,transfer_type,min_transfer_time\n1,1,2,300\n1,3,2,90\n3,1,3,\n3,3,1,\nB,1,2,\n").unwrap();
+        std::fs::write(folder_path.join("capacities.csv"), "route_id,capacity\nr1,80\n").unwrap();
+
+        let gtfs_folder_path = folder_path.to_str().unwrap();
+        let mut config = gtfs::GtfsConfig {
+            service_date: 20210104, // monday
+            default_capacity: 100,
+            default_transfer_time: 2,
+            capacities_filepath: Some(format!("{}/capacities.csv", gtfs_folder_path)),
+        };
+
+        let (stations, trips, footpaths) = gtfs::read_feed(gtfs_folder_path, &config).unwrap();
+
+        // non-numeric stop_id is numbered after the highest numeric one
+        assert_eq!(stations[&4].name, "B");
+        assert_eq!(stations[&1].transfer_time, 5);
+        // transfer without min_transfer_time keeps the default transfer time
+        assert_eq!(stations[&3].transfer_time, 2);
+
+        // only t1 is running, its stop_times are ordered by stop_sequence
+        assert_eq!(trips.len(), 2);
+        assert!(trips.iter().all(|trip| trip.id == trips[0].id && trip.capacity == 80));
+        assert_eq!((trips[0].from_station, trips[0].departure, trips[0].to_station, trips[0].arrival), (1, 480, 4, 490));
+        assert_eq!((trips[1].from_station, trips[1].departure, trips[1].to_station, trips[1].arrival), (4, 492, 3, 1520));
+
+        // transfer between different stops becomes a footpath (with the default transfer time without min_transfer_time), transfer_type 3 is skipped
+        assert_eq!(footpaths.len(), 2);
+        assert_eq!((footpaths[0].from_station, footpaths[0].to_station, footpaths[0].duration), (1, 3, 2));
+        assert_eq!((footpaths[1].from_station, footpaths[1].to_station, footpaths[1].duration), (4, 1, 2));
+
+        // service of t1 is removed on tuesday
+        config.service_date = 20210105;
+        let (_, trips, _) = gtfs::read_feed(gtfs_folder_path, &config).unwrap();
+        assert!(trips.is_empty());
+
+        // only t2 is running on saturday, without capacity entry
+        config.service_date = 20210109;
+        let model = Model::with_gtfs_feed(gtfs_folder_path, &config).unwrap();
+        let trip_edges: Vec<&TimetableEdge> = model.graph.raw_edges().iter().map(|edge| &edge.weight).filter(|edge| edge.is_trip()).collect();
+        assert_eq!(trip_edges.len(), 1);
+        assert_eq!((trip_edges[0].duration(), trip_edges[0].capacity()), (30, 100));
+
+        // service dates that do not exist are rejected, year 0 is a valid (proleptic) year
+        for service_date in [0, 20211301, 20210230].iter() {
+            config.service_date = *service_date;
+            assert!(matches!(gtfs::read_feed(gtfs_folder_path, &config), Err(Error::InvalidConfig { .. })));
+        }
+        config.service_date = 101;
+        assert!(gtfs::read_feed(gtfs_folder_path, &config).unwrap().1.is_empty());
+
+        // distinct stop_ids with the same numeric value are all numbered in order of records instead of being merged
+        std::fs::write(folder_path.join("stops.txt"), "stop_id,stop_name\n7,A\n007,B\n3,C\n").unwrap();
+        std::fs::write(folder_path.join("stop_times.txt"), "trip_id,arrival_time,departure_time,stop_id,stop_sequence\n\
+            t1,08:00:00,08:00:00,7,1\nt1,08:10:00,08:10:00,007,2\nt1,08:20:00,08:20:00,3,3\n").unwrap();
+        std::fs::write(folder_path.join("transfers.txt"), "from_stop_id,to_stop_id,transfer_type,min_transfer_time\n").unwrap();
+        config.service_date = 20210104;
+        let (stations, trips, _) = gtfs::read_feed(gtfs_folder_path, &config).unwrap();
+        assert_eq!((stations[&0].name.as_str(), stations[&1].name.as_str(), stations[&2].name.as_str()), ("A", "B", "C"));
+        assert_eq!(trips.iter().map(|trip| (trip.from_station, trip.to_station)).collect::<Vec<_>>(), vec![(0, 1), (1, 2)]);
+    }
+
+    /// tests that the k shortest paths are the k cheapest of all (exhaustively enumerated) paths
+    #[test]
+    fn validate_k_shortest_paths() {
+        let model = Model::with_stations_trips_and_footpaths("sample_data").unwrap();
+        let cost_model = cost_model::CostModel::default();
+
+        let start = *model.stations_transfers[&1].iter().find(|transfer| model.graph[**transfer].time() >= 100).unwrap();
+
+        let mut all_paths = enumerate_all_paths(&model.graph, &cost_model, start, 5, 240);
+        all_paths.sort();
+        assert!(all_paths.len() > 3, "Not enough paths in sample data!");
+
+        let k = all_paths.len() - 2;
+        let paths = k_shortest_paths::k_shortest_paths(&model.graph, &cost_model, start, 5, k, 240, &constraints::GroupConstraints::default(), &model.inaccessible_stations);
+        assert_eq!(paths.len(), k);
+
+        let costs: Vec<u64> = paths.iter().map(|edges| edges.iter().map(|edge| model.graph[*edge].travel_cost(&cost_model)).sum()).collect();
+        assert_eq!(costs, all_paths.iter().take(k).map(|(cost, _)| *cost).collect::<Vec<u64>>());
+
+        // all found paths are distinct and exist
+        let distinct_paths: HashSet<&Vec<EdgeIndex>> = paths.iter().collect();
+        assert_eq!(distinct_paths.len(), k);
+        assert!(paths.iter().all(|edges| all_paths.iter().any(|(_, other_edges)| edges == other_edges)));
+
+        // no paths are requested
+        assert!(k_shortest_paths::k_shortest_paths(&model.graph, &cost_model, start, 5, 0, 240, &constraints::GroupConstraints::default(), &model.inaccessible_stations).is_empty());
+
+        // more paths than existing are limited to all paths
+        assert_eq!(k_shortest_paths::k_shortest_paths(&model.graph, &cost_model, start, 5, 1000, 240, &constraints::GroupConstraints::default(), &model.inaccessible_stations).len(), all_paths.len());
+    }
+
+    /// tests that the pareto search returns exactly the Pareto set of all (exhaustively enumerated) paths
+    #[test]
+    fn validate_pareto_paths() {
+        let stations = std::fs::read_to_string("sample_data/stations.csv").unwrap();
+        // trip 8 creates transfers at station 5, so that it can be reached by foot
+        let trips = std::fs::read_to_string("sample_data/trips.csv").unwrap() + "\n8,00000005,170,00000001,200,5\n";
+        let footpaths = "from_station,to_station,duration\n00000007,00000006,3\n00000008,00000005,30\n00000003,00000009,20\n";
+        let folder_path = write_input_folder("mcfp_pareto_paths", &stations, &trips, footpaths, "");
+
+        let model = Model::with_stations_trips_and_footpaths(&folder_path).unwrap();
+        let cost_model = cost_model::CostModel::default();
+        let start = *model.stations_transfers[&1].iter().find(|transfer| model.graph[**transfer].time() >= 100).unwrap();
+
+        // (arrival time, transfers, walking time) of a path
+        let criteria = |edges: &Vec<EdgeIndex>| {
+            let path = path::Path::new(&model.graph, &cost_model, edges.clone(), 1, 0);
+            let destination = model.graph.edge_endpoints(*edges.last().unwrap()).unwrap().1;
+            (model.graph[destination].time(), path.get_transfers(&model.graph), path.get_walking_time(&model.graph))
+        };
+
+        let all_criteria: Vec<(u64, u64, u64)> = enumerate_all_paths(&model.graph, &cost_model, start, 5, 240)
+            .iter()
+            .map(|(_, edges)| criteria(edges))
+            .collect();
+        let mut pareto_criteria: Vec<(u64, u64, u64)> = all_criteria
+            .iter()
+            .filter(|(time, transfers, walking_time)| !all_criteria.iter().any(|(other_time, other_transfers, other_walking_time)| {
+                other_time <= time && other_transfers <= transfers && other_walking_time <= walking_time
+                    && (other_time, other_transfers, other_walking_time) != (time, transfers, walking_time)
+            }))
+            .cloned()
+            .collect();
+        pareto_criteria.sort();
+        pareto_criteria.dedup();
+        assert!(pareto_criteria.len() > 2, "Pareto set of sample data is too small!");
+        assert!(pareto_criteria.iter().any(|(_, _, walking_time)| *walking_time > 0), "No walking in Pareto set!");
+
+        let paths = pareto_paths::pareto_paths(&model.graph, &cost_model, start, 5, 240, &constraints::GroupConstraints::default(), &model.inaccessible_stations);
+        let found_criteria: Vec<(u64, u64, u64)> = paths.iter().map(criteria).collect();
+        assert_eq!(found_criteria, pareto_criteria);
+    }
+
+    /// returns (travel_cost, edges) of all paths from start to the first node at destination station within max_duration
+    fn enumerate_all_paths(
+        graph: &DiGraph<TimetableNode, TimetableEdge>,
+        cost_model: &cost_model::CostModel,
+        start: NodeIndex,
+        destination_station_id: u64,
+        max_duration: u64,
+    ) -> Vec<(u64, Vec<EdgeIndex>)> {
+        let max_time = graph[start].time() + max_duration;
+
+        let mut all_paths = Vec::new();
+        let mut stack = vec![(start, 0, Vec::new())];
+        while let Some((node, cost, edges)) = stack.pop() {
+            if graph[node].station_id() == destination_station_id {
+                all_paths.push((cost, edges));
+                continue;
+            }
+
+            for edge in graph.edges(node) {
+                if graph[edge.target()].time() <= max_time {
+                    let mut next_edges = edges.clone();
+                    next_edges.push(edge.id());
+                    stack.push((edge.target(), cost + edge.weight().travel_cost(cost_model), next_edges));
+                }
+            }
+        }
+
+        all_paths
+    }
+
+    /// tests loading cost models from TOML and JSON files and their penalty functions
+    #[test]
+    fn validate_cost_model_files() {
+        let folder_path = std::env::temp_dir().join("mcfp_cost_model");
+        std::fs::create_dir_all(&folder_path).unwrap();
+
+        // defaults reproduce the original costs
+        let default = cost_model::CostModel::default();
+        assert_eq!(TimetableEdge::Trip { duration: 4, capacity: 10, seats: 10, first_class_seats: 0 }.travel_cost(&default), 1);
+        assert_eq!(TimetableEdge::Alight { duration: 0 }.travel_cost(&default), 6);
+        assert_eq!(TimetableEdge::WaitAtStation { duration: 7 }.travel_cost(&default), 7);
+        assert_eq!(TimetableEdge::Trip { duration: 4, capacity: 10, seats: 10, first_class_seats: 0 }.utilization_cost_with(&default, 13), 9);
+        assert_eq!(default.delay_cost(-3), -3);
+
+        let toml_filepath = folder_path.join("cost_model.toml");
+        std::fs::write(&toml_filepath, "transfer_penalty = 10\n\
+            [walk]\nper_minute = 2\n\
+            [delay]\nearly = 0\nlate = 3\n\
+            [overload_penalty]\nkind = \"piecewise\"\nsegments = [{ from = 0, slope = 1 }, { from = 5, slope = 4 }]\n").unwrap();
+        let cost_model = cost_model::CostModel::from_file(toml_filepath.to_str().unwrap()).unwrap();
+        assert_eq!(TimetableEdge::Walk { duration: 3 }.travel_cost(&cost_model), 16);
+        assert_eq!(TimetableEdge::WaitAtStation { duration: 7 }.travel_cost(&cost_model), 7);
+        assert_eq!((cost_model.delay_cost(-3), cost_model.delay_cost(2)), (0, 6));
+        assert_eq!(cost_model.overload_penalty.cost(3), 3);
+        assert_eq!(cost_model.overload_penalty.cost(7), 5 + 8);
+
+        let json_filepath = folder_path.join("cost_model.json");
+        std::fs::write(&json_filepath, r#"{"trip_inverse_duration": 0, "overload_penalty": {"kind": "linear", "factor": 7}}"#).unwrap();
+        let cost_model = cost_model::CostModel::from_file(json_filepath.to_str().unwrap()).unwrap();
+        assert_eq!(TimetableEdge::Trip { duration: 4, capacity: 10, seats: 10, first_class_seats: 0 }.travel_cost(&cost_model), 0);
+        assert_eq!(cost_model.overload_penalty.cost(3), 21);
+
+        // unknown fields and unordered or non-convex segments are rejected
+        for content in [
+            "transfer_penaltyy = 1\n",
+            "[overload_penalty]\nkind = \"piecewise\"\nsegments = []\n",
+            "[overload_penalty]\nkind = \"piecewise\"\nsegments = [{ from = 5, slope = 1 }, { from = 0, slope = 4 }]\n",
+            "[overload_penalty]\nkind = \"piecewise\"\nsegments = [{ from = 0, slope = 4 }, { from = 5, slope = 1 }]\n",
+        ].iter() {
+            std::fs::write(&toml_filepath, content).unwrap();
+            match cost_model::CostModel::from_file(toml_filepath.to_str().unwrap()) {
+                Err(Error::InvalidConfig { file, message: _ }) => assert_eq!(file, toml_filepath.to_str().unwrap()),
+                result => panic!("expected InvalidConfig for {:?}, got {:?}", content, result),
+            }
+        }
+    }
+
     /// tests the integrity of the paths of the groups
     #[test]
     fn validate_groups_paths_integrity() {
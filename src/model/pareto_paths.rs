@@ -0,0 +1,178 @@
+use std::{cmp::Reverse, collections::{BinaryHeap, HashMap, HashSet}};
+
+use petgraph::{
+    graph::{DiGraph, EdgeIndex, NodeIndex},
+    visit::EdgeRef,
+};
+
+use super::{
+    constraints::GroupConstraints,
+    cost_model::CostModel,
+    graph_weight::{TimetableEdge, TimetableNode},
+};
+
+/// label of a partial path in the multi-criteria search
+struct Label {
+    node: NodeIndex,
+    boardings: u64,     // number of Board edges
+    walking_time: u64,  // summed duration of Walk edges
+    travel_cost: u64,   // tie-breaker of labels with equal criteria
+    predecessor: Option<(usize, EdgeIndex)>, // (label index, edge) this label was extended from
+    alive: bool,        // false if dominated by a later label at the same node
+}
+
+impl Label {
+
+    /// returns true if self is at least as good as other in all criteria (equal criteria: not more expensive)
+    fn dominates(&self, other: &Self) -> bool {
+        self.boardings <= other.boardings
+            && self.walking_time <= other.walking_time
+            && (self.boardings < other.boardings || self.walking_time < other.walking_time || self.travel_cost <= other.travel_cost)
+    }
+}
+
+/// McRAPTOR-like multi-criteria search on the time-expanded graph
+///
+/// returns the Pareto set of paths from start to the destination station regarding
+/// (arrival time, number of transfers, walking minutes), sorted by arrival time
+///
+/// as each node has a fixed time, labels at a node only compete in boardings and walking time,
+/// paths with equal criteria are reduced to the one with the lowest travel_cost
+///
+/// each path ends at its first node at the destination station, nodes later than `max_duration` after start are not reached
+///
+/// labels are only extended along edges the constraints admit, as they track boardings and walking time
+/// no admissible path is lost by the pruning
+pub fn pareto_paths(
+    graph: &DiGraph<TimetableNode, TimetableEdge>,
+    cost_model: &CostModel,
+    start: NodeIndex,
+    destination_station_id: u64,
+    max_duration: u64,
+    constraints: &GroupConstraints,
+    inaccessible_stations: &HashSet<u64>, // stations without step-free access (see `GroupConstraints::step_free`)
+) -> Vec<Vec<EdgeIndex>> {
+    let max_time = graph[start].time() + max_duration;
+    let starts_in_trip = graph[start].is_arrival();
+
+    let mut labels = vec![Label {
+        node: start,
+        boardings: 0,
+        walking_time: 0,
+        travel_cost: 0,
+        predecessor: None,
+        alive: true,
+    }];
+
+    // node -> indices of its (possibly dead) labels
+    let mut nodes_labels: HashMap<NodeIndex, Vec<usize>> = HashMap::new();
+    nodes_labels.insert(start, vec![0]);
+
+    // labels are extended in order of their node's time
+    let mut queue = BinaryHeap::new();
+    queue.push(Reverse((graph[start].time(), 0)));
+
+    let mut destination_labels = Vec::new();
+
+    while let Some(Reverse((_, label_index))) = queue.pop() {
+        if !labels[label_index].alive {
+            continue;
+        }
+
+        let node = labels[label_index].node;
+
+        if graph[node].station_id() == destination_station_id {
+            // destination nodes are not expanded
+            destination_labels.push(label_index);
+            continue;
+        }
+
+        for edge in graph.edges(node) {
+            let next = edge.target();
+            let weight = edge.weight();
+
+            if graph[next].time() > max_time {
+                continue;
+            }
+
+            let label = &labels[label_index];
+            if !constraints.admits_edge(graph, inaccessible_stations, edge.id(), starts_in_trip, label.boardings, label.walking_time) {
+                continue;
+            }
+
+            let next_label = Label {
+                node: next,
+                boardings: label.boardings + if weight.is_board() { 1 } else { 0 },
+                walking_time: label.walking_time + if weight.is_walk() { weight.duration() } else { 0 },
+                travel_cost: label.travel_cost + weight.travel_cost(cost_model),
+                predecessor: Some((label_index, edge.id())),
+                alive: true,
+            };
+
+            let next_node_labels = nodes_labels.entry(next).or_default();
+            if next_node_labels.iter().any(|other| labels[*other].alive && labels[*other].dominates(&next_label)) {
+                continue;
+            }
+
+            // remove all labels dominated by the new one
+            for other in next_node_labels.iter() {
+                if labels[*other].alive && next_label.dominates(&labels[*other]) {
+                    labels[*other].alive = false;
+                }
+            }
+            next_node_labels.retain(|other| labels[*other].alive);
+
+            next_node_labels.push(labels.len());
+            queue.push(Reverse((graph[next].time(), labels.len())));
+            labels.push(next_label);
+        }
+    }
+
+    // collect edges by walking back the predecessors
+    let label_edges = |label_index: usize| {
+        let mut edges = Vec::new();
+        let mut current = label_index;
+        while let Some((predecessor, edge)) = labels[current].predecessor {
+            edges.push(edge);
+            current = predecessor;
+        }
+        edges.reverse();
+        edges
+    };
+
+    // Pareto filter over all admissible destination labels (alighting at the destination is only checked here),
+    // now including the arrival time
+    let criteria = |label_index: usize| {
+        let label = &labels[label_index];
+        let transfers = if starts_in_trip { label.boardings } else { label.boardings.saturating_sub(1) };
+        (graph[label.node].time(), transfers, label.walking_time, label.travel_cost)
+    };
+
+    let mut pareto_labels: Vec<usize> = Vec::new();
+    for label_index in destination_labels
+        .into_iter()
+        .filter(|label_index| labels[*label_index].alive && constraints.admits_path(graph, inaccessible_stations, &label_edges(*label_index)))
+    {
+        let (time, transfers, walking_time, travel_cost) = criteria(label_index);
+
+        let dominated = pareto_labels.iter().any(|other| {
+            let (other_time, other_transfers, other_walking_time, other_travel_cost) = criteria(*other);
+            other_time <= time
+                && other_transfers <= transfers
+                && other_walking_time <= walking_time
+                && ((other_time, other_transfers, other_walking_time) != (time, transfers, walking_time) || other_travel_cost <= travel_cost)
+        });
+
+        if !dominated {
+            pareto_labels.retain(|other| {
+                let (other_time, other_transfers, other_walking_time, _) = criteria(*other);
+                !(time <= other_time && transfers <= other_transfers && walking_time <= other_walking_time)
+            });
+            pareto_labels.push(label_index);
+        }
+    }
+
+    pareto_labels.sort_by_key(|label_index| criteria(*label_index));
+
+    pareto_labels.into_iter().map(label_edges).collect()
+}
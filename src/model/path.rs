@@ -3,7 +3,8 @@ use petgraph::{dot::Dot, graph::{DiGraph, EdgeIndex, NodeIndex}, visit::{depth_f
 use serde::{Deserialize, Serialize};
 use std::{cmp::Ordering, collections::{HashMap, HashSet, VecDeque}, fs::File, io::{self, BufWriter, Write}};
 
-use super::{TimetableEdge, TimetableNode};
+use super::{constraints::GroupConstraints, cost_model::CostModel, TimetableEdge, TimetableNode};
+use crate::time;
 
 /// travel path
 #[derive(Eq, Clone, Debug, Serialize, Deserialize)]
@@ -11,7 +12,9 @@ pub struct Path {
     travel_cost: u64,     // cost for this path
     travel_duration: u64, // duration of this path
     travel_delay: i64,    // time between planned and real arrival
+    delay_cost: i64,      // travel_delay weighted by the cost model
     utilization: u64,     // number of passengers
+    first_class: bool,    // passengers travel in first class (may take first class seats)
 
     pub edges: IndexSet<EdgeIndex>, // edges determining the path in graph
 }
@@ -35,11 +38,27 @@ impl PartialEq for Path {
     }
 }
 
+/// algorithm searching the paths of each group (dfs_visitor_search as fallback if it finds none)
+#[derive(Debug, Clone)]
+pub enum PathSearch {
+    Iddfs { // iterative deepening dfs, retried with the next budget until min_paths are found
+        search_budgets: Vec<u64>,
+        min_paths: usize,
+    },
+
+    KShortestPaths { // k cheapest loopless paths by travel_cost (Yen's algorithm)
+        k: usize,
+    },
+
+    Pareto, // Pareto set regarding arrival time, transfers and walking time
+}
+
 impl Path {
 
     /// creates new path using non-empty vector of edges
     pub fn new(
         graph: &DiGraph<TimetableNode, TimetableEdge>,
+        cost_model: &CostModel,
         edges: Vec<EdgeIndex>,
         utilization: u64,
         planned_arrival_time: u64,
@@ -49,7 +68,7 @@ impl Path {
 
         for edge in edges.iter() {
             let edge_weight = &graph[*edge];
-            travel_cost += edge_weight.travel_cost();
+            travel_cost += edge_weight.travel_cost(cost_model);
             duration += edge_weight.duration();
         }
 
@@ -65,13 +84,39 @@ impl Path {
             travel_duration: duration,
             utilization,
             travel_delay,
+            delay_cost: cost_model.delay_cost(travel_delay),
+            first_class: false,
             edges: edges.into_iter().collect(),
         }
     }
 
-    /// returns cost (travel cost + travel delay) of this path
+    /// returns self with the class of its passengers (second class by default)
+    pub fn with_first_class(mut self, first_class: bool) -> Self {
+        self.first_class = first_class;
+        self
+    }
+
+    /// creates the virtual "no service" path of a group, which has no edges and costs unserved_penalty per passenger
+    pub fn unserved(unserved_penalty: u64, utilization: u64) -> Self {
+        Self {
+            travel_cost: unserved_penalty * utilization,
+            travel_duration: 0,
+            utilization,
+            travel_delay: 0,
+            delay_cost: 0,
+            first_class: false,
+            edges: IndexSet::new(),
+        }
+    }
+
+    /// returns true if this is the virtual "no service" path (the group is not transported)
+    pub fn is_unserved(&self) -> bool {
+        self.edges.is_empty()
+    }
+
+    /// returns cost (travel cost + weighted travel delay) of this path
     pub fn cost(&self) -> i64 {
-        self.travel_cost as i64 + self.travel_delay
+        self.travel_cost as i64 + self.delay_cost
     }
 
     /// returns travel cost of this path
@@ -88,11 +133,30 @@ impl Path {
         self.utilization
     }
 
+    /// returns true if the passengers of this path travel in first class
+    pub fn is_first_class(&self) -> bool {
+        self.first_class
+    }
+
+    /// returns utilization of this path in first class
+    pub fn first_class_utilization(&self) -> u64 {
+        if self.first_class {
+            self.utilization
+        } else {
+            0
+        }
+    }
+
     /// returns travel delay of this path
     pub fn travel_delay(&self) -> i64 {
         self.travel_delay
     }
 
+    /// returns travel delay of this path weighted by the cost model
+    pub fn delay_cost(&self) -> i64 {
+        self.delay_cost
+    }
+
     /// returns the intersecting edges with another path
     pub fn intersecting_edges(&self, other: &Self) -> Vec<EdgeIndex> {
         self.edges.intersection(&other.edges).cloned().collect()
@@ -106,6 +170,32 @@ impl Path {
             .sum()
     }
 
+    /// returns the summed durations of Walk edges in the path
+    pub fn get_walking_time(&self, graph: &DiGraph<TimetableNode, TimetableEdge>) -> u64 {
+        self.edges
+            .iter()
+            .map(|edge| if graph[*edge].is_walk() { graph[*edge].duration() } else { 0 })
+            .sum()
+    }
+
+    /// returns the number of transfers, i.e. Board edges (the first one does not count if the path starts at a station)
+    ///
+    /// the unserved path has no transfers
+    pub fn get_transfers(&self, graph: &DiGraph<TimetableNode, TimetableEdge>) -> u64 {
+        if self.is_unserved() {
+            return 0;
+        }
+
+        let boardings: u64 = self.edges.iter().map(|edge| if graph[*edge].is_board() { 1 } else { 0 }).sum();
+        let starts_in_trip = graph[graph.edge_endpoints(self.edges[0]).unwrap().0].is_arrival();
+
+        if starts_in_trip {
+            boardings
+        } else {
+            boardings.saturating_sub(1)
+        }
+    }
+
     /// returns the summed time of durations of WaitAtStation edges in the path
     pub fn get_waiting_time(&self, graph: &DiGraph<TimetableNode, TimetableEdge>) -> u64 {
         self.edges
@@ -177,6 +267,11 @@ impl Path {
         // For trip edges save (trip_id, duration, Trip)
         let mut travel = Vec::new();
 
+        // the unserved path does not travel at all
+        if self.is_unserved() {
+            return travel;
+        }
+
         // start with the first node if arrival
         let (node_a_index, _) = graph.edge_endpoints(self.edges[0]).unwrap();
         let node_a = &graph[node_a_index];
@@ -261,7 +356,7 @@ impl Path {
     pub fn display(&self, graph: &DiGraph<TimetableNode, TimetableEdge>) {
         for (location, time, kind) in self.to_location_time_and_type(graph) {
             if kind == "Arrival" || kind == "Departure" {
-                println!("{} at station {}, time={} ->", kind, location, time,)
+                println!("{} at station {}, time={} ->", kind, location, time::format_time(time))
             } else {
                 let mut in_trip = "".to_string();
                 if location != "" {
@@ -277,7 +372,12 @@ impl Path {
     pub fn to_string(&self, graph: &DiGraph<TimetableNode, TimetableEdge>) -> String {
         let mut path_string = String::new();
         for (location, time, kind) in self.to_location_time_and_type(graph) {
-            path_string += &format!("{}${}${}->", location, time, kind);
+            // times of Arrival and Departure nodes as `HH:MM[+D]`, durations of Trip and Walk edges in minutes
+            if kind == "Arrival" || kind == "Departure" {
+                path_string += &format!("{}${}${}->", location, time::format_time(time), kind);
+            } else {
+                path_string += &format!("{}${}${}->", location, time, kind);
+            }
         }
         path_string.pop();
         path_string.pop();
@@ -339,47 +439,17 @@ impl Path {
     //     colliding
     // }
 
-    /// occupy self to graph (add utilization to edges)
-    #[inline]
-    pub fn strain_to_graph(
-        &self,
-        graph: &mut DiGraph<TimetableNode, TimetableEdge>,
-        strained_edges: &mut HashSet<EdgeIndex>,
-    ) {
-        for edge in self.edges.iter() {
-            graph[*edge].increase_utilization(self.utilization);
-
-            // also add edge to set of strained edges
-            strained_edges.insert(*edge);
-        }
-    }
-
-    /// release self from graph (remove utilization from edges)
-    #[inline]
-    pub fn relieve_from_graph(
-        &self,
-        graph: &mut DiGraph<TimetableNode, TimetableEdge>,
-        strained_edges: &mut HashSet<EdgeIndex>,
-    ) {
-        for edge in self.edges.iter() {
-            let timetable_edge = &mut graph[*edge];
-            timetable_edge.decrease_utilization(self.utilization);
-
-            if timetable_edge.utilization() == 0 {
-                // utilization is zero (edge is not strained) -> remove from strained_edges
-                strained_edges.remove(edge);
-            }
-        }
-    }
-
-
-
     /// iterative deeping depth-first-search (IDDFS) to find paths for a given group
+    ///
+    /// paths violating the constraints of the group are pruned
     pub fn all_paths_iddfs(
         graph: &DiGraph<TimetableNode, TimetableEdge>,
+        cost_model: &CostModel,
         start: NodeIndex,
         destination_station_id: u64, // condition that determines whether goal node was found
         min_edge_vecs: usize,
+        constraints: &GroupConstraints,
+        inaccessible_stations: &HashSet<u64>,
 
         max_duration: u64,
         budgets: &[u64],
@@ -393,8 +463,11 @@ impl Path {
 
             edge_vecs = Self::recursive_dfs_search(
                 graph,
+                cost_model,
                 start,
                 destination_station_id,
+                constraints,
+                inaccessible_stations,
                 max_duration,
                 *budget,
             );
@@ -412,8 +485,11 @@ impl Path {
     // returns a vec of paths along with their remaining_duration
     pub fn recursive_dfs_search(
         graph: &DiGraph<TimetableNode, TimetableEdge>,
+        cost_model: &CostModel,
         start: NodeIndex,
         destination_station_id: u64,
+        constraints: &GroupConstraints,
+        inaccessible_stations: &HashSet<u64>,
 
         max_duration: u64,
         max_budget: u64,
@@ -432,31 +508,40 @@ impl Path {
         let mut counter_out_of_depth = 0;
         let mut counter_out_of_budget = 0;
         let mut counter_out_of_time = 0;
+        let mut counter_out_of_constraints = 0;
 
 
         Self::recursive_dfs_search_helper(
             graph,
+            cost_model,
             &mut results,
             start,
             destination_station_id,
+            constraints,
+            inaccessible_stations,
             &mut edge_stack,
             &mut station_arrival_stack,
             &mut visited_stations,
             max_duration,
             max_budget,
+            graph[start].is_arrival(),
+            0,
+            0,
 
             &mut counter_already_visited_earlier,
             &mut counter_out_of_depth,
             &mut counter_out_of_budget,
-            &mut counter_out_of_time
+            &mut counter_out_of_time,
+            &mut counter_out_of_constraints
         );
 
         print!(
-            "[ave={} ood={} oob={} oot={}] ",
+            "[ave={} ood={} oob={} oot={} ooc={}] ",
             counter_already_visited_earlier,
             counter_out_of_depth,
             counter_out_of_budget,
-            counter_out_of_time
+            counter_out_of_time,
+            counter_out_of_constraints
         );
 
         results
@@ -465,9 +550,12 @@ impl Path {
     /// recursive helper function for dfs search
     fn recursive_dfs_search_helper(
         graph: &DiGraph<TimetableNode, TimetableEdge>,
+        cost_model: &CostModel,
         results: &mut Vec<Vec<EdgeIndex>>, // paths found until now
         current_node: NodeIndex,
         destination_station_id: u64,
+        constraints: &GroupConstraints,
+        inaccessible_stations: &HashSet<u64>,
         edge_stack: &mut Vec<EdgeIndex>, // visited edges (in order of visit)
         station_arrival_stack: &mut IndexSet<u64>,
 
@@ -476,10 +564,16 @@ impl Path {
         remaining_duration: u64,
         remaining_budget: u64,
 
+        // counters of the path on edge_stack the constraints limit (see `GroupConstraints::admits_edge()`)
+        starts_in_trip: bool,
+        boardings: u64,
+        walking_time: u64,
+
         counter_already_visited_earlier: &mut u64,
         counter_out_of_depth: &mut u64,
         counter_out_of_budget: &mut u64,
         counter_out_of_time: &mut u64,
+        counter_out_of_constraints: &mut u64,
 
     ) {
         if edge_stack.len() == 100 {
@@ -508,7 +602,11 @@ impl Path {
 
         if current_node_weight_station_id == destination_station_id {
             // found destination node -> don't further continue this path
-            results.push(edge_stack.clone());
+            if constraints.admits_path(graph, inaccessible_stations, edge_stack) {
+                results.push(edge_stack.clone());
+            } else {
+                *counter_out_of_constraints += 1;
+            }
         } else {
             let mut walker = graph.neighbors(current_node).detach();
 
@@ -517,7 +615,7 @@ impl Path {
                 // lookup edge's cost
                 let next_edge_weight = &graph[next_edge];
                 let next_edge_weight_duration = next_edge_weight.duration();
-                let next_edge_weight_cost = next_edge_weight.travel_cost();
+                let next_edge_weight_cost = next_edge_weight.travel_cost(cost_model);
 
                 if next_edge_weight_cost > remaining_budget {
                     // not enough budget left
@@ -531,6 +629,12 @@ impl Path {
                     continue
                 }
 
+                if !constraints.admits_edge(graph, inaccessible_stations, next_edge, starts_in_trip, boardings, walking_time) {
+                    // path would violate the constraints of the group
+                    *counter_out_of_constraints += 1;
+                    continue
+                }
+
                 // -> we can "afford" going using next_edge
 
                 // add next_edge to stack
@@ -539,18 +643,25 @@ impl Path {
                 // make recursive call
                 &mut Self::recursive_dfs_search_helper(
                     graph,
+                    cost_model,
                     results,
                     next_node,
                     destination_station_id,
+                    constraints,
+                    inaccessible_stations,
                     edge_stack,
                     station_arrival_stack,
                     visited_stations,
                     remaining_duration - next_edge_weight_duration,
                     remaining_budget - next_edge_weight_cost,
+                    starts_in_trip,
+                    boardings + if next_edge_weight.is_board() { 1 } else { 0 },
+                    walking_time + if next_edge_weight.is_walk() { next_edge_weight_duration } else { 0 },
                     counter_already_visited_earlier,
                     counter_out_of_depth,
                     counter_out_of_budget,
-                    counter_out_of_time
+                    counter_out_of_time,
+                    counter_out_of_constraints
                 );
 
                 // remove next_edge from stack
@@ -565,13 +676,18 @@ impl Path {
 
     /// petgraph native depth first search (using visitors) to find paths for a given group
     /// returns vector of discovered paths
+    ///
+    /// edges violating the constraints of the group are pruned, paths violating them (e.g. by their number of transfers) are skipped
     pub fn dfs_visitor_search(
         graph: &DiGraph<TimetableNode, TimetableEdge>,
+        cost_model: &CostModel,
         start: NodeIndex,
         destination_station_id: u64, // condition that determines whether goal node was found
 
         utilization: u64, // number of passengers, weight of load, etc.
         planned_arrival: u64,
+        constraints: &GroupConstraints,
+        inaccessible_stations: &HashSet<u64>,
 
         limit_paths: usize,
     ) -> Vec<Self> {
@@ -579,6 +695,11 @@ impl Path {
 
         let mut predecessor = vec![NodeIndex::end(); graph.node_count()];
 
+        // counters of the path to each node the constraints limit (see `GroupConstraints::admits_edge()`)
+        let starts_in_trip = graph[start].is_arrival();
+        let mut boardings = vec![0; graph.node_count()];
+        let mut walking_time = vec![0; graph.node_count()];
+
         let start_time = graph[start].time();
 
         depth_first_search(graph, Some(start), |event| {
@@ -590,6 +711,15 @@ impl Path {
                     return Control::Prune;
                 }
 
+                let edge = graph.find_edge(u, v).unwrap();
+                if !constraints.admits_edge(graph, inaccessible_stations, edge, starts_in_trip, boardings[u.index()], walking_time[u.index()]) {
+                    return Control::Prune;
+                }
+
+                // each node is discovered once, so its counters are the ones of the path along the predecessors
+                boardings[v.index()] = boardings[u.index()] + if graph[edge].is_board() { 1 } else { 0 };
+                walking_time[v.index()] = walking_time[u.index()] + if graph[edge].is_walk() { graph[edge].duration() } else { 0 };
+
                 if graph[v].station_id() == destination_station_id {
                     // we found destination node -> use predecessor map to look-up edge path
                     // start at destination node (to) and "walk" back to start (from), collect all nodes in path vec and then reverse vec
@@ -618,8 +748,12 @@ impl Path {
                         );
                     }
 
+                    if !constraints.admits_path(graph, inaccessible_stations, &edges) {
+                        return Control::Prune;
+                    }
+
                     // create and insert Self
-                    paths.push(Self::new(graph, edges, utilization, planned_arrival));
+                    paths.push(Self::new(graph, cost_model, edges, utilization, planned_arrival));
 
                     if limit_paths != 0 && paths.len() >= limit_paths {
                         return Control::Break(v);
@@ -681,6 +815,7 @@ pub fn collect_paths_recursive(graph: &DiGraph<TimetableNode, TimetableEdge>, pr
 /// breadth first search to find paths for a given group
 pub fn bfs(
     graph: &DiGraph<TimetableNode, TimetableEdge>,
+    cost_model: &CostModel,
     start: NodeIndex,
     destination_station_id: u64,
 
@@ -741,7 +876,7 @@ pub fn bfs(
             while let Some((next_edge, next_node)) = walker.next(graph) {
     
                 let next_edge_weight = &graph[next_edge];
-                let next_edge_weight_cost = next_edge_weight.travel_cost();
+                let next_edge_weight_cost = next_edge_weight.travel_cost(cost_model);
                 let next_edge_weight_duration = next_edge_weight.duration();
 
                 let next_cost = current_cost + next_edge_weight_cost;
@@ -0,0 +1,142 @@
+use std::{collections::HashMap, fs::File, io::{BufWriter, Write}};
+
+use serde::{Deserialize, Serialize};
+
+use super::{trip::Trip, Model};
+use crate::{csv_reader::{self, Record}, error::Error};
+
+/// a fraction of a periodic trip pattern, repeated every period within the validity window
+///
+/// all fractions with the same id form one pattern
+#[derive(Debug, Clone)]
+pub struct PeriodicTrip {
+    pub trip: Trip,       // fraction of one run of the pattern (trip.id is the pattern id)
+    pub period: u64,      // minutes between two runs
+    pub valid_from: u64,  // earliest departure of a run (at the first station of the pattern)
+    pub valid_until: u64, // latest departure of a run (at the first station of the pattern)
+}
+
+/// run of a pattern a trip was expanded from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TripPattern {
+    pub pattern: u64, // id of the pattern in periodic_trips.csv
+    pub run: u64,     // index of the run in order of time (0 is the first run within the validity window)
+}
+
+impl PeriodicTrip {
+
+    /// returns periodic trips from maps read from filepath
+    ///
+    /// the fractions are parsed like `Trip::from_maps_to_vec()`, all fractions of a pattern have to share period and validity window
+    pub fn from_maps_to_vec(periodic_trip_maps: &[Record], filepath: &str, reference_date: Option<i64>) -> Result<Vec<Self>, Error> {
+        let trips = Trip::from_maps_to_vec(periodic_trip_maps, filepath, reference_date)?;
+
+        let mut periodic_trips = Vec::with_capacity(trips.len());
+        let mut patterns_window: HashMap<u64, (u64, u64, u64)> = HashMap::new();
+
+        for (index, (trip, periodic_trip_map)) in trips.into_iter().zip(periodic_trip_maps.iter()).enumerate() {
+            let periodic_trip = Self {
+                period: csv_reader::parse_field(periodic_trip_map, filepath, index, "period")?,
+                valid_from: csv_reader::parse_time_field(periodic_trip_map, filepath, index, "valid_from", reference_date)?,
+                valid_until: csv_reader::parse_time_field(periodic_trip_map, filepath, index, "valid_until", reference_date)?,
+                trip,
+            };
+
+            // the first fraction defines period and validity window of the pattern
+            let window = (periodic_trip.period, periodic_trip.valid_from, periodic_trip.valid_until);
+            let pattern_window = *patterns_window.entry(periodic_trip.trip.id).or_insert(window);
+
+            let invalid_column = if periodic_trip.period == 0 || pattern_window.0 != window.0 {
+                Some("period")
+            } else if pattern_window.1 != window.1 {
+                Some("valid_from")
+            } else if pattern_window.2 != window.2 {
+                Some("valid_until")
+            } else {
+                None
+            };
+
+            if let Some(column) = invalid_column {
+                return Err(Error::InvalidValue {
+                    file: filepath.to_string(),
+                    row: csv_reader::row(index),
+                    column: column.to_string(),
+                    value: csv_reader::field(periodic_trip_map, filepath, index, column)?.to_string(),
+                });
+            }
+
+            periodic_trips.push(periodic_trip);
+        }
+
+        Ok(periodic_trips)
+    }
+
+    /// expands the patterns into one trip per run, derived trip ids are numbered from first_id on (in order of patterns and runs)
+    ///
+    /// runs are shifted by multiples of the period, so that the departure at the first station of the pattern lies within the validity window
+    ///
+    /// returns the trips and the pattern and run of each derived trip id
+    pub fn expand(periodic_trips: &[Self], first_id: u64) -> (Vec<Trip>, HashMap<u64, TripPattern>) {
+        // patterns in order of their first fraction
+        let mut pattern_ids: Vec<u64> = Vec::new();
+        let mut patterns: HashMap<u64, Vec<&Self>> = HashMap::new();
+
+        for periodic_trip in periodic_trips.iter() {
+            let pattern = patterns.entry(periodic_trip.trip.id).or_insert_with(|| {
+                pattern_ids.push(periodic_trip.trip.id);
+                Vec::new()
+            });
+            pattern.push(periodic_trip);
+        }
+
+        let mut trips = Vec::new();
+        let mut trip_patterns = HashMap::new();
+        let mut next_id = first_id;
+
+        for pattern_id in pattern_ids {
+            let fractions = &patterns[&pattern_id];
+            let (period, valid_from, valid_until) = (fractions[0].period as i64, fractions[0].valid_from as i64, fractions[0].valid_until as i64);
+            let start = fractions.iter().map(|fraction| fraction.trip.departure).min().unwrap() as i64;
+
+            // first and last run with a start within the validity window
+            let first_run = (valid_from - start).div_euclid(period) + i64::from((valid_from - start).rem_euclid(period) != 0);
+            let last_run = (valid_until - start).div_euclid(period);
+
+            for (run, shift) in (first_run..=last_run).map(|run| run * period).enumerate() {
+                for fraction in fractions.iter() {
+                    trips.push(Trip {
+                        id: next_id,
+                        departure: (fraction.trip.departure as i64 + shift) as u64,
+                        arrival: (fraction.trip.arrival as i64 + shift) as u64,
+                        ..fraction.trip.clone()
+                    });
+                }
+
+                trip_patterns.insert(next_id, TripPattern { pattern: pattern_id, run: run as u64 });
+                next_id += 1;
+            }
+        }
+
+        (trips, trip_patterns)
+    }
+}
+
+impl Model {
+
+    /// saves the pattern and run of each trip expanded from periodic trips to a pipe-separated CSV file (in order of trip ids)
+    pub fn save_trip_patterns_to_csv(&self, filepath: &str) {
+        let mut writer = BufWriter::new(
+            File::create(filepath).unwrap_or_else(|_| panic!("Could not create file \"{}\"", filepath)),
+        );
+
+        writeln!(writer, "trip_id|pattern|run").unwrap();
+
+        let mut trip_ids: Vec<&u64> = self.trip_patterns.keys().collect();
+        trip_ids.sort_unstable();
+
+        for trip_id in trip_ids {
+            let trip_pattern = &self.trip_patterns[trip_id];
+            writeln!(writer, "{}|{}|{}", trip_id, trip_pattern.pattern, trip_pattern.run).unwrap();
+        }
+    }
+}
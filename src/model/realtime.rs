@@ -0,0 +1,497 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::Path,
+    time::Instant,
+};
+
+use petgraph::graph::NodeIndex;
+use prost::Message;
+use serde::{Deserialize, Serialize};
+
+use super::{cost_model::CostModel, group::Group, path::PathSearch, update::ModelChanges, Model};
+use crate::{
+    error::Error,
+    optimization::{cooling_schedule::AnnealingParameters, simulated_annealing, InitialState},
+};
+
+/// delays and cancellations of trips at one point in time, read from a GTFS-Realtime TripUpdates feed or a JSON equivalent
+/// and applied to a model with `Model::apply_realtime_update()`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RealtimeUpdate {
+    pub timestamp: Option<u64>, // time of the update (POSIX time in GTFS-RT feeds)
+    pub trip_updates: Vec<TripUpdate>,
+}
+
+/// current state of a trip, replaces all previous updates of the trip
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TripUpdate {
+    pub trip: u64,
+    pub cancelled: bool,
+    pub delay: Option<i64>, // delay of the whole trip (in minutes), only used without stop_time_updates
+    pub stop_time_updates: Vec<StopTimeUpdate>, // in order of the stations of the trip
+}
+
+/// delay (in minutes, negative if early) of a trip at a station relative to the timetable,
+/// applies to all following stations of the trip until the next stop time update
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StopTimeUpdate {
+    pub station: u64,
+    pub arrival_delay: Option<i64>,   // the departure delay if not set
+    pub departure_delay: Option<i64>, // the arrival delay if not set
+}
+
+/// subset of the GTFS-Realtime protobuf messages (gtfs-realtime.proto) required for TripUpdates, all other fields are skipped
+pub mod gtfs_rt {
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct FeedMessage {
+        #[prost(message, optional, tag = "1")]
+        pub header: Option<FeedHeader>,
+        #[prost(message, repeated, tag = "2")]
+        pub entity: Vec<FeedEntity>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct FeedHeader {
+        #[prost(string, optional, tag = "1")]
+        pub gtfs_realtime_version: Option<String>,
+        #[prost(uint64, optional, tag = "3")]
+        pub timestamp: Option<u64>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct FeedEntity {
+        #[prost(string, optional, tag = "1")]
+        pub id: Option<String>,
+        #[prost(bool, optional, tag = "2")]
+        pub is_deleted: Option<bool>,
+        #[prost(message, optional, tag = "3")]
+        pub trip_update: Option<TripUpdate>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct TripUpdate {
+        #[prost(message, optional, tag = "1")]
+        pub trip: Option<TripDescriptor>,
+        #[prost(message, repeated, tag = "2")]
+        pub stop_time_update: Vec<StopTimeUpdate>,
+        #[prost(int32, optional, tag = "5")]
+        pub delay: Option<i32>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct TripDescriptor {
+        #[prost(string, optional, tag = "1")]
+        pub trip_id: Option<String>,
+        #[prost(int32, optional, tag = "4")]
+        pub schedule_relationship: Option<i32>, // 3 = CANCELED
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct StopTimeUpdate {
+        #[prost(uint32, optional, tag = "1")]
+        pub stop_sequence: Option<u32>,
+        #[prost(message, optional, tag = "2")]
+        pub arrival: Option<StopTimeEvent>,
+        #[prost(message, optional, tag = "3")]
+        pub departure: Option<StopTimeEvent>,
+        #[prost(string, optional, tag = "4")]
+        pub stop_id: Option<String>,
+        #[prost(int32, optional, tag = "5")]
+        pub schedule_relationship: Option<i32>, // 2 = NO_DATA
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct StopTimeEvent {
+        #[prost(int32, optional, tag = "1")]
+        pub delay: Option<i32>, // in seconds
+        #[prost(int64, optional, tag = "2")]
+        pub time: Option<i64>,
+    }
+
+    pub const CANCELED: i32 = 3;
+    pub const NO_DATA: i32 = 2;
+}
+
+/// converts a GTFS-RT delay (in seconds) to minutes
+fn delay_in_minutes(seconds: i32) -> i64 {
+    (f64::from(seconds) / 60.0).round() as i64
+}
+
+impl RealtimeUpdate {
+
+    /// reads an update from a GTFS-RT protobuf (`.pb` or `.bin`) or JSON (any other extension) file
+    ///
+    /// trip_ids and stop_ids of GTFS-RT feeds have to be numeric (the ids of the model), entities with other trip_ids,
+    /// stop time updates with other stop_ids and stop time events with absolute times only are skipped
+    pub fn from_file(filepath: &str) -> Result<Self, Error> {
+        let content = fs::read(filepath).map_err(|source| Error::Io {
+            file: filepath.to_string(),
+            source,
+        })?;
+
+        let invalid_feed = |message: String| Error::InvalidFeed {
+            file: filepath.to_string(),
+            message,
+        };
+
+        let is_protobuf = Path::new(filepath).extension().is_some_and(|extension| extension == "pb" || extension == "bin");
+
+        if !is_protobuf {
+            return serde_json::from_slice(&content).map_err(|error| invalid_feed(error.to_string()));
+        }
+
+        let feed_message = gtfs_rt::FeedMessage::decode(content.as_slice()).map_err(|error| invalid_feed(error.to_string()))?;
+
+        let mut update = Self {
+            timestamp: feed_message.header.and_then(|header| header.timestamp),
+            trip_updates: Vec::new(),
+        };
+
+        let mut n_skipped = 0;
+
+        for entity in feed_message.entity.into_iter().filter(|entity| !entity.is_deleted.unwrap_or(false)) {
+            let trip_update = match entity.trip_update {
+                Some(trip_update) => trip_update,
+                None => continue,
+            };
+
+            let trip_descriptor = trip_update.trip.unwrap_or_default();
+            let trip_id = match trip_descriptor.trip_id.as_deref().and_then(|trip_id| trip_id.parse().ok()) {
+                Some(trip_id) => trip_id,
+                None => {
+                    n_skipped += 1;
+                    continue;
+                }
+            };
+
+            let mut stop_time_updates = Vec::new();
+
+            for stop_time_update in trip_update.stop_time_update {
+                let station_id = stop_time_update.stop_id.as_deref().and_then(|stop_id| stop_id.parse().ok());
+                let arrival_delay = stop_time_update.arrival.and_then(|event| event.delay).map(delay_in_minutes);
+                let departure_delay = stop_time_update.departure.and_then(|event| event.delay).map(delay_in_minutes);
+
+                match station_id {
+                    Some(station_id) if stop_time_update.schedule_relationship != Some(gtfs_rt::NO_DATA) && (arrival_delay.is_some() || departure_delay.is_some()) => {
+                        stop_time_updates.push(StopTimeUpdate {
+                            station: station_id,
+                            arrival_delay,
+                            departure_delay,
+                        });
+                    }
+                    _ => n_skipped += 1,
+                }
+            }
+
+            update.trip_updates.push(TripUpdate {
+                trip: trip_id,
+                cancelled: trip_descriptor.schedule_relationship == Some(gtfs_rt::CANCELED),
+                delay: trip_update.delay.map(delay_in_minutes),
+                stop_time_updates,
+            });
+        }
+
+        if n_skipped > 0 {
+            println!("{}: skipped {} entities or stop time updates without numeric ids or delays", filepath, n_skipped);
+        }
+
+        Ok(update)
+    }
+
+    /// reads all GTFS-RT protobuf (`.pb` or `.bin`) and JSON (`.json`) files of a folder in order of their file names
+    pub fn from_folder(folder_path: &str) -> Result<Vec<(String, Self)>, Error> {
+        let entries = fs::read_dir(folder_path).map_err(|source| Error::Io {
+            file: folder_path.to_string(),
+            source,
+        })?;
+
+        let mut filepaths: Vec<String> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|extension| extension == "pb" || extension == "bin" || extension == "json"))
+            .map(|path| path.to_string_lossy().to_string())
+            .collect();
+
+        filepaths.sort();
+
+        filepaths
+            .into_iter()
+            .map(|filepath| Self::from_file(&filepath).map(|update| (filepath, update)))
+            .collect()
+    }
+}
+
+impl Model {
+
+    /// returns the times of all Departure and Arrival nodes, the timetable that realtime delays refer to
+    pub fn scheduled_times(&self) -> HashMap<NodeIndex, u64> {
+        self.graph
+            .node_indices()
+            .filter(|node| self.graph[*node].trip_id().is_some())
+            .map(|node| (node, self.graph[node].time()))
+            .collect()
+    }
+
+    /// applies a realtime update: cancels trips and sets the times of Departure and Arrival nodes to their scheduled time plus
+    /// the current delay (see `Model::set_trip_times()`), so the Alight and Walk edges of the affected stations are rewired
+    ///
+    /// guaranteed connections are held afterwards (see `Model::hold_guaranteed_connections()`),
+    /// stations of a trip before its first stop time update keep their current times,
+    /// updates of unknown trips and stop time updates that do not match the stations of the trip (in order) are skipped
+    ///
+    /// returns the number of applied trip updates
+    pub fn apply_realtime_update(&mut self, update: &RealtimeUpdate, scheduled_times: &HashMap<NodeIndex, u64>, changes: &mut ModelChanges) -> usize {
+        let mut n_applied = 0;
+
+        for trip_update in update.trip_updates.iter() {
+            let mut trip_nodes = self.trip_nodes(trip_update.trip);
+
+            if trip_nodes.is_empty() {
+                continue;
+            }
+
+            n_applied += 1;
+
+            if trip_update.cancelled {
+                self.remove_trip(trip_update.trip, changes);
+                continue;
+            }
+
+            // in order of the timetable, the arrival at a station before the departure
+            let scheduled_time = |node: &NodeIndex| *scheduled_times.get(node).unwrap_or(&self.graph[*node].time());
+            trip_nodes.sort_by_key(|node| (scheduled_time(node), self.graph[*node].is_departure()));
+
+            let whole_trip_update = [StopTimeUpdate {
+                station: self.graph[trip_nodes[0]].station_id(),
+                arrival_delay: trip_update.delay,
+                departure_delay: trip_update.delay,
+            }];
+
+            let stop_time_updates = match (trip_update.stop_time_updates.is_empty(), trip_update.delay) {
+                (true, Some(_)) => &whole_trip_update[..],
+                _ => &trip_update.stop_time_updates[..],
+            };
+
+            let mut times = HashMap::new();
+            let mut stop_time_updates = stop_time_updates.iter().peekable();
+            let mut delay = None;
+
+            for node in trip_nodes.iter() {
+                let station_id = self.graph[*node].station_id();
+
+                if let Some(stop_time_update) = stop_time_updates.peek().filter(|stop_time_update| stop_time_update.station == station_id) {
+                    let (first, second) = if self.graph[*node].is_arrival() {
+                        (stop_time_update.arrival_delay, stop_time_update.departure_delay)
+                    } else {
+                        (stop_time_update.departure_delay, stop_time_update.arrival_delay)
+                    };
+
+                    delay = first.or(second);
+
+                    if self.graph[*node].is_departure() {
+                        stop_time_updates.next();
+                    }
+                }
+
+                if let Some(delay) = delay {
+                    times.insert(*node, (scheduled_time(node) as i64 + delay).max(0) as u64);
+                }
+            }
+
+            self.set_trip_times(trip_update.trip, &times, changes);
+        }
+
+        self.hold_guaranteed_connections(changes);
+
+        n_applied
+    }
+
+    /// replays the realtime updates of a folder (see `RealtimeUpdate::from_folder()`) in order of their file names
+    ///
+    /// after each update the paths of the affected groups are repaired or searched again (see `Model::update_groups()`)
+    /// and simulated annealing re-optimizes the paths of the groups searched again, starting with the previous selection
+    /// (groups_path_index, all other groups keep their paths)
+    ///
+    /// saves one line per update to `<filepath>.csv`, the broken, rerouted and unserved groups of each update to
+    /// `<filepath>_flagged_groups.csv`, the runtime to `<filepath>_runtime.csv` and the edges and groups after the last update
+    ///
+    /// returns the selected path index of each group after the last update
+    pub fn replay_feed(
+        &mut self,
+        realtime_folder_path: &str,
+        groups: &mut Vec<Group>,
+        mut groups_path_index: Vec<usize>,
+        cost_model: &CostModel,
+        path_search: &PathSearch,
+        annealing_parameters: &AnnealingParameters,
+        filepath: &str,
+        seed: u64,
+    ) -> Result<Vec<usize>, Error> {
+        let updates = RealtimeUpdate::from_folder(realtime_folder_path)?;
+
+        println!("replaying {} realtime update(s) from '{}'", updates.len(), realtime_folder_path);
+
+        let mut writer = BufWriter::new(
+            File::create(format!("{}.{}", filepath, "csv"))
+                .unwrap_or_else(|_| panic!("Could not create file \"{}.csv\"", filepath)),
+        );
+
+        writeln!(writer, "update,file,timestamp,trip_updates,broken_groups,broken_passengers,searched_groups,rerouted_groups,rerouted_passengers,unserved_groups,cost").unwrap();
+
+        let mut g_writer = BufWriter::new(
+            File::create(format!("{}_{}.{}", filepath, "flagged_groups", "csv"))
+                .unwrap_or_else(|_| panic!("Could not create file \"{}_flagged_groups.csv\"", filepath)),
+        );
+
+        writeln!(g_writer, "update,group_id,passengers,broken,rerouted,unserved").unwrap();
+
+        let start_instant = Instant::now();
+
+        let scheduled_times = self.scheduled_times();
+        let mut total_rerouted_passengers = 0;
+
+        for (update_index, (update_filepath, update)) in updates.iter().enumerate() {
+            let previous_groups_trips: Vec<Vec<u64>> = groups
+                .iter()
+                .zip(groups_path_index.iter())
+                .map(|(group, path_index)| self.selected_trips(group, *path_index))
+                .collect();
+
+            let mut changes = ModelChanges::default();
+            let n_trip_updates = self.apply_realtime_update(update, &scheduled_times, &mut changes);
+
+            // selected paths along a removed edge (a connection that is not feasible anymore) broke
+            let broken: Vec<bool> = groups
+                .iter()
+                .zip(groups_path_index.iter())
+                .map(|(group, path_index)| group.paths.get(*path_index).is_some_and(|path| path.edges.iter().any(|edge| changes.removed_edges.contains(edge))))
+                .collect();
+
+            let searched_groups = self.update_groups(groups, &changes, cost_model, path_search);
+            for group_index in searched_groups.iter() {
+                groups_path_index[*group_index] = 0;
+            }
+
+            // only the groups searched again (with at least one path) are re-optimized, all others keep their selected path
+            let served_group_indices: Vec<usize> = (0..groups.len()).filter(|group_index| !groups[*group_index].paths.is_empty()).collect();
+            let served_groups: Vec<Group> = served_group_indices.iter().map(|group_index| groups[*group_index].clone()).collect();
+
+            let movable_groups: Vec<usize> = served_group_indices
+                .iter()
+                .enumerate()
+                .filter(|(_, group_index)| searched_groups.contains(group_index))
+                .map(|(served_index, _)| served_index)
+                .collect();
+
+            let parameters = AnnealingParameters {
+                initial_state: InitialState::Selection(served_group_indices.iter().map(|group_index| groups_path_index[*group_index]).collect()),
+                movable_groups: Some(movable_groups),
+                ..annealing_parameters.clone()
+            };
+
+            let selection_state = simulated_annealing::simulated_annealing(
+                &self.graph,
+                cost_model,
+                &served_groups,
+                &format!("{}_{}_simulated_annealing", filepath, update_index),
+                &parameters,
+                seed,
+            );
+
+            for (served_index, group_index) in served_group_indices.iter().enumerate() {
+                groups_path_index[*group_index] = selection_state.groups_path_index[served_index];
+            }
+
+            // groups on other trips than before the update were rerouted
+            let mut n_rerouted_groups = 0;
+            let mut rerouted_passengers = 0;
+            let mut n_unserved_groups = 0;
+
+            for (group_index, group) in groups.iter().enumerate() {
+                let previous_trips = &previous_groups_trips[group_index];
+                // without any path or on the no-service path (if unserved_penalty is set) the group is stranded, not rerouted
+                let unserved = !previous_trips.is_empty() && group.paths.get(groups_path_index[group_index]).is_none_or(|path| path.is_unserved());
+                let rerouted = !previous_trips.is_empty() && !unserved && *previous_trips != self.selected_trips(group, groups_path_index[group_index]);
+
+                if rerouted {
+                    n_rerouted_groups += 1;
+                    rerouted_passengers += group.passengers;
+                }
+
+                if unserved {
+                    n_unserved_groups += 1;
+                }
+
+                if broken[group_index] || rerouted || unserved {
+                    writeln!(g_writer, "{},{},{},{},{},{}", update_index, group.id, group.passengers, broken[group_index], rerouted, unserved).unwrap();
+                }
+            }
+
+            total_rerouted_passengers += rerouted_passengers;
+
+            let n_broken_groups = broken.iter().filter(|broken| **broken).count();
+            let broken_passengers: u64 = groups.iter().zip(broken.iter()).filter(|(_, broken)| **broken).map(|(group, _)| group.passengers).sum();
+
+            writeln!(
+                writer,
+                "{},{},{},{},{},{},{},{},{},{},{}",
+                update_index,
+                update_filepath,
+                update.timestamp.map(|timestamp| timestamp.to_string()).unwrap_or_default(),
+                n_trip_updates,
+                n_broken_groups,
+                broken_passengers,
+                searched_groups.len(),
+                n_rerouted_groups,
+                rerouted_passengers,
+                n_unserved_groups,
+                selection_state.cost
+            )
+            .unwrap();
+
+            println!(
+                "[update={}]: trip_updates={}, broken_groups={} ({} passengers), rerouted_groups={} ({} passengers), unserved_groups={}, cost={}",
+                update_index, n_trip_updates, n_broken_groups, broken_passengers, n_rerouted_groups, rerouted_passengers, n_unserved_groups, selection_state.cost
+            );
+
+            // save results of the last update
+            if update_index + 1 == updates.len() {
+                selection_state.save_strained_trip_edges_to_csv(&self.graph, &format!("{}_edges.csv", filepath));
+                selection_state.save_groups_to_csv(&self.graph, &format!("{}_groups.csv", filepath));
+            }
+        }
+
+        let mut r_writer = BufWriter::new(
+            File::create(format!("{}_{}.{}", filepath, "runtime", "csv"))
+                .unwrap_or_else(|_| panic!("Could not create file \"{}_runtime.csv\"", filepath)),
+        );
+
+        writeln!(r_writer, "runtime,updates,rerouted_passengers").unwrap();
+        writeln!(r_writer, "{}s,{},{}", start_instant.elapsed().as_secs(), updates.len(), total_rerouted_passengers).unwrap();
+
+        println!("replayed {} realtime update(s), {} passengers rerouted (done in {}s)", updates.len(), total_rerouted_passengers, start_instant.elapsed().as_secs());
+
+        Ok(groups_path_index)
+    }
+
+    /// returns the trips of the path with path_index of group in order of the path (empty without path)
+    fn selected_trips(&self, group: &Group, path_index: usize) -> Vec<u64> {
+        let mut trips: Vec<u64> = group.paths
+            .get(path_index)
+            .map(|path| {
+                path.edges
+                    .iter()
+                    .filter(|edge| self.graph[**edge].is_trip())
+                    .filter_map(|edge| self.graph[self.graph.edge_endpoints(*edge).unwrap().0].trip_id())
+                    .collect()
+            })
+            .unwrap_or_default();
+        trips.dedup();
+        trips
+    }
+}
@@ -0,0 +1,190 @@
+use std::{fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use super::{cost_model::CostModel, footpath::Footpath, group::Group, path::PathSearch, trip::Trip, Model};
+use crate::{
+    error::Error,
+    optimization::{cooling_schedule::AnnealingParameters, simulated_annealing, InitialState},
+};
+
+/// disruptions and additions (e.g. replacement services) of a timetable, applied to a model with `Model::apply_scenario()`
+///
+/// can be loaded from a TOML or JSON file, missing fields are empty
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Scenario {
+    pub cancelled_trips: Vec<u64>, // trips that do not run at all
+    pub delays: Vec<TripDelay>,
+    pub closed_stations: Vec<u64>, // stations no trip departs from or arrives at (trips are cut there)
+    pub capacity_reductions: Vec<CapacityReduction>,
+    pub added_trips: Vec<Trip>, // trip segments (records of trips.csv), e.g. replacement services
+    pub added_footpaths: Vec<Footpath>,
+    pub transfer_times: Vec<TransferTime>,
+}
+
+/// delay of a trip from its departure at a station on (the following stations are reached later as well)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TripDelay {
+    pub trip: u64,
+    #[serde(default)]
+    pub station: Option<u64>, // first delayed departure, the first departure of the trip if not set
+    pub minutes: u64,
+}
+
+/// new capacity of all Trip edges of a trip
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CapacityReduction {
+    pub trip: u64,
+    pub capacity: u64,
+}
+
+/// new transfer time of a station
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TransferTime {
+    pub station: u64,
+    pub minutes: u64,
+}
+
+impl Scenario {
+
+    /// reads a scenario from a TOML (`.toml`) or JSON (any other extension) file
+    ///
+    /// returns an error if the file could not be parsed or references trips or stations that are not part of model
+    /// (trips added by the scenario may be referenced as well)
+    pub fn from_file(filepath: &str, model: &Model) -> Result<Self, Error> {
+        let content = fs::read_to_string(filepath).map_err(|source| Error::Io {
+            file: filepath.to_string(),
+            source,
+        })?;
+
+        let invalid_config = |message: String| Error::InvalidConfig {
+            file: filepath.to_string(),
+            message,
+        };
+
+        let scenario: Self = if Path::new(filepath).extension().is_some_and(|extension| extension == "toml") {
+            toml::from_str(&content).map_err(|error| invalid_config(error.to_string()))?
+        } else {
+            serde_json::from_str(&content).map_err(|error| invalid_config(error.to_string()))?
+        };
+
+        let referenced_trips = scenario.cancelled_trips.iter()
+            .chain(scenario.delays.iter().map(|delay| &delay.trip))
+            .chain(scenario.capacity_reductions.iter().map(|capacity_reduction| &capacity_reduction.trip));
+
+        for trip_id in referenced_trips {
+            if !model.trips_nodes.contains_key(trip_id) && !scenario.added_trips.iter().any(|trip| trip.id == *trip_id) {
+                return Err(invalid_config(format!("unknown trip {}", trip_id)));
+            }
+        }
+
+        for station_id in scenario.closed_stations.iter() {
+            if !model.stations_transfers.contains_key(station_id) {
+                return Err(invalid_config(format!("unknown station {}", station_id)));
+            }
+        }
+
+        let referenced_stations = scenario.added_trips.iter().flat_map(|trip| vec![trip.from_station, trip.to_station])
+            .chain(scenario.added_footpaths.iter().flat_map(|footpath| vec![footpath.from_station, footpath.to_station]))
+            .chain(scenario.transfer_times.iter().map(|transfer_time| transfer_time.station));
+
+        for station_id in referenced_stations {
+            if !model.stations_name.contains_key(&station_id) {
+                return Err(invalid_config(format!("unknown station {}", station_id)));
+            }
+        }
+
+        for trip in scenario.added_trips.iter() {
+            if trip.arrival < trip.departure || trip.first_class_seats > trip.seats || trip.seats > trip.capacity {
+                return Err(invalid_config(format!("invalid added trip {} from station {} at {}", trip.id, trip.from_station, trip.departure)));
+            }
+        }
+
+        for delay in scenario.delays.iter() {
+            if let Some(station_id) = delay.station {
+                let departs_at_station = model.trip_nodes(delay.trip)
+                    .iter()
+                    .any(|node| model.graph[*node].is_departure() && model.graph[*node].station_id() == station_id);
+
+                if !departs_at_station {
+                    return Err(invalid_config(format!("trip {} does not depart at station {}", delay.trip, station_id)));
+                }
+            }
+        }
+
+        Ok(scenario)
+    }
+}
+
+impl Model {
+
+    /// applies the scenario of a TOML or JSON file (see `Scenario::from_file()`) and re-optimizes the selection
+    /// (groups_path_index of groups) with simulated annealing
+    ///
+    /// only groups that lost a path are searched again (see `Model::update_groups()`), they start with their best path,
+    /// all other groups with their previous path
+    ///
+    /// saves the groups without path after the scenario to `<filepath>_unserved_groups.csv` and the result of simulated
+    /// annealing to `<filepath>_simulated_annealing_edges.csv` and `<filepath>_simulated_annealing_groups.csv`
+    pub fn reoptimize_scenario(
+        &mut self,
+        scenario_filepath: &str,
+        groups: &[Group],
+        mut groups_path_index: Vec<usize>,
+        cost_model: &CostModel,
+        path_search: &PathSearch,
+        annealing_parameters: &AnnealingParameters,
+        filepath: &str,
+        seed: u64,
+    ) -> Result<(), Error> {
+        let scenario = Scenario::from_file(scenario_filepath, self)?;
+
+        let changes = self.apply_scenario(&scenario)?;
+
+        let mut disrupted_groups = groups.to_vec();
+        let searched_groups = self.update_groups(&mut disrupted_groups, &changes, cost_model, path_search);
+
+        Group::save_unserved_groups_to_csv(&disrupted_groups, &format!("{}_unserved_groups.csv", filepath));
+
+        println!(
+            "{} group(s) with {} passengers searched again, {} of them without path (see {}_unserved_groups.csv)",
+            searched_groups.len(),
+            searched_groups.iter().map(|group_index| disrupted_groups[*group_index].passengers).sum::<u64>(),
+            searched_groups.iter().filter(|group_index| disrupted_groups[**group_index].paths.is_empty()).count(),
+            filepath
+        );
+
+        for group_index in searched_groups.iter() {
+            groups_path_index[*group_index] = 0;
+        }
+
+        let (disrupted_groups, groups_path_index): (Vec<Group>, Vec<usize>) = disrupted_groups
+            .into_iter()
+            .zip(groups_path_index)
+            .filter(|(g, _)| !g.paths.is_empty())
+            .unzip();
+
+        let parameters = AnnealingParameters {
+            initial_state: InitialState::Selection(groups_path_index),
+            ..annealing_parameters.clone()
+        };
+
+        let selection_state = simulated_annealing::simulated_annealing(
+            &self.graph,
+            cost_model,
+            &disrupted_groups,
+            &format!("{}_simulated_annealing", filepath),
+            &parameters,
+            seed,
+        );
+
+        selection_state.save_strained_trip_edges_to_csv(&self.graph, &format!("{}_simulated_annealing_edges.csv", filepath));
+        selection_state.save_groups_to_csv(&self.graph, &format!("{}_simulated_annealing_groups.csv", filepath));
+
+        Ok(())
+    }
+}
@@ -1,48 +1,68 @@
 use petgraph::graph::{DiGraph, NodeIndex};
 use std::collections::HashMap;
 
-use super::{TimetableEdge, TimetableNode};
+use super::{transfer::{self, AlightTarget, TransferRule}, TimetableEdge, TimetableNode};
+use crate::{csv_reader::{self, Record}, error::Error};
 
 /// stop station 
 pub struct Station {
     pub id: u64, // unique identifer
     pub transfer_time: u64, // transfer time (minutes) at this station
     pub name: String, // station's name
+    pub step_free: bool, // station has step-free access (optional column, defaults to true)
 
     // key is the trip_id, value is Vec<>, because one trip may have multiple arrivals/departures at the same station
     pub arrivals: HashMap<u64, Vec<NodeIndex>>,
     pub departures: HashMap<u64, Vec<NodeIndex>>,
 
     pub transfers: Vec<NodeIndex>,
+
+    // platform of arrivals and departures (if given)
+    pub platforms: HashMap<NodeIndex, String>,
 }
 
 impl Station {
 
-    /// returns stations from maps
-    pub fn from_maps_to_map(station_maps: &Vec<HashMap<String, String>>) -> HashMap<u64, Self> {
+    /// returns stations from maps read from filepath
+    pub fn from_maps_to_map(station_maps: &[Record], filepath: &str) -> Result<HashMap<u64, Self>, Error> {
         println!("parsing {} station(s)", station_maps.len());
 
         let mut stations_map = HashMap::with_capacity(station_maps.len());
 
-        for station_map in station_maps.iter() {
-            let id = station_map.get("id").unwrap().parse().expect("Could not parse station id!");
-            let name = station_map.get("name").unwrap().clone();
+        for (index, station_map) in station_maps.iter().enumerate() {
+            let id = csv_reader::parse_field(station_map, filepath, index, "id")?;
+            let name = csv_reader::field(station_map, filepath, index, "name")?.to_string();
 
-            stations_map.insert(
-                id,
-                Self {
-                    id: id,
-                    transfer_time: station_map.get("transfer").unwrap().parse().unwrap(),
-                    name: name.clone(),
+            if stations_map.contains_key(&id) {
+                return Err(Error::DuplicateId {
+                    file: filepath.to_string(),
+                    row: csv_reader::row(index),
+                    id,
+                });
+            }
 
-                    arrivals: HashMap::new(),
-                    departures: HashMap::new(),
-                    transfers: Vec::new(),
-                },
-            );
+            let mut station = Self::new(id, csv_reader::parse_field(station_map, filepath, index, "transfer")?, name);
+            station.step_free = csv_reader::parse_optional_bool_field(station_map, filepath, index, "step_free")?.unwrap_or(true);
+
+            stations_map.insert(id, station);
         }
 
-        stations_map
+        Ok(stations_map)
+    }
+
+    /// creates a station without any arrivals/departures
+    pub fn new(id: u64, transfer_time: u64, name: String) -> Self {
+        Self {
+            id,
+            transfer_time,
+            name,
+            step_free: true,
+
+            arrivals: HashMap::new(),
+            departures: HashMap::new(),
+            transfers: Vec::new(),
+            platforms: HashMap::new(),
+        }
     }
 
     /// adds departure node to graph
@@ -51,6 +71,7 @@ impl Station {
         graph: &mut DiGraph<TimetableNode, TimetableEdge>,
         trip_id: u64,
         time: u64,
+        platform: Option<String>,
     ) -> NodeIndex {
         // create departure node
         let departure = graph.add_node(TimetableNode::Departure {
@@ -66,6 +87,10 @@ impl Station {
             .or_insert(Vec::new())
             .push(departure);
 
+        if let Some(platform) = platform {
+            self.platforms.insert(departure, platform);
+        }
+
         // create transfer node, as each departure also induces a corresponding transfer node at the station
         let transfer = graph.add_node(TimetableNode::Transfer {
             time,
@@ -88,6 +113,7 @@ impl Station {
         graph: &mut DiGraph<TimetableNode, TimetableEdge>,
         trip_id: u64,
         time: u64,
+        platform: Option<String>,
     ) -> NodeIndex {
         // create node
         let arrival = graph.add_node(TimetableNode::Arrival {
@@ -103,6 +129,10 @@ impl Station {
             .or_insert(Vec::new())
             .push(arrival);
 
+        if let Some(platform) = platform {
+            self.platforms.insert(arrival, platform);
+        }
+
         arrival
     }
 
@@ -110,10 +140,15 @@ impl Station {
     ///
     /// consumes self to prevent programmers to add departures or arrivals afterwards ;)
     ///
+    /// transfer_rules of this station replace its transfer time for some arrivals and departures (see `transfer::alight_targets()`),
+    /// departures that are boarded directly get their own transfer node in direct_transfers
+    ///
     /// returns the transfer nodes (for departure) and the main arrival nodes
     pub fn connect(
         mut self,
         graph: &mut DiGraph<TimetableNode, TimetableEdge>,
+        transfer_rules: &[TransferRule],
+        direct_transfers: &mut HashMap<NodeIndex, NodeIndex>,
     ) -> (Vec<NodeIndex>, Vec<NodeIndex>) {
 
         // FIRST: sort transfers list by time (first tuple element)
@@ -135,22 +170,17 @@ impl Station {
         }
 
         // THIRD: iterate over all arrivals and connect them to the station's next available transfer
+        // (requires transfers to be sorted (earliest first)), departures before it with shorter transfer rules are boarded directly
         for arrival in self.arrivals.values().flatten() {
-            let arrival_time = graph[*arrival].time();
-            let earliest_transfer_time = arrival_time + self.transfer_time;
+            let alight_targets = transfer::alight_targets(graph, &self.transfers, *arrival, self.transfer_time, transfer_rules, &self.platforms);
 
-            // try to find next transfer node at this station (requires transfers to be sorted (earliest first))
-            for transfer in self.transfers.iter() {
-                if earliest_transfer_time <= graph[*transfer].time() {
-                    graph.add_edge(
-                        *arrival,
-                        *transfer,
-                        TimetableEdge::Alight {
-                            duration: self.transfer_time,
-                        },
-                    );
-                    break; // we connected a reachable transfer node -> break search loop
-                }
+            for (alight_target, duration) in alight_targets {
+                let transfer = match alight_target {
+                    AlightTarget::Transfer(transfer) => transfer,
+                    AlightTarget::Departure(departure) => transfer::direct_transfer(graph, direct_transfers, departure).0,
+                };
+
+                graph.add_edge(*arrival, transfer, TimetableEdge::Alight {duration});
             }
         }
 
@@ -0,0 +1,223 @@
+use std::collections::HashMap;
+
+use petgraph::{graph::{DiGraph, EdgeIndex, NodeIndex}, EdgeDirection::Outgoing};
+use serde::{Deserialize, Serialize};
+
+use super::{update::ModelChanges, Model, TimetableEdge, TimetableNode};
+use crate::{csv_reader::{self, Record}, error::Error};
+
+/// minimum transfer time at a station between two trips or platforms, replaces the transfer time of the station
+///
+/// a rule applies to a transfer if all given fields match the arrival and the departure,
+/// the most specific rule wins (trips count more than platforms, the first rule of the file on ties)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransferRule {
+    pub station: u64,
+    pub from_trip: Option<u64>,        // trip of the arrival (any trip if None)
+    pub to_trip: Option<u64>,          // trip of the departure (any trip if None)
+    pub from_platform: Option<String>, // platform of the arrival (any platform if None)
+    pub to_platform: Option<String>,   // platform of the departure (any platform if None)
+    pub duration: u64,                 // minimum transfer time (minutes)
+    pub guaranteed: bool,              // to_trip waits for from_trip if it is delayed (see `Model::hold_guaranteed_connections()`)
+}
+
+/// target of an Alight edge
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlightTarget {
+    Transfer(NodeIndex),  // transfer node of the station, all later departures are reachable by WaitAtStation edges
+    Departure(NodeIndex), // only this departure, boarded from its direct transfer node (see `direct_transfer()`)
+}
+
+impl TransferRule {
+
+    /// returns transfer rules from maps read from filepath
+    ///
+    /// all columns but station and duration are optional, guaranteed connections (`1` or `true`) require from_trip and to_trip
+    pub fn from_maps_to_vec(transfer_maps: &[Record], filepath: &str) -> Result<Vec<Self>, Error> {
+        println!("parsing {} transfer rule(s)", transfer_maps.len());
+
+        let mut transfer_rules = Vec::with_capacity(transfer_maps.len());
+
+        for (index, transfer_map) in transfer_maps.iter().enumerate() {
+            let invalid_value = |column: &str, value: &str| Error::InvalidValue {
+                file: filepath.to_string(),
+                row: csv_reader::row(index),
+                column: column.to_string(),
+                value: value.to_string(),
+            };
+
+            let from_trip = csv_reader::parse_optional_field(transfer_map, filepath, index, "from_trip")?;
+            let to_trip = csv_reader::parse_optional_field(transfer_map, filepath, index, "to_trip")?;
+
+            let guaranteed = match csv_reader::optional_field(transfer_map, "guaranteed").map(|value| value.trim()) {
+                None | Some("0") | Some("false") => false,
+                Some(value @ "1") | Some(value @ "true") if from_trip.is_none() || to_trip.is_none() => return Err(invalid_value("guaranteed", value)),
+                Some("1") | Some("true") => true,
+                Some(value) => return Err(invalid_value("guaranteed", value)),
+            };
+
+            transfer_rules.push(Self {
+                station: csv_reader::parse_field(transfer_map, filepath, index, "station")?,
+                from_trip,
+                to_trip,
+                from_platform: csv_reader::optional_field(transfer_map, "from_platform").map(|platform| platform.trim().to_string()),
+                to_platform: csv_reader::optional_field(transfer_map, "to_platform").map(|platform| platform.trim().to_string()),
+                duration: csv_reader::parse_field(transfer_map, filepath, index, "duration")?,
+                guaranteed,
+            });
+        }
+
+        Ok(transfer_rules)
+    }
+
+    /// returns how specific the rule is (a given trip counts more than a given platform)
+    fn specificity(&self) -> usize {
+        2 * (self.from_trip.is_some() as usize + self.to_trip.is_some() as usize)
+            + self.from_platform.is_some() as usize
+            + self.to_platform.is_some() as usize
+    }
+}
+
+/// returns true if node is of trip and at platform (if given)
+fn matches(graph: &DiGraph<TimetableNode, TimetableEdge>, platforms: &HashMap<NodeIndex, String>, node: NodeIndex, trip: Option<u64>, platform: &Option<String>) -> bool {
+    trip.is_none_or(|trip| graph[node].trip_id() == Some(trip))
+        && platform.as_ref().is_none_or(|platform| platforms.get(&node) == Some(platform))
+}
+
+/// returns the departure boarded from a transfer node
+fn boarded_departure(graph: &DiGraph<TimetableNode, TimetableEdge>, transfer: NodeIndex) -> NodeIndex {
+    graph.neighbors_directed(transfer, Outgoing).find(|node| graph[*node].is_departure()).unwrap()
+}
+
+/// returns the Alight edges (target and duration) of an arrival at a station with transfers (sorted by time)
+///
+/// without applying rules, the arrival alights to the first transfer after the transfer time of the station. Otherwise it alights
+/// to the first transfer after which every departure respects its minimum transfer time, and departures before that transfer
+/// that are reachable in time are boarded directly (only these departures get an additional transfer node)
+pub fn alight_targets(
+    graph: &DiGraph<TimetableNode, TimetableEdge>,
+    transfers: &[NodeIndex],
+    arrival: NodeIndex,
+    transfer_time: u64,
+    transfer_rules: &[TransferRule],
+    platforms: &HashMap<NodeIndex, String>,
+) -> Vec<(AlightTarget, u64)> {
+    let arrival_time = graph[arrival].time();
+    let next_transfer = |time: u64| transfers.iter().position(|transfer| time <= graph[*transfer].time()).unwrap_or(transfers.len());
+
+    let mut first_transfer = next_transfer(arrival_time + transfer_time);
+
+    let arrival_rules: Vec<&TransferRule> = transfer_rules
+        .iter()
+        .filter(|rule| matches(graph, platforms, arrival, rule.from_trip, &rule.from_platform))
+        .collect();
+
+    if arrival_rules.is_empty() {
+        return transfers.get(first_transfer).map(|transfer| vec![(AlightTarget::Transfer(*transfer), transfer_time)]).unwrap_or_default();
+    }
+
+    // only departures within the window may have another transfer time than the station
+    let min_duration = arrival_rules.iter().map(|rule| rule.duration).min().unwrap().min(transfer_time);
+    let max_duration = arrival_rules.iter().map(|rule| rule.duration).max().unwrap().max(transfer_time);
+    let window_start = next_transfer(arrival_time + min_duration);
+    let window_end = next_transfer(arrival_time + max_duration);
+
+    let departures_duration: Vec<(NodeIndex, u64)> = transfers[window_start..window_end]
+        .iter()
+        .map(|transfer| {
+            let departure = boarded_departure(graph, *transfer);
+            let rule = arrival_rules
+                .iter()
+                .filter(|rule| matches(graph, platforms, departure, rule.to_trip, &rule.to_platform))
+                .rev()
+                .max_by_key(|rule| rule.specificity());
+
+            (departure, rule.map_or(transfer_time, |rule| rule.duration))
+        })
+        .collect();
+
+    // all departures after the first transfer have to be reachable in time
+    for (offset, (departure, duration)) in departures_duration.iter().enumerate() {
+        if graph[*departure].time() < arrival_time + duration {
+            first_transfer = first_transfer.max(window_start + offset + 1);
+        }
+    }
+
+    let mut targets: Vec<(AlightTarget, u64)> = departures_duration
+        .iter()
+        .enumerate()
+        .filter(|(offset, (departure, duration))| window_start + offset < first_transfer && arrival_time + duration <= graph[*departure].time())
+        .map(|(_, (departure, duration))| (AlightTarget::Departure(*departure), *duration))
+        .collect();
+
+    if let Some(transfer) = transfers.get(first_transfer) {
+        targets.push((AlightTarget::Transfer(*transfer), transfer_time));
+    }
+
+    targets
+}
+
+/// returns the direct transfer node of a departure (a Transfer node connected to the departure only by a Board edge)
+/// and the added Board edge if the node was added
+pub fn direct_transfer(
+    graph: &mut DiGraph<TimetableNode, TimetableEdge>,
+    direct_transfers: &mut HashMap<NodeIndex, NodeIndex>,
+    departure: NodeIndex,
+) -> (NodeIndex, Option<EdgeIndex>) {
+    if let Some(transfer) = direct_transfers.get(&departure) {
+        return (*transfer, None);
+    }
+
+    let transfer = graph.add_node(TimetableNode::Transfer {
+        time: graph[departure].time(),
+        station_id: graph[departure].station_id(),
+        station_name: graph[departure].station_name(),
+    });
+    let board = graph.add_edge(transfer, departure, TimetableEdge::Board);
+
+    direct_transfers.insert(departure, transfer);
+
+    (transfer, Some(board))
+}
+
+impl Model {
+
+    /// holds guaranteed connections after delays: departures of to_trip before the arrival of from_trip plus the transfer time
+    /// are delayed to that time (along with the rest of to_trip, see `Model::delay_trip()`)
+    ///
+    /// repeats until all guaranteed connections hold, as holds may propagate along several connections
+    ///
+    /// returns the number of holds
+    pub fn hold_guaranteed_connections(&mut self, changes: &mut ModelChanges) -> usize {
+        let guaranteed_rules: Vec<TransferRule> = self.transfer_rules.values().flatten().filter(|rule| rule.guaranteed).cloned().collect();
+        let mut n_holds = 0;
+
+        // every round holds at least one departure, stop after as many rounds as rules in case of a cycle
+        for _ in 0..guaranteed_rules.len() {
+            let mut is_held = false;
+
+            for rule in guaranteed_rules.iter() {
+                let (from_trip, to_trip) = (rule.from_trip.unwrap(), rule.to_trip.unwrap());
+
+                let arrival = self.trip_nodes(from_trip).into_iter().find(|node| self.graph[*node].is_arrival() && self.graph[*node].station_id() == rule.station);
+                let departure = self.trip_nodes(to_trip).into_iter().find(|node| self.graph[*node].is_departure() && self.graph[*node].station_id() == rule.station);
+
+                if let (Some(arrival), Some(departure)) = (arrival, departure) {
+                    let earliest_departure = self.graph[arrival].time() + rule.duration;
+
+                    if self.graph[departure].time() < earliest_departure {
+                        self.delay_trip(to_trip, Some(rule.station), earliest_departure - self.graph[departure].time(), changes);
+                        is_held = true;
+                        n_holds += 1;
+                    }
+                }
+            }
+
+            if !is_held {
+                break;
+            }
+        }
+
+        n_holds
+    }
+}
@@ -4,6 +4,7 @@ use serde::{Deserialize, Serialize};
 use petgraph::graph::DiGraph;
 
 use super::{station::Station, TimetableEdge, TimetableNode};
+use crate::{csv_reader::{self, Record}, error::Error, time};
 
 /// a trip from a station to another station
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -13,35 +14,89 @@ pub struct Trip {
     pub departure: u64, // departure time
     pub to_station: u64, // station to a arrive
     pub arrival: u64, // arrival time
-    pub capacity: u64, // capacity on this trip 
+    pub capacity: u64, // capacity on this trip (crush load including standing passengers)
+    pub seats: u64, // seated capacity (optional, defaults to capacity)
+    pub first_class_seats: u64, // seats reserved for first class (optional, defaults to 0)
+    pub from_platform: Option<String>, // platform of the departure (optional, see transfer.rs)
+    pub to_platform: Option<String>, // platform of the arrival (optional)
 }
 
 impl Trip {
 
-    /// returns trips from maps
-    pub fn from_maps_to_vec(trip_maps: &Vec<HashMap<String, String>>) -> Vec<Self> {
+    /// returns trips from maps read from filepath
+    ///
+    /// times are parsed with `time::parse_time()` relative to reference_date, a time of day before the previous time
+    /// of the same trip (in order of records) lies on the next day (trips crossing midnight)
+    ///
+    /// the columns seats, first_class_seats, from_platform and to_platform are optional,
+    /// first_class_seats must not exceed seats and seats must not exceed capacity
+    pub fn from_maps_to_vec(trip_maps: &[Record], filepath: &str, reference_date: Option<i64>) -> Result<Vec<Self>, Error> {
         println!("parsing {} trip(s)", trip_maps.len());
 
         let mut trips = Vec::with_capacity(trip_maps.len());
+        let mut trips_last_time: HashMap<u64, u64> = HashMap::new();
 
-        for trip_map in trip_maps.iter() {
-            let id = trip_map.get("id").unwrap().parse().unwrap();
-            let from_station = trip_map.get("from_station").unwrap().parse().unwrap();
-            let to_station = trip_map.get("to_station").unwrap().parse().unwrap();
+        for (index, trip_map) in trip_maps.iter().enumerate() {
+            let id = csv_reader::parse_field(trip_map, filepath, index, "id")?;
+            let from_station = csv_reader::parse_field(trip_map, filepath, index, "from_station")?;
+            let to_station = csv_reader::parse_field(trip_map, filepath, index, "to_station")?;
 
             // println!("{}_{}->{}", id, from_station, to_station);
 
+            let departure_value = csv_reader::field(trip_map, filepath, index, "departure")?;
+            let arrival_value = csv_reader::field(trip_map, filepath, index, "arrival")?;
+
+            let mut departure = csv_reader::parse_time_field(trip_map, filepath, index, "departure", reference_date)?;
+            if time::is_time_of_day(departure_value) {
+                departure = time::roll_over(departure, trips_last_time.get(&id).copied().unwrap_or(0));
+            }
+
+            let mut arrival = csv_reader::parse_time_field(trip_map, filepath, index, "arrival", reference_date)?;
+            if time::is_time_of_day(arrival_value) {
+                arrival = time::roll_over(arrival, departure);
+            }
+
+            if arrival < departure {
+                return Err(Error::InvalidValue {
+                    file: filepath.to_string(),
+                    row: csv_reader::row(index),
+                    column: "arrival".to_string(),
+                    value: arrival_value.to_string(),
+                });
+            }
+
+            trips_last_time.insert(id, arrival);
+
+            let capacity = csv_reader::parse_field(trip_map, filepath, index, "capacity")?;
+            let seats = csv_reader::parse_optional_field(trip_map, filepath, index, "seats")?.unwrap_or(capacity);
+            let first_class_seats = csv_reader::parse_optional_field(trip_map, filepath, index, "first_class_seats")?.unwrap_or(0);
+
+            for (column, value, limit) in [("seats", seats, capacity), ("first_class_seats", first_class_seats, seats)].iter() {
+                if value > limit {
+                    return Err(Error::InvalidValue {
+                        file: filepath.to_string(),
+                        row: csv_reader::row(index),
+                        column: column.to_string(),
+                        value: value.to_string(),
+                    });
+                }
+            }
+
             trips.push(Self {
                 id,
                 from_station,
-                departure: trip_map.get("departure").unwrap().parse().unwrap(),
+                departure,
                 to_station,
-                arrival: trip_map.get("arrival").unwrap().parse().unwrap(),
-                capacity: trip_map.get("capacity").unwrap().parse().unwrap(),
+                arrival,
+                capacity,
+                seats,
+                first_class_seats,
+                from_platform: csv_reader::optional_field(trip_map, "from_platform").map(|platform| platform.trim().to_string()),
+                to_platform: csv_reader::optional_field(trip_map, "to_platform").map(|platform| platform.trim().to_string()),
             });
         }
 
-        trips
+        Ok(trips)
     }
 
     /// connects a departure node with an arrival node with a trip edge
@@ -54,13 +109,13 @@ impl Trip {
             "from_station {} of trip {} could not be found",
             &self.from_station, self.id
         ));
-        let departure = from_station.add_departure(graph, self.id, self.departure);
+        let departure = from_station.add_departure(graph, self.id, self.departure, self.from_platform);
 
         let to_station = stations.get_mut(&self.to_station).expect(&format!(
             "to_station {} of trip {} could not be found",
             &self.to_station, self.id
         ));
-        let arrival = to_station.add_arrival(graph, self.id, self.arrival);
+        let arrival = to_station.add_arrival(graph, self.id, self.arrival, self.to_platform);
 
         // connect start and end of this ride
         graph.add_edge(
@@ -69,7 +124,8 @@ impl Trip {
             TimetableEdge::Trip {
                 duration: self.arrival - self.departure,
                 capacity: self.capacity,
-                utilization: 0,
+                seats: self.seats,
+                first_class_seats: self.first_class_seats,
             },
         );
     }
@@ -0,0 +1,658 @@
+use std::{collections::{HashMap, HashSet}, time::Instant};
+
+use petgraph::{graph::{DiGraph, EdgeIndex, NodeIndex}, visit::EdgeRef, EdgeDirection::{Incoming, Outgoing}};
+
+use super::{cost_model::CostModel, footpath::Footpath, graph_weight::{TimetableEdge, TimetableNode}, group::Group, path::PathSearch, scenario::Scenario, transfer::{self, AlightTarget}, trip::Trip, Model};
+use crate::error::Error;
+
+/// edges changed by updates of a model, used to repair the paths of groups (see `Group::repair_paths()`)
+///
+/// removing an edge moves the last edge of the graph to its index, so unchanged edges may get a new index as well
+#[derive(Debug, Default)]
+pub struct ModelChanges {
+    pub invalid_edges: HashSet<EdgeIndex>,          // removed or modified edges (indices before the updates)
+    pub removed_edges: HashSet<EdgeIndex>,          // removed edges, paths along them are not feasible anymore (indices before the updates)
+    pub moved_edges: HashMap<EdgeIndex, EdgeIndex>, // index before -> after the updates of edges moved by removals
+
+    original_edges: HashMap<EdgeIndex, Option<EdgeIndex>>, // index before the updates of moved or added edges (None if added)
+}
+
+impl ModelChanges {
+
+    /// returns the index before the updates of an edge (None if it was added)
+    fn original(&self, edge: EdgeIndex) -> Option<EdgeIndex> {
+        self.original_edges.get(&edge).copied().unwrap_or(Some(edge))
+    }
+
+    /// marks an edge as modified
+    fn invalidate(&mut self, edge: EdgeIndex) {
+        if let Some(original) = self.original(edge) {
+            self.invalid_edges.insert(original);
+        }
+    }
+
+    /// records an added edge
+    fn add(&mut self, edge: EdgeIndex) {
+        self.original_edges.insert(edge, None);
+    }
+
+    /// records the removal of edge, which moved the former last edge of the graph to its index
+    fn remove(&mut self, edge: EdgeIndex, last: EdgeIndex) {
+        self.invalidate(edge);
+
+        if let Some(original) = self.original(edge) {
+            self.removed_edges.insert(original);
+        }
+
+        if edge != last {
+            let last_original = self.original(last);
+            if let Some(original) = last_original {
+                self.moved_edges.insert(original, edge);
+            }
+            self.original_edges.insert(edge, last_original);
+        }
+
+        self.original_edges.remove(&last);
+    }
+}
+
+impl Model {
+
+    /// returns the Departure and Arrival nodes of a trip in order of time (without nodes of cancelled segments)
+    ///
+    /// only visits the nodes of the trip (see `Model::trips_nodes`)
+    pub fn trip_nodes(&self, trip_id: u64) -> Vec<NodeIndex> {
+        let mut trip_nodes = self.trips_nodes.get(&trip_id).cloned().unwrap_or_default();
+
+        trip_nodes.sort_by_key(|node| self.graph[*node].time());
+        trip_nodes
+    }
+
+    /// returns the Trip edges of a trip
+    fn trip_edges(&self, trip_id: u64) -> Vec<EdgeIndex> {
+        self.trip_nodes(trip_id)
+            .into_iter()
+            .flat_map(|node| self.graph.edges_directed(node, Outgoing))
+            .filter(|edge| edge.weight().is_trip())
+            .map(|edge| edge.id())
+            .collect()
+    }
+
+    /// sets the capacity of all Trip edges of a trip, seats are reduced to the new capacity (the paths of groups stay valid)
+    pub fn set_trip_capacity(&mut self, trip_id: u64, new_capacity: u64) {
+        for edge in self.trip_edges(trip_id) {
+            if let TimetableEdge::Trip {duration: _, capacity, seats, first_class_seats} = &mut self.graph[edge] {
+                *capacity = new_capacity;
+                *seats = (*seats).min(new_capacity);
+                *first_class_seats = (*first_class_seats).min(*seats);
+            }
+        }
+    }
+
+    /// adds a trip segment (a record of trips.csv) without rebuilding the model
+    ///
+    /// creates its Departure, Transfer and Arrival nodes like `Trip::connect()`, connects it to the other segments of the trip
+    /// with WaitInTrain edges like `Station::connect()` and rewires both stations
+    ///
+    /// returns an error (without changing the model) if a station of the trip is not part of the model
+    pub fn add_trip(&mut self, trip: Trip, changes: &mut ModelChanges) -> Result<(), Error> {
+        let station_name = |station_id: u64| {
+            self.stations_name.get(&station_id).cloned().ok_or(Error::UnknownTripStation {
+                trip_id: trip.id,
+                station_id,
+            })
+        };
+
+        let from_station_name = station_name(trip.from_station)?;
+        let to_station_name = station_name(trip.to_station)?;
+
+        // other segments of the trip, before the new nodes exist
+        let trip_nodes = self.trip_nodes(trip.id);
+
+        let departure = self.graph.add_node(TimetableNode::Departure {
+            trip_id: trip.id,
+            time: trip.departure,
+            station_id: trip.from_station,
+            station_name: from_station_name.clone(),
+        });
+        let transfer = self.graph.add_node(TimetableNode::Transfer {
+            time: trip.departure,
+            station_id: trip.from_station,
+            station_name: from_station_name,
+        });
+        let arrival = self.graph.add_node(TimetableNode::Arrival {
+            trip_id: trip.id,
+            time: trip.arrival,
+            station_id: trip.to_station,
+            station_name: to_station_name,
+        });
+
+        let mut new_edges = vec![
+            (transfer, departure, TimetableEdge::Board),
+            (departure, arrival, TimetableEdge::Trip {
+                duration: trip.arrival - trip.departure,
+                capacity: trip.capacity,
+                seats: trip.seats,
+                first_class_seats: trip.first_class_seats,
+            }),
+        ];
+
+        // stay in the train: from the latest arrival of the trip at from_station before the departure
+        // and to the earliest departure of the trip at to_station after the arrival
+        let has_wait_in_train = |node: NodeIndex, direction| self.graph.edges_directed(node, direction).any(|edge| edge.weight().is_wait_in_train());
+
+        let previous_arrival = trip_nodes.iter().copied().rev().find(|node| {
+            self.graph[*node].is_arrival() && self.graph[*node].station_id() == trip.from_station
+                && self.graph[*node].time() <= trip.departure && !has_wait_in_train(*node, Outgoing)
+        });
+        if let Some(previous_arrival) = previous_arrival {
+            new_edges.push((previous_arrival, departure, TimetableEdge::WaitInTrain {
+                duration: trip.departure - self.graph[previous_arrival].time(),
+            }));
+        }
+
+        let next_departure = trip_nodes.iter().copied().find(|node| {
+            self.graph[*node].is_departure() && self.graph[*node].station_id() == trip.to_station
+                && trip.arrival <= self.graph[*node].time() && !has_wait_in_train(*node, Incoming)
+        });
+        if let Some(next_departure) = next_departure {
+            new_edges.push((arrival, next_departure, TimetableEdge::WaitInTrain {
+                duration: self.graph[next_departure].time() - trip.arrival,
+            }));
+        }
+
+        for (source, target, weight) in new_edges {
+            let edge = self.graph.add_edge(source, target, weight);
+            changes.add(edge);
+        }
+
+        self.stations_transfers.entry(trip.from_station).or_default().push(transfer);
+        self.stations_arrivals.entry(trip.to_station).or_default().push(arrival);
+        self.stations_departures.entry(trip.from_station).or_default().push(departure);
+        self.trips_nodes.entry(trip.id).or_default().extend([departure, arrival].iter().copied());
+
+        if let Some(platform) = trip.from_platform {
+            self.platforms.insert(departure, platform);
+        }
+        if let Some(platform) = trip.to_platform {
+            self.platforms.insert(arrival, platform);
+        }
+
+        self.rewire_stations(&[trip.from_station, trip.to_station].iter().copied().collect(), changes);
+
+        Ok(())
+    }
+
+    /// adds a footpath without rebuilding the model, connects it like `Footpath::connect()`
+    pub fn add_footpath(&mut self, footpath: Footpath, changes: &mut ModelChanges) {
+        let station_ids: HashSet<u64> = [footpath.from_station, footpath.to_station].iter().copied().collect();
+
+        for station_id in station_ids.iter() {
+            self.stations_footpaths.entry(*station_id).or_default().push(self.footpaths.len());
+        }
+
+        self.footpaths.push(footpath);
+        self.rewire_stations(&station_ids, changes);
+    }
+
+    /// sets the transfer time of a station without rebuilding the model and rewires its Alight edges
+    pub fn set_transfer_time(&mut self, station_id: u64, transfer_time: u64, changes: &mut ModelChanges) {
+        self.stations_transfer_time.insert(station_id, transfer_time);
+        self.rewire_stations(&[station_id].iter().copied().collect(), changes);
+    }
+
+    /// delays a trip by minutes from its departure at from_station on (from its first departure if None)
+    ///
+    /// shifts all Departure and Arrival nodes of the trip following this departure along with the transfers of the departures,
+    /// extends the WaitInTrain edge to the first delayed departure and rewires the affected stations
+    pub fn delay_trip(&mut self, trip_id: u64, from_station: Option<u64>, minutes: u64, changes: &mut ModelChanges) {
+        let first_departure = self.trip_nodes(trip_id)
+            .into_iter()
+            .find(|node| self.graph[*node].is_departure() && from_station.is_none_or(|station_id| self.graph[*node].station_id() == station_id));
+
+        let first_departure = match first_departure {
+            Some(first_departure) if minutes > 0 => first_departure,
+            _ => return,
+        };
+
+        // follow the trip along its Trip and WaitInTrain edges
+        let mut delayed_nodes = vec![first_departure];
+        let mut index = 0;
+        while index < delayed_nodes.len() {
+            let node = delayed_nodes[index];
+            delayed_nodes.extend(
+                self.graph
+                    .edges_directed(node, Outgoing)
+                    .filter(|edge| edge.weight().is_trip() || edge.weight().is_wait_in_train())
+                    .map(|edge| edge.target())
+            );
+            index += 1;
+        }
+
+        // each departure is boarded from its own transfer node
+        let transfers: Vec<NodeIndex> = delayed_nodes
+            .iter()
+            .flat_map(|node| self.graph.neighbors_directed(*node, Incoming))
+            .filter(|node| self.graph[*node].is_transfer())
+            .collect();
+        delayed_nodes.extend(transfers);
+
+        let wait_in_train_edges: Vec<EdgeIndex> = self.graph
+            .edges_directed(first_departure, Incoming)
+            .filter(|edge| edge.weight().is_wait_in_train())
+            .map(|edge| edge.id())
+            .collect();
+
+        for edge in wait_in_train_edges {
+            if let TimetableEdge::WaitInTrain {duration} = &mut self.graph[edge] {
+                *duration += minutes;
+            }
+        }
+
+        let mut station_ids = HashSet::new();
+        for node in delayed_nodes.iter() {
+            self.graph[*node].delay(minutes);
+            station_ids.insert(self.graph[*node].station_id());
+
+            // paths along the node arrive later now
+            let edges: Vec<EdgeIndex> = self.graph
+                .edges_directed(*node, Outgoing)
+                .chain(self.graph.edges_directed(*node, Incoming))
+                .map(|edge| edge.id())
+                .collect();
+
+            for edge in edges {
+                changes.invalidate(edge);
+            }
+        }
+
+        self.rewire_stations(&station_ids, changes);
+    }
+
+    /// sets the times of Departure and Arrival nodes of a trip (nodes without a new time keep theirs),
+    /// no node departs or arrives before its predecessor in the trip (later nodes are pushed back if necessary)
+    ///
+    /// updates the transfers of the departures and the durations of Trip and WaitInTrain edges, then rewires the affected stations
+    pub fn set_trip_times(&mut self, trip_id: u64, times: &HashMap<NodeIndex, u64>, changes: &mut ModelChanges) {
+        let in_trip = |edge: &TimetableEdge| edge.is_trip() || edge.is_wait_in_train();
+
+        // first nodes of the trip (or of its parts left by cancelled segments)
+        let first_nodes: Vec<NodeIndex> = self.trip_nodes(trip_id)
+            .into_iter()
+            .filter(|node| !self.graph.edges_directed(*node, Incoming).any(|edge| in_trip(edge.weight())))
+            .collect();
+
+        let mut changed_nodes = Vec::new();
+
+        for first_node in first_nodes {
+            let mut node_option = Some(first_node);
+            let mut previous_time = 0;
+
+            while let Some(node) = node_option {
+                let time = times.get(&node).copied().unwrap_or_else(|| self.graph[node].time()).max(previous_time);
+
+                if time != self.graph[node].time() {
+                    self.graph[node].set_time(time);
+                    changed_nodes.push(node);
+                }
+
+                previous_time = time;
+                node_option = self.graph
+                    .edges_directed(node, Outgoing)
+                    .find(|edge| in_trip(edge.weight()))
+                    .map(|edge| edge.target());
+            }
+        }
+
+        // each departure is boarded from its own transfer node
+        let transfers: Vec<NodeIndex> = changed_nodes
+            .iter()
+            .flat_map(|node| self.graph.neighbors_directed(*node, Incoming))
+            .filter(|node| self.graph[*node].is_transfer())
+            .collect();
+
+        for transfer in transfers.iter() {
+            let departure = self.graph.neighbors_directed(*transfer, Outgoing).find(|node| self.graph[*node].is_departure()).unwrap();
+            let time = self.graph[departure].time();
+            self.graph[*transfer].set_time(time);
+        }
+        changed_nodes.extend(transfers);
+
+        let mut station_ids = HashSet::new();
+        for node in changed_nodes.iter() {
+            station_ids.insert(self.graph[*node].station_id());
+
+            let edges: Vec<EdgeIndex> = self.graph
+                .edges_directed(*node, Outgoing)
+                .chain(self.graph.edges_directed(*node, Incoming))
+                .map(|edge| edge.id())
+                .collect();
+
+            for edge in edges {
+                // WaitAtStation, Alight and Walk edges are rewired
+                if in_trip(&self.graph[edge]) {
+                    let (source, target) = self.graph.edge_endpoints(edge).unwrap();
+                    let new_duration = self.graph[target].time() - self.graph[source].time();
+
+                    match &mut self.graph[edge] {
+                        TimetableEdge::Trip {duration, capacity: _, seats: _, first_class_seats: _} => *duration = new_duration,
+                        TimetableEdge::WaitInTrain {duration} => *duration = new_duration,
+                        _ => {}
+                    }
+                }
+
+                changes.invalidate(edge);
+            }
+        }
+
+        self.rewire_stations(&station_ids, changes);
+    }
+
+    /// removes (cancels) all segments of a trip, see cancel_trip_segments()
+    pub fn remove_trip(&mut self, trip_id: u64, changes: &mut ModelChanges) {
+        let trip_edges = self.trip_edges(trip_id);
+        self.cancel_trip_segments(&trip_edges, changes);
+    }
+
+    /// closes a station: cancels all trip segments from or to the station (trips are cut there), see cancel_trip_segments()
+    pub fn close_station(&mut self, station_id: u64, changes: &mut ModelChanges) {
+        let no_nodes = Vec::new();
+        let departures = self.stations_departures.get(&station_id).unwrap_or(&no_nodes);
+        let arrivals = self.stations_arrivals.get(&station_id).unwrap_or(&no_nodes);
+
+        let trip_edges: Vec<EdgeIndex> = departures
+            .iter()
+            .flat_map(|departure| self.graph.edges_directed(*departure, Outgoing))
+            .chain(arrivals.iter().flat_map(|arrival| self.graph.edges_directed(*arrival, Incoming)))
+            .filter(|edge| edge.weight().is_trip())
+            .map(|edge| edge.id())
+            .collect();
+
+        self.cancel_trip_segments(&trip_edges, changes);
+    }
+
+    /// cancels trip segments (Trip edges): removes all edges of their Departure and Arrival nodes and the transfers of the departures
+    /// (the nodes stay in the graph without any edge), then rewires the affected stations
+    fn cancel_trip_segments(&mut self, trip_edges: &[EdgeIndex], changes: &mut ModelChanges) {
+        let mut cancelled_nodes = HashSet::new();
+
+        for edge in trip_edges.iter() {
+            let (departure, arrival) = self.graph.edge_endpoints(*edge).unwrap();
+            cancelled_nodes.insert(departure);
+            cancelled_nodes.insert(arrival);
+            cancelled_nodes.extend(self.graph.neighbors_directed(departure, Incoming).filter(|node| self.graph[*node].is_transfer()));
+        }
+
+        let station_ids: HashSet<u64> = cancelled_nodes.iter().map(|node| self.graph[*node].station_id()).collect();
+
+        for station_id in station_ids.iter() {
+            if let Some(transfers) = self.stations_transfers.get_mut(station_id) {
+                transfers.retain(|transfer| !cancelled_nodes.contains(transfer));
+            }
+            if let Some(arrivals) = self.stations_arrivals.get_mut(station_id) {
+                arrivals.retain(|arrival| !cancelled_nodes.contains(arrival));
+            }
+            if let Some(departures) = self.stations_departures.get_mut(station_id) {
+                departures.retain(|departure| !cancelled_nodes.contains(departure));
+            }
+        }
+
+        let trip_ids: HashSet<u64> = cancelled_nodes.iter().filter_map(|node| self.graph[*node].trip_id()).collect();
+        for trip_id in trip_ids.iter() {
+            if let Some(trip_nodes) = self.trips_nodes.get_mut(trip_id) {
+                trip_nodes.retain(|node| !cancelled_nodes.contains(node));
+            }
+        }
+
+        let edges = cancelled_nodes
+            .iter()
+            .flat_map(|node| self.graph.edges_directed(*node, Outgoing).chain(self.graph.edges_directed(*node, Incoming)))
+            .map(|edge| edge.id())
+            .collect();
+
+        self.remove_edges(edges, changes);
+        self.rewire_stations(&station_ids, changes);
+    }
+
+    /// applies the additions (trips, footpaths and transfer times) and disruptions of scenario to this model
+    /// (capacity reductions, delays, holds of guaranteed connections, cancellations and closures in this order)
+    ///
+    /// returns the changed edges to repair the paths of groups (see update_groups()),
+    /// or an error if an added trip references a station that is not part of the model (see `Model::add_trip()`)
+    pub fn apply_scenario(&mut self, scenario: &Scenario) -> Result<ModelChanges, Error> {
+        println!(
+            "apply_scenario(cancelled_trips={}, delays={}, closed_stations={}, capacity_reductions={}, added_trips={}, added_footpaths={}, transfer_times={})",
+            scenario.cancelled_trips.len(),
+            scenario.delays.len(),
+            scenario.closed_stations.len(),
+            scenario.capacity_reductions.len(),
+            scenario.added_trips.len(),
+            scenario.added_footpaths.len(),
+            scenario.transfer_times.len()
+        );
+
+        let start = Instant::now();
+        let mut changes = ModelChanges::default();
+
+        for trip in scenario.added_trips.iter() {
+            self.add_trip(trip.clone(), &mut changes)?;
+        }
+
+        for footpath in scenario.added_footpaths.iter() {
+            self.add_footpath(footpath.clone(), &mut changes);
+        }
+
+        for transfer_time in scenario.transfer_times.iter() {
+            self.set_transfer_time(transfer_time.station, transfer_time.minutes, &mut changes);
+        }
+
+        for capacity_reduction in scenario.capacity_reductions.iter() {
+            self.set_trip_capacity(capacity_reduction.trip, capacity_reduction.capacity);
+        }
+
+        for delay in scenario.delays.iter() {
+            self.delay_trip(delay.trip, delay.station, delay.minutes, &mut changes);
+        }
+
+        let n_holds = self.hold_guaranteed_connections(&mut changes);
+        if n_holds > 0 {
+            println!("held {} guaranteed connection(s)", n_holds);
+        }
+
+        for trip_id in scenario.cancelled_trips.iter() {
+            self.remove_trip(*trip_id, &mut changes);
+        }
+
+        for station_id in scenario.closed_stations.iter() {
+            self.close_station(*station_id, &mut changes);
+        }
+
+        println!(
+            "[apply_scenario()]: done ({}ms), {} edge(s) removed or modified, graph.edge_count()={}",
+            start.elapsed().as_millis(),
+            changes.invalid_edges.len(),
+            self.graph.edge_count()
+        );
+
+        Ok(changes)
+    }
+
+    /// repairs the paths of groups after the updates described by changes,
+    /// groups with a removed or modified edge in any path are searched again (all other groups keep their paths)
+    ///
+    /// returns the indices of the groups searched again
+    pub fn update_groups(&self, groups: &mut [Group], changes: &ModelChanges, cost_model: &CostModel, path_search: &PathSearch) -> Vec<usize> {
+        let mut searched_groups = Vec::new();
+
+        for (group_index, group) in groups.iter_mut().enumerate() {
+            if !group.repair_paths(changes) {
+                print!("[group={}]: ", group.id);
+                group.search_paths(self, cost_model, path_search);
+                group.add_unserved_path(cost_model);
+                searched_groups.push(group_index);
+            }
+        }
+
+        println!("searched {}/{} groups again", searched_groups.len(), groups.len());
+
+        searched_groups
+    }
+
+    /// removes edges from the graph
+    fn remove_edges(&mut self, mut edges: Vec<EdgeIndex>, changes: &mut ModelChanges) {
+        // in descending order: the last edge moved to the index of a removed edge is never removed afterwards
+        edges.sort_unstable_by(|edge_a, edge_b| edge_b.cmp(edge_a));
+        edges.dedup();
+
+        for edge in edges {
+            let last = EdgeIndex::new(self.graph.edge_count() - 1);
+            self.graph.remove_edge(edge);
+            changes.remove(edge, last);
+        }
+    }
+
+    /// rewires the WaitAtStation, Alight and Walk edges of stations after their transfers or arrivals changed
+    ///
+    /// creates the same edges as `Station::connect()` and `Footpath::connect()`, but keeps the edges that did not change
+    fn rewire_stations(&mut self, station_ids: &HashSet<u64>, changes: &mut ModelChanges) {
+        let graph = &self.graph;
+
+        // transfers have to be sorted by time
+        for station_id in station_ids.iter() {
+            if let Some(transfers) = self.stations_transfers.get_mut(station_id) {
+                transfers.sort_by_key(|transfer| graph[*transfer].time());
+            }
+        }
+
+        let mut station_ids: Vec<u64> = station_ids.iter().copied().collect();
+        station_ids.sort_unstable();
+
+        let no_nodes = Vec::new();
+        let no_transfer_rules = Vec::new();
+
+        // Alight edges respecting the transfer rules, departures that are boarded directly get their transfer node first
+        let mut alight_edges: Vec<(NodeIndex, AlightTarget, u64)> = Vec::new();
+
+        for station_id in station_ids.iter() {
+            let transfers = self.stations_transfers.get(station_id).unwrap_or(&no_nodes);
+            let transfer_time = self.stations_transfer_time.get(station_id).copied().unwrap_or(0);
+            let transfer_rules = self.transfer_rules.get(station_id).unwrap_or(&no_transfer_rules);
+
+            for arrival in self.stations_arrivals.get(station_id).unwrap_or(&no_nodes).iter() {
+                let alight_targets = transfer::alight_targets(&self.graph, transfers, *arrival, transfer_time, transfer_rules, &self.platforms);
+                alight_edges.extend(alight_targets.into_iter().map(|(alight_target, duration)| (*arrival, alight_target, duration)));
+            }
+        }
+
+        let mut required_edges: Vec<(NodeIndex, NodeIndex, TimetableEdge)> = Vec::new();
+
+        for (arrival, alight_target, duration) in alight_edges {
+            let transfer = match alight_target {
+                AlightTarget::Transfer(transfer) => transfer,
+                AlightTarget::Departure(departure) => {
+                    let (transfer, board) = transfer::direct_transfer(&mut self.graph, &mut self.direct_transfers, departure);
+                    if let Some(board) = board {
+                        changes.add(board);
+                    }
+                    transfer
+                }
+            };
+
+            required_edges.push((arrival, transfer, TimetableEdge::Alight {duration}));
+        }
+
+        let graph = &self.graph;
+        let mut existing_edges: Vec<EdgeIndex> = Vec::new();
+
+        for station_id in station_ids.iter() {
+            let transfers = self.stations_transfers.get(station_id).unwrap_or(&no_nodes);
+            let arrivals = self.stations_arrivals.get(station_id).unwrap_or(&no_nodes);
+
+            for transfer_pair in transfers.windows(2) {
+                required_edges.push((
+                    transfer_pair[0],
+                    transfer_pair[1],
+                    TimetableEdge::WaitAtStation {
+                        duration: graph[transfer_pair[1]].time() - graph[transfer_pair[0]].time(),
+                    },
+                ));
+            }
+
+            for transfer in transfers.iter() {
+                existing_edges.extend(
+                    graph.edges_directed(*transfer, Outgoing).filter(|edge| edge.weight().is_wait_at_station()).map(|edge| edge.id())
+                );
+                existing_edges.extend(
+                    graph.edges_directed(*transfer, Incoming).filter(|edge| edge.weight().is_walk()).map(|edge| edge.id())
+                );
+            }
+
+            for arrival in arrivals.iter() {
+                existing_edges.extend(
+                    graph.edges_directed(*arrival, Outgoing).filter(|edge| edge.weight().is_alight() || edge.weight().is_walk()).map(|edge| edge.id())
+                );
+            }
+        }
+
+        // footpaths from or to the stations (in order of footpaths)
+        let mut footpath_indices: Vec<usize> = station_ids
+            .iter()
+            .filter_map(|station_id| self.stations_footpaths.get(station_id))
+            .flatten()
+            .copied()
+            .collect();
+        footpath_indices.sort_unstable();
+        footpath_indices.dedup();
+
+        for footpath in footpath_indices.into_iter().map(|footpath_index| &self.footpaths[footpath_index]) {
+            let from_station_arrivals = self.stations_arrivals.get(&footpath.from_station).unwrap_or(&no_nodes);
+            let to_station_transfers = self.stations_transfers.get(&footpath.to_station).unwrap_or(&no_nodes);
+
+            for arrival in from_station_arrivals.iter() {
+                if let Some(transfer) = next_transfer(graph, to_station_transfers, graph[*arrival].time() + footpath.duration) {
+                    required_edges.push((*arrival, transfer, TimetableEdge::Walk {duration: footpath.duration}));
+                }
+            }
+        }
+
+        existing_edges.sort_unstable();
+        existing_edges.dedup();
+
+        // keep existing edges that are still required
+        let mut required_edges_positions: HashMap<(NodeIndex, NodeIndex), Vec<usize>> = HashMap::new();
+        for (position, (source, target, _)) in required_edges.iter().enumerate() {
+            required_edges_positions.entry((*source, *target)).or_default().push(position);
+        }
+
+        let mut is_required_edge_present = vec![false; required_edges.len()];
+        let mut removed_edges = Vec::new();
+
+        for edge in existing_edges {
+            let (source, target) = graph.edge_endpoints(edge).unwrap();
+            let present_position = required_edges_positions
+                .get(&(source, target))
+                .and_then(|positions| {
+                    positions.iter().copied().find(|position| !is_required_edge_present[*position] && required_edges[*position].2 == graph[edge])
+                });
+
+            match present_position {
+                Some(position) => is_required_edge_present[position] = true,
+                None => removed_edges.push(edge),
+            }
+        }
+
+        self.remove_edges(removed_edges, changes);
+
+        for ((source, target, weight), is_present) in required_edges.into_iter().zip(is_required_edge_present) {
+            if !is_present {
+                let edge = self.graph.add_edge(source, target, weight);
+                changes.add(edge);
+            }
+        }
+    }
+}
+
+/// returns the first transfer (of transfers sorted by time) at or after time
+fn next_transfer(graph: &DiGraph<TimetableNode, TimetableEdge>, transfers: &[NodeIndex], time: u64) -> Option<NodeIndex> {
+    transfers.iter().copied().find(|transfer| time <= graph[*transfer].time())
+}
@@ -0,0 +1,369 @@
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
+use super::InitialState;
+
+/// temperature of simulated annealing over the iterations
+///
+/// the temperature of the first iteration is the initial temperature of the schedule,
+/// the annealing is frozen (and stops) as soon as the temperature is lower than the final temperature
+pub trait CoolingSchedule: Send {
+
+    /// returns the temperature of the current iteration
+    fn temperature(&self) -> f64;
+
+    /// advances the schedule to the next iteration
+    ///
+    /// accepted is true if the neighbor of the current iteration was accepted,
+    /// improved is true if it is the best state found so far
+    fn cool(&mut self, accepted: bool, improved: bool);
+
+    /// restarts the schedule at its initial temperature
+    fn reset(&mut self);
+}
+
+/// T = initial / time, the original schedule (with the defaults: n_iterations / time)
+pub struct Hyperbolic {
+    initial_temperature: f64,
+    time: u64,
+}
+
+impl CoolingSchedule for Hyperbolic {
+    fn temperature(&self) -> f64 {
+        self.initial_temperature / self.time as f64
+    }
+
+    fn cool(&mut self, _accepted: bool, _improved: bool) {
+        self.time += 1;
+    }
+
+    fn reset(&mut self) {
+        self.time = 1;
+    }
+}
+
+/// T = initial * rate^(time - 1), the rate is chosen so the final temperature is reached after n_iterations
+pub struct Geometric {
+    initial_temperature: f64,
+    rate: f64,
+    temperature: f64,
+}
+
+impl CoolingSchedule for Geometric {
+    fn temperature(&self) -> f64 {
+        self.temperature
+    }
+
+    fn cool(&mut self, _accepted: bool, _improved: bool) {
+        self.temperature *= self.rate;
+    }
+
+    fn reset(&mut self) {
+        self.temperature = self.initial_temperature;
+    }
+}
+
+/// T decreases by a constant step from the initial temperature to the final temperature in n_iterations
+pub struct Linear {
+    initial_temperature: f64,
+    step: f64,
+    time: u64,
+}
+
+impl CoolingSchedule for Linear {
+    fn temperature(&self) -> f64 {
+        self.initial_temperature - self.step * (self.time - 1) as f64
+    }
+
+    fn cool(&mut self, _accepted: bool, _improved: bool) {
+        self.time += 1;
+    }
+
+    fn reset(&mut self) {
+        self.time = 1;
+    }
+}
+
+/// T = initial * ln(2) / ln(time + 1), cools very slowly (usually stopped by the iterations, not the final temperature)
+pub struct Logarithmic {
+    initial_temperature: f64,
+    time: u64,
+}
+
+impl CoolingSchedule for Logarithmic {
+    fn temperature(&self) -> f64 {
+        self.initial_temperature * 2f64.ln() / ((self.time + 1) as f64).ln()
+    }
+
+    fn cool(&mut self, _accepted: bool, _improved: bool) {
+        self.time += 1;
+    }
+
+    fn reset(&mut self) {
+        self.time = 1;
+    }
+}
+
+/// geometric schedule that additionally steers the acceptance rate
+///
+/// after each window of iterations the observed acceptance rate is compared with the target rate,
+/// which decreases linearly from target_acceptance to zero over n_iterations:
+/// the temperature is lowered if more neighbors were accepted and raised if fewer were accepted
+pub struct Adaptive {
+    geometric: Geometric,
+    target_acceptance: f64,
+    n_iterations: u64,
+    time: u64,
+    n_accepted: u64, // accepted neighbors in the current window
+}
+
+impl Adaptive {
+    const WINDOW: u64 = 100; // iterations between two adjustments
+    const ADJUSTMENT: f64 = 0.1; // relative change of the temperature per adjustment
+}
+
+impl CoolingSchedule for Adaptive {
+    fn temperature(&self) -> f64 {
+        self.geometric.temperature()
+    }
+
+    fn cool(&mut self, accepted: bool, improved: bool) {
+        self.geometric.cool(accepted, improved);
+        self.time += 1;
+
+        if accepted {
+            self.n_accepted += 1;
+        }
+
+        if self.time.is_multiple_of(Self::WINDOW) {
+            let acceptance = self.n_accepted as f64 / Self::WINDOW as f64;
+            let progress = (self.time as f64 / self.n_iterations as f64).min(1.0);
+            let target = self.target_acceptance * (1.0 - progress);
+
+            if acceptance > target {
+                self.geometric.temperature *= 1.0 - Self::ADJUSTMENT;
+            } else {
+                self.geometric.temperature *= 1.0 + Self::ADJUSTMENT;
+            }
+
+            self.n_accepted = 0;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.geometric.reset();
+        self.time = 0;
+        self.n_accepted = 0;
+    }
+}
+
+/// restarts the wrapped schedule if no better state was found for `window` iterations
+///
+/// each reheat halves the temperature of the restarted schedule, so later reheats are cooler
+pub struct Reheating {
+    schedule: Box<dyn CoolingSchedule>,
+    window: u64,
+    since_improvement: u64, // iterations since the last best state
+    factor: f64,            // 0.5^number of reheats
+}
+
+impl CoolingSchedule for Reheating {
+    fn temperature(&self) -> f64 {
+        self.factor * self.schedule.temperature()
+    }
+
+    fn cool(&mut self, accepted: bool, improved: bool) {
+        self.schedule.cool(accepted, improved);
+
+        if improved {
+            self.since_improvement = 0;
+        } else {
+            self.since_improvement += 1;
+        }
+
+        if self.since_improvement >= self.window {
+            self.schedule.reset();
+            self.since_improvement = 0;
+            self.factor *= 0.5;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.schedule.reset();
+        self.since_improvement = 0;
+        self.factor = 1.0;
+    }
+}
+
+/// kinds of cooling schedules selectable from the CLI
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScheduleKind {
+    Hyperbolic,
+    Geometric,
+    Linear,
+    Logarithmic,
+    Adaptive,
+}
+
+impl ScheduleKind {
+
+    /// parses the CLI name of a schedule
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "hyperbolic" => Some(Self::Hyperbolic),
+            "geometric" => Some(Self::Geometric),
+            "linear" => Some(Self::Linear),
+            "logarithmic" => Some(Self::Logarithmic),
+            "adaptive" => Some(Self::Adaptive),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ScheduleKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Self::Hyperbolic => "hyperbolic",
+            Self::Geometric => "geometric",
+            Self::Linear => "linear",
+            Self::Logarithmic => "logarithmic",
+            Self::Adaptive => "adaptive",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// when simulated annealing stops (besides a frozen schedule), the first criterion met stops the search
+#[derive(Debug, Clone)]
+pub struct StoppingCriteria {
+    pub max_iterations: u64,
+    pub max_duration: Option<Duration>,     // wall clock limit
+    pub no_improvement_window: Option<u64>, // stop after this many iterations without a new best state
+}
+
+impl StoppingCriteria {
+
+    /// returns the met criterion, if the search has to stop before iteration `time`
+    pub fn met(&self, time: u64, start_instant: Instant, time_of_last_improvement: u64) -> Option<&'static str> {
+        if time > self.max_iterations {
+            Some("max_iterations")
+        } else if self.max_duration.is_some_and(|max_duration| start_instant.elapsed() >= max_duration) {
+            Some("max_duration")
+        } else if self.no_improvement_window.is_some_and(|window| time - time_of_last_improvement > window) {
+            Some("no_improvement_window")
+        } else {
+            None
+        }
+    }
+}
+
+/// parameters of a simulated annealing run: initial state, cooling schedule and stopping criteria
+#[derive(Debug, Clone)]
+pub struct AnnealingParameters {
+    pub initial_state: InitialState, // ignored by simulated annealing on path, which continues a given state
+    pub schedule: ScheduleKind,
+    pub initial_temperature: f64,
+    pub final_temperature: f64, // the annealing is frozen below this temperature
+    pub target_acceptance: f64, // initial target acceptance rate of the adaptive schedule
+    pub reheat_window: Option<u64>,
+    pub stopping: StoppingCriteria,
+    pub movable_groups: Option<Vec<usize>>, // indices of the groups whose path may change (all groups if None)
+}
+
+impl AnnealingParameters {
+
+    /// returns the original parameters: best path of each group, hyperbolic schedule from n_iterations down to 1
+    pub fn with_iterations(n_iterations: u64) -> Self {
+        Self {
+            initial_state: InitialState::BestPath,
+            schedule: ScheduleKind::Hyperbolic,
+            initial_temperature: n_iterations as f64,
+            final_temperature: 1.0,
+            target_acceptance: 0.5,
+            reheat_window: None,
+            stopping: StoppingCriteria {
+                max_iterations: n_iterations,
+                max_duration: None,
+                no_improvement_window: None,
+            },
+            movable_groups: None,
+        }
+    }
+
+    /// returns a new cooling schedule with these parameters
+    pub fn build_schedule(&self) -> Box<dyn CoolingSchedule> {
+        let n_iterations = self.stopping.max_iterations.max(2);
+        let rate = (self.final_temperature / self.initial_temperature).powf(1.0 / (n_iterations - 1) as f64);
+
+        let geometric = Geometric {
+            initial_temperature: self.initial_temperature,
+            rate,
+            temperature: self.initial_temperature,
+        };
+
+        let schedule: Box<dyn CoolingSchedule> = match self.schedule {
+            ScheduleKind::Hyperbolic => Box::new(Hyperbolic {
+                initial_temperature: self.initial_temperature,
+                time: 1,
+            }),
+            ScheduleKind::Geometric => Box::new(geometric),
+            ScheduleKind::Linear => Box::new(Linear {
+                initial_temperature: self.initial_temperature,
+                step: (self.initial_temperature - self.final_temperature) / (n_iterations - 1) as f64,
+                time: 1,
+            }),
+            ScheduleKind::Logarithmic => Box::new(Logarithmic {
+                initial_temperature: self.initial_temperature,
+                time: 1,
+            }),
+            ScheduleKind::Adaptive => Box::new(Adaptive {
+                geometric,
+                target_acceptance: self.target_acceptance,
+                n_iterations,
+                time: 0,
+                n_accepted: 0,
+            }),
+        };
+
+        match self.reheat_window {
+            Some(window) => Box::new(Reheating {
+                schedule,
+                window,
+                since_improvement: 0,
+                factor: 1.0,
+            }),
+            None => schedule,
+        }
+    }
+}
+
+impl AnnealingParameters {
+
+    /// columns of the parameters in the runtime CSV outputs (see `Display`)
+    pub const CSV_HEADER: &'static str = "schedule,initial_temperature,final_temperature,target_acceptance,reheat_window,max_iterations,max_duration,no_improvement_window,initial_state,movable_groups";
+}
+
+impl fmt::Display for AnnealingParameters {
+
+    /// formats the parameters as values of the columns `AnnealingParameters::CSV_HEADER` of the runtime CSV outputs
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let optional = |value: Option<u64>| value.map(|value| value.to_string()).unwrap_or_else(|| String::from("none"));
+
+        write!(
+            f,
+            "{},{},{},{},{},{},{},{},{},{}",
+            self.schedule,
+            self.initial_temperature,
+            self.final_temperature,
+            self.target_acceptance,
+            optional(self.reheat_window),
+            self.stopping.max_iterations,
+            optional(self.stopping.max_duration.map(|duration| duration.as_secs())),
+            optional(self.stopping.no_improvement_window),
+            self.initial_state,
+            self.movable_groups.as_ref().map(|movable_groups| movable_groups.len().to_string()).unwrap_or_else(|| String::from("all"))
+        )
+    }
+}
@@ -0,0 +1,240 @@
+use petgraph::graph::{DiGraph, EdgeIndex};
+
+use crate::model::{
+    cost_model::CostModel,
+    graph_weight::{TimetableEdge, TimetableNode},
+    group::Group,
+    path::Path,
+};
+
+/// persistent evaluation context of a path selection
+///
+/// owns the utilization of every Trip edge (indexed by EdgeIndex) instead of straining it to the graph,
+/// so the cost change of switching one group's path can be calculated from the edges of the old and the new path only
+#[derive(Debug, Clone)]
+pub struct EvaluationContext {
+    edges_utilization: Vec<u64>,             // number of passengers on each edge
+    edges_first_class_utilization: Vec<u64>, // number of first class passengers on each edge
+    strained_edges_cost: i64,                // summed utilization_cost of all edges
+    cost_model: CostModel,                   // weights of the utilization_cost
+}
+
+impl EvaluationContext {
+
+    /// creates a new context with all selected paths strained to it
+    pub fn new(
+        graph: &DiGraph<TimetableNode, TimetableEdge>,
+        cost_model: &CostModel,
+        groups: &[Group],
+        groups_path_index: &[usize],
+    ) -> Self {
+        let mut context = Self {
+            edges_utilization: vec![0; graph.edge_count()],
+            edges_first_class_utilization: vec![0; graph.edge_count()],
+            strained_edges_cost: 0,
+            cost_model: cost_model.clone(),
+        };
+
+        for (group, path_index) in groups.iter().zip(groups_path_index.iter()) {
+            context.strain_path(graph, &group.paths[*path_index]);
+        }
+
+        context
+    }
+
+    /// creates a new context with the passengers of each group strained to its paths (groups_path_shares[group][path] passengers)
+    pub fn with_shares(
+        graph: &DiGraph<TimetableNode, TimetableEdge>,
+        cost_model: &CostModel,
+        groups: &[Group],
+        groups_path_shares: &[Vec<u64>],
+    ) -> Self {
+        let mut context = Self::new(graph, cost_model, &[], &[]);
+
+        for (group, path_shares) in groups.iter().zip(groups_path_shares.iter()) {
+            for (path, passengers) in group.paths.iter().zip(path_shares.iter()) {
+                context.strain_path_share(graph, path, *passengers);
+            }
+        }
+
+        context
+    }
+
+    /// returns summed utilization_cost of all edges
+    pub fn strained_edges_cost(&self) -> i64 {
+        self.strained_edges_cost
+    }
+
+    /// returns utilization of edge
+    pub fn utilization(&self, edge: EdgeIndex) -> u64 {
+        self.edges_utilization[edge.index()]
+    }
+
+    /// returns utilization of edge in first class
+    pub fn first_class_utilization(&self, edge: EdgeIndex) -> u64 {
+        self.edges_first_class_utilization[edge.index()]
+    }
+
+    /// returns utilization_cost of edge with its utilization in this context
+    pub fn utilization_cost(&self, graph: &DiGraph<TimetableNode, TimetableEdge>, edge: EdgeIndex) -> u64 {
+        graph[edge].utilization_cost_by_class(&self.cost_model, self.utilization(edge), self.first_class_utilization(edge))
+    }
+
+    /// returns all edges with a utilization_cost greater than zero
+    pub fn overcrowded_edges(&self, graph: &DiGraph<TimetableNode, TimetableEdge>) -> Vec<EdgeIndex> {
+        graph
+            .edge_indices()
+            .filter(|edge| self.utilization(*edge) != 0 && self.utilization_cost(graph, *edge) > 0)
+            .collect()
+    }
+
+    /// returns the number of passengers that still fit on each Trip edge of edges (u64::MAX if there is no Trip edge)
+    pub fn residual_capacity<'e>(
+        &self,
+        graph: &DiGraph<TimetableNode, TimetableEdge>,
+        edges: impl Iterator<Item = &'e EdgeIndex>,
+    ) -> u64 {
+        edges
+            .filter(|edge| graph[**edge].is_trip())
+            .map(|edge| graph[*edge].capacity().saturating_sub(self.utilization(*edge)))
+            .min()
+            .unwrap_or(u64::MAX)
+    }
+
+    /// returns the summed utilization over capacity of all Trip edges
+    pub fn overflow(&self, graph: &DiGraph<TimetableNode, TimetableEdge>) -> u64 {
+        graph
+            .edge_indices()
+            .filter(|edge| graph[*edge].is_trip())
+            .map(|edge| self.utilization(edge).saturating_sub(graph[edge].capacity()))
+            .sum()
+    }
+
+    /// occupy path (add its utilization to the edges)
+    pub fn strain_path(&mut self, graph: &DiGraph<TimetableNode, TimetableEdge>, path: &Path) {
+        self.strain_path_share(graph, path, path.utilization());
+    }
+
+    /// release path (remove its utilization from the edges)
+    pub fn relieve_path(&mut self, graph: &DiGraph<TimetableNode, TimetableEdge>, path: &Path) {
+        self.relieve_path_share(graph, path, path.utilization());
+    }
+
+    /// occupy path with a share of its group (add `passengers` to the edges)
+    pub fn strain_path_share(&mut self, graph: &DiGraph<TimetableNode, TimetableEdge>, path: &Path, passengers: u64) {
+        let first_class_passengers = if path.is_first_class() { passengers } else { 0 };
+
+        for edge in path.edges.iter() {
+            self.change_utilization(graph, *edge, passengers as i64, first_class_passengers as i64);
+        }
+    }
+
+    /// release a share of path's group (remove `passengers` from the edges)
+    pub fn relieve_path_share(&mut self, graph: &DiGraph<TimetableNode, TimetableEdge>, path: &Path, passengers: u64) {
+        let first_class_passengers = if path.is_first_class() { passengers } else { 0 };
+
+        for edge in path.edges.iter() {
+            self.change_utilization(graph, *edge, -(passengers as i64), -(first_class_passengers as i64));
+        }
+    }
+
+    /// calculates the change of strained_edges_cost if path is strained additionally
+    ///
+    /// only visits the edges of path, the context itself is not modified
+    pub fn strain_path_cost_delta(&self, graph: &DiGraph<TimetableNode, TimetableEdge>, path: &Path) -> i64 {
+        path.edges
+            .iter()
+            .map(|edge| self.utilization_cost_delta(graph, *edge, path.utilization() as i64, path.first_class_utilization() as i64))
+            .sum()
+    }
+
+    /// calculates the change of strained_edges_cost if old_path is replaced by new_path
+    ///
+    /// only visits the edges of both paths, the context itself is not modified
+    pub fn switch_path_cost_delta(
+        &self,
+        graph: &DiGraph<TimetableNode, TimetableEdge>,
+        old_path: &Path,
+        new_path: &Path,
+    ) -> i64 {
+        let mut delta = 0;
+
+        // edges of old path (may also be part of new path)
+        for edge in old_path.edges.iter() {
+            let mut change = -(old_path.utilization() as i64);
+            let mut first_class_change = -(old_path.first_class_utilization() as i64);
+            if new_path.edges.contains(edge) {
+                change += new_path.utilization() as i64;
+                first_class_change += new_path.first_class_utilization() as i64;
+            }
+            delta += self.utilization_cost_delta(graph, *edge, change, first_class_change);
+        }
+
+        // edges only part of new path
+        for edge in new_path.edges.iter().filter(|edge| !old_path.edges.contains(*edge)) {
+            delta += self.utilization_cost_delta(graph, *edge, new_path.utilization() as i64, new_path.first_class_utilization() as i64);
+        }
+
+        delta
+    }
+
+    /// replaces old_path by new_path, returns the change of strained_edges_cost
+    pub fn switch_path(
+        &mut self,
+        graph: &DiGraph<TimetableNode, TimetableEdge>,
+        old_path: &Path,
+        new_path: &Path,
+    ) -> i64 {
+        let strained_edges_cost_before = self.strained_edges_cost;
+
+        self.relieve_path(graph, old_path);
+        self.strain_path(graph, new_path);
+
+        self.strained_edges_cost - strained_edges_cost_before
+    }
+
+    /// returns the change of the edge's utilization_cost if its utilization changed by `change`, `first_class_change` of them in first class
+    #[inline]
+    pub fn utilization_cost_delta(
+        &self,
+        graph: &DiGraph<TimetableNode, TimetableEdge>,
+        edge: EdgeIndex,
+        change: i64,
+        first_class_change: i64,
+    ) -> i64 {
+        if change == 0 || !graph[edge].is_trip() {
+            // no need to track utilization on other edges, as they have unlimited capacity
+            return 0;
+        }
+
+        let edge_weight = &graph[edge];
+        let (utilization, first_class_utilization) = (self.utilization(edge), self.first_class_utilization(edge));
+        let next_utilization = (utilization as i64 + change) as u64;
+        let next_first_class_utilization = (first_class_utilization as i64 + first_class_change) as u64;
+
+        edge_weight.utilization_cost_by_class(&self.cost_model, next_utilization, next_first_class_utilization) as i64
+            - edge_weight.utilization_cost_by_class(&self.cost_model, utilization, first_class_utilization) as i64
+    }
+
+    /// changes utilization of edge and keeps strained_edges_cost up to date
+    #[inline]
+    fn change_utilization(
+        &mut self,
+        graph: &DiGraph<TimetableNode, TimetableEdge>,
+        edge: EdgeIndex,
+        change: i64,
+        first_class_change: i64,
+    ) {
+        let delta = self.utilization_cost_delta(graph, edge, change, first_class_change);
+
+        if graph[edge].is_trip() {
+            let utilization = &mut self.edges_utilization[edge.index()];
+            *utilization = (*utilization as i64 + change) as u64;
+
+            let first_class_utilization = &mut self.edges_first_class_utilization[edge.index()];
+            *first_class_utilization = (*first_class_utilization as i64 + first_class_change) as u64;
+        }
+
+        self.strained_edges_cost += delta;
+    }
+}
@@ -0,0 +1,386 @@
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashSet},
+    fs::File,
+    io::{BufWriter, Write},
+    time::Instant,
+};
+
+use colored::Colorize;
+use petgraph::{
+    graph::{DiGraph, EdgeIndex, NodeIndex},
+    visit::EdgeRef,
+};
+
+use super::{evaluation::EvaluationContext, SelectionState};
+use crate::model::{
+    cost_model::CostModel,
+    graph_weight::{TimetableEdge, TimetableNode},
+    group::Group,
+    path::Path,
+};
+
+/// Lagrangian relaxation of the capacity constraints of all Trip edges, solved by subgradient optimization
+///
+/// the overcrowding cost of an edge is modelled as `standing_penalty * standing + overload_penalty(overload)` of its overflow
+/// (standing up to the capacity, overload beyond) with `utilization - overflow <= seats` (first class seats are relaxed to seats),
+/// relaxing this constraint with a multiplier per Trip edge decomposes the problem into one cheapest path search per group
+/// (edge cost = travel_cost + passengers * multiplier, plus the delay cost at the destination), so every iteration yields a lower bound for the optimal cost
+///
+/// the cheapest paths are appended to the paths of the groups (if not already known) and their selection is an upper bound (feasible selection)
+///
+/// requires each group to have at least one path (the start node is taken from its first edge)
+pub fn lagrangian_relaxation<'a>(
+    graph: &DiGraph<TimetableNode, TimetableEdge>,
+    inaccessible_stations: &HashSet<u64>, // stations without step-free access (see `GroupConstraints::step_free`)
+    cost_model: &'a CostModel,
+    groups: &'a mut Vec<Group>,
+    filepath: &str,
+    n_iterations: u64,
+) -> SelectionState<'a> {
+    println!("lagrangian_relaxation()");
+
+    let mut writer = BufWriter::new(
+        File::create(format!("{}.{}", filepath, "csv"))
+            .unwrap_or_else(|_| panic!("Could not create file \"{}.csv\"", filepath)),
+    );
+
+    writeln!(writer, "time,lower_bound,upper_bound,gap,step_factor,new_paths").unwrap();
+
+    let mut r_writer = BufWriter::new(
+        File::create(format!("{}_{}.{}", filepath, "runtime", "csv"))
+            .unwrap_or_else(|_| panic!("Could not create file \"{}_runtime.csv\"", filepath)),
+    );
+
+    writeln!(r_writer, "runtime,time,lower_bound,upper_bound").unwrap();
+
+    let start_instant = Instant::now();
+
+    // each group starts at the source node of its first path's first edge (None if it only has the virtual "no service" path)
+    let groups_start: Vec<Option<NodeIndex>> = groups
+        .iter()
+        .map(|group| group.paths.iter().find(|path| !path.is_unserved()).map(|path| graph.edge_endpoints(path.edges[0]).unwrap().0))
+        .collect();
+
+    // upper bound: best selection found so far, start with the best path of each group
+    let mut best_groups_path_index = vec![0; groups.len()];
+    let mut upper_bound = SelectionState::from_groups_path_index(graph, cost_model, groups, best_groups_path_index.clone()).cost;
+
+    let mut lower_bound = f64::NEG_INFINITY;
+
+    // one multiplier per edge (only Trip edges are ever increased)
+    let mut multipliers = vec![0.0; graph.edge_count()];
+
+    // the lagrangian is unbounded from below for multipliers over the penalty's largest slope
+    let max_multiplier = cost_model.overload_penalty.max_multiplier().unwrap_or(f64::INFINITY);
+
+    // step factor of the polyak step size, halved if the lower bound did not improve for some iterations
+    let mut step_factor = 2.0;
+    let mut n_iterations_without_improvement = 0;
+
+    let mut n_performed_iterations = 0;
+
+    for time in 1..=n_iterations {
+        n_performed_iterations = time;
+
+        // FIRST: pricing -> cheapest path for each group with modified edge costs
+        let mut utilization = vec![0; graph.edge_count()];
+        let mut lagrangian_value = 0.0;
+        let mut groups_path_index = Vec::with_capacity(groups.len());
+        let mut n_new_paths = 0;
+
+        for (group, start) in groups.iter_mut().zip(groups_start.iter()) {
+            let cheapest = start.and_then(|start| cheapest_path(graph, cost_model, &multipliers, group, start));
+            let unserved_cost = group.paths.iter().find(|path| path.is_unserved()).map(|path| path.cost() as f64);
+
+            let (edges, reduced_cost) = match cheapest {
+                Some(cheapest) if unserved_cost.is_none_or(|unserved_cost| cheapest.1 <= unserved_cost) && group.admits_path(graph, inaccessible_stations, &cheapest.0) => cheapest,
+                _ => {
+                    // start is already at the destination station, not transporting the group is cheaper or the cheapest path
+                    // violates the constraints of the group -> cheapest of the known paths (the bound relaxes the constraints)
+                    let (path_index, path_cost) = group
+                        .paths
+                        .iter()
+                        .map(|path| path.cost() as f64 + path_multipliers_cost(graph, &multipliers, path))
+                        .enumerate()
+                        .min_by(|(_, cost_a), (_, cost_b)| cost_a.partial_cmp(cost_b).unwrap())
+                        .unwrap();
+
+                    lagrangian_value += cheapest.map_or(path_cost, |cheapest| cheapest.1.min(path_cost));
+                    for edge in group.paths[path_index].edges.iter() {
+                        utilization[edge.index()] += group.passengers;
+                    }
+                    groups_path_index.push(path_index);
+                    continue;
+                }
+            };
+
+            lagrangian_value += reduced_cost;
+
+            for edge in edges.iter() {
+                utilization[edge.index()] += group.passengers;
+            }
+
+            let path = Path::new(graph, cost_model, edges, group.passengers, group.arrival_time).with_first_class(group.first_class);
+
+            // append path to the group's paths if it is not known yet
+            let path_index = match group.paths.iter().position(|known_path| known_path.edges == path.edges) {
+                Some(path_index) => path_index,
+                None => {
+                    group.paths.push(path);
+                    n_new_paths += 1;
+                    group.paths.len() - 1
+                }
+            };
+            groups_path_index.push(path_index);
+        }
+
+        // minimum of penalty(overflow) - multiplier * (overflow + seats) for each edge, where the overflow over the seats
+        // is standing up to the capacity and overload beyond (first class seats are relaxed to seats for every passenger)
+        let mut optimal_overflows = vec![0.0; graph.edge_count()];
+        for (edge_index, multiplier) in multipliers.iter().enumerate() {
+            if *multiplier > 0.0 {
+                let edge = &graph[EdgeIndex::new(edge_index)];
+                let standing_capacity = (edge.capacity() - edge.seats()) as f64;
+                let (minimum, overload) = cost_model.overload_penalty.lagrangian_minimum(*multiplier);
+                let overflow_minimum = (cost_model.standing_penalty as f64 - multiplier) * standing_capacity + minimum;
+
+                if overflow_minimum < 0.0 {
+                    lagrangian_value += overflow_minimum;
+                    optimal_overflows[edge_index] = standing_capacity + overload;
+                }
+                lagrangian_value -= multiplier * edge.seats() as f64;
+            }
+        }
+
+        if lagrangian_value > lower_bound {
+            lower_bound = lagrangian_value;
+            n_iterations_without_improvement = 0;
+        } else {
+            n_iterations_without_improvement += 1;
+            if n_iterations_without_improvement == 5 {
+                step_factor /= 2.0;
+                n_iterations_without_improvement = 0;
+            }
+        }
+
+        // SECOND: the priced selection is feasible -> update upper bound
+        let cost = SelectionState::from_groups_path_index(graph, cost_model, groups, groups_path_index.clone()).cost;
+        if cost < upper_bound {
+            upper_bound = cost;
+            best_groups_path_index = groups_path_index;
+        }
+
+        let gap = gap(lower_bound, upper_bound);
+        print!(
+            "[time={}]: lower_bound={:.2}, upper_bound={}, gap={:.4}, step_factor={:.4}, new_paths={} ",
+            time, lower_bound, upper_bound, gap, step_factor, n_new_paths
+        );
+        writeln!(
+            writer,
+            "{},{},{},{},{},{}",
+            time, lower_bound, upper_bound, gap, step_factor, n_new_paths
+        )
+        .unwrap();
+
+        // THIRD: subgradient of the relaxed constraints (utilization - overflow - seats), only Trip edges
+        let mut subgradient = vec![0.0; graph.edge_count()];
+        let mut subgradient_norm = 0.0;
+        for edge_index in graph.edge_indices() {
+            let edge = &graph[edge_index];
+            let multiplier = multipliers[edge_index.index()];
+
+            if !edge.is_trip() || (multiplier == 0.0 && utilization[edge_index.index()] <= edge.seats()) {
+                // multiplier would stay zero
+                continue;
+            }
+
+            let value = utilization[edge_index.index()] as f64 - optimal_overflows[edge_index.index()] - edge.seats() as f64;
+            subgradient[edge_index.index()] = value;
+            subgradient_norm += value * value;
+        }
+
+        if subgradient_norm == 0.0 || gap == 0.0 {
+            println!("{}", "-> optimal".green());
+            break;
+        }
+
+        let step = step_factor * (upper_bound as f64 - lower_bound).max(1.0) / subgradient_norm;
+        println!("step={:.6}", step);
+
+        for (multiplier, value) in multipliers.iter_mut().zip(subgradient.iter()) {
+            *multiplier = (*multiplier + step * value).clamp(0.0, max_multiplier);
+        }
+    }
+
+    // FOURTH: improve the best selection by switching single groups to their (incrementally evaluated) best path
+    let groups: &'a Vec<Group> = groups;
+    let mut context = EvaluationContext::new(graph, cost_model, groups, &best_groups_path_index);
+
+    let mut improved = true;
+    while improved {
+        improved = false;
+
+        for (group_index, group) in groups.iter().enumerate() {
+            let current_path = &group.paths[best_groups_path_index[group_index]];
+
+            let mut best_delta = 0;
+            let mut best_path_index = best_groups_path_index[group_index];
+            for (path_index, path) in group.paths.iter().enumerate() {
+                let delta = context.switch_path_cost_delta(graph, current_path, path) + path.cost() - current_path.cost();
+                if delta < best_delta {
+                    best_delta = delta;
+                    best_path_index = path_index;
+                }
+            }
+
+            if best_delta < 0 {
+                context.switch_path(graph, current_path, &group.paths[best_path_index]);
+                best_groups_path_index[group_index] = best_path_index;
+                improved = true;
+            }
+        }
+    }
+
+    let state = SelectionState::from_groups_path_index(graph, cost_model, groups, best_groups_path_index);
+
+    println!(
+        "lower_bound={:.2}, upper_bound={}, gap={:.4} (done in {}s)",
+        lower_bound,
+        state.cost,
+        gap(lower_bound, state.cost),
+        start_instant.elapsed().as_secs()
+    );
+
+    writeln!(
+        r_writer,
+        "{}s,{},{},{}",
+        start_instant.elapsed().as_secs(),
+        n_performed_iterations,
+        lower_bound,
+        state.cost
+    )
+    .unwrap();
+
+    state
+}
+
+/// returns the relative gap between lower and upper bound
+fn gap(lower_bound: f64, upper_bound: i64) -> f64 {
+    if lower_bound >= upper_bound as f64 {
+        0.0
+    } else {
+        (upper_bound as f64 - lower_bound) / (upper_bound as f64).abs().max(1.0)
+    }
+}
+
+/// returns the summed multipliers (times passengers) of the path's Trip edges
+fn path_multipliers_cost(graph: &DiGraph<TimetableNode, TimetableEdge>, multipliers: &[f64], path: &Path) -> f64 {
+    path.edges
+        .iter()
+        .filter(|edge| graph[**edge].is_trip())
+        .map(|edge| multipliers[edge.index()] * path.utilization() as f64)
+        .sum()
+}
+
+/// entry of the priority queue of cheapest_path()
+struct QueueEntry {
+    cost: f64,
+    node: NodeIndex,
+}
+
+impl PartialEq for QueueEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cost == other.cost
+    }
+}
+
+impl Eq for QueueEntry {}
+
+impl PartialOrd for QueueEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// reversed, as BinaryHeap is a max-heap
+impl Ord for QueueEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.partial_cmp(&self.cost).unwrap_or(Ordering::Equal)
+    }
+}
+
+/// dijkstra from start to all nodes at the group's destination station (which are not expanded further)
+///
+/// edge cost is travel_cost (+ passengers * multiplier for Trip edges),
+/// the destination node with the lowest cost + delay_cost of its time is selected
+///
+/// returns the path's edges and its cost (including the delay cost), None if start is at the destination station
+pub(super) fn cheapest_path(
+    graph: &DiGraph<TimetableNode, TimetableEdge>,
+    cost_model: &CostModel,
+    multipliers: &[f64],
+    group: &Group,
+    start: NodeIndex,
+) -> Option<(Vec<EdgeIndex>, f64)> {
+    if graph[start].station_id() == group.destination_station_id {
+        return None;
+    }
+
+    let mut costs = vec![f64::INFINITY; graph.node_count()];
+    let mut predecessors: Vec<Option<EdgeIndex>> = vec![None; graph.node_count()];
+    let mut queue = BinaryHeap::new();
+
+    // destination node with the lowest cost (including delay cost) so far
+    let mut best_destination: Option<(NodeIndex, f64)> = None;
+
+    costs[start.index()] = 0.0;
+    queue.push(QueueEntry { cost: 0.0, node: start });
+
+    while let Some(QueueEntry { cost, node }) = queue.pop() {
+        if cost > costs[node.index()] {
+            // outdated entry
+            continue;
+        }
+
+        if graph[node].station_id() == group.destination_station_id {
+            let delay = graph[node].time() as i64 - group.arrival_time as i64;
+            let total_cost = cost + cost_model.delay_cost(delay) as f64;
+
+            if best_destination.is_none_or(|(_, best_cost)| total_cost < best_cost) {
+                best_destination = Some((node, total_cost));
+            }
+            continue;
+        }
+
+        for edge in graph.edges(node) {
+            let weight = edge.weight();
+            let mut edge_cost = weight.travel_cost(cost_model) as f64;
+
+            if weight.is_trip() {
+                edge_cost += multipliers[edge.id().index()] * group.passengers as f64;
+            }
+
+            let next_cost = cost + edge_cost;
+            if next_cost < costs[edge.target().index()] {
+                costs[edge.target().index()] = next_cost;
+                predecessors[edge.target().index()] = Some(edge.id());
+                queue.push(QueueEntry { cost: next_cost, node: edge.target() });
+            }
+        }
+    }
+
+    // None if destination is not reachable
+    best_destination.map(|(destination, total_cost)| {
+        // collect edges by walking back to start
+        let mut edges = Vec::new();
+        let mut current = destination;
+        while let Some(edge) = predecessors[current.index()] {
+            edges.push(edge);
+            current = graph.edge_endpoints(edge).unwrap().0;
+        }
+        edges.reverse();
+
+        (edges, total_cost)
+    })
+}
@@ -0,0 +1,456 @@
+use std::{
+    collections::HashSet,
+    fmt,
+    fs::File,
+    io::{BufWriter, Write},
+    time::Instant,
+};
+
+use petgraph::graph::{DiGraph, EdgeIndex, NodeIndex};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use super::{evaluation::EvaluationContext, lagrangian_relaxation, InitialState, SelectionState};
+use crate::model::{
+    cost_model::CostModel,
+    graph_weight::{TimetableEdge, TimetableNode},
+    group::Group,
+    path::Path,
+};
+
+/// selects the groups whose paths are removed from the selection
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DestroyOperator {
+    OverloadedTrips, // all groups on the n_trips most overloaded Trip edges
+    Station,         // all groups visiting the station of a random overloaded Trip edge
+    TimeWindow,      // all groups using an edge that starts in the time window around a random overloaded Trip edge
+}
+
+/// reinserts the removed groups one after another
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RepairOperator {
+    Greedy, // largest group first, each with its cheapest path
+    Regret, // group with the largest cost difference between its cheapest and second cheapest path first
+}
+
+const DESTROY_OPERATORS: [DestroyOperator; 3] = [DestroyOperator::OverloadedTrips, DestroyOperator::Station, DestroyOperator::TimeWindow];
+const REPAIR_OPERATORS: [RepairOperator; 2] = [RepairOperator::Greedy, RepairOperator::Regret];
+
+// scores of the operators of an iteration
+const SCORE_NEW_BEST: f64 = 33.0; // new best selection
+const SCORE_IMPROVED: f64 = 9.0;  // better than the current selection
+const SCORE_ACCEPTED: f64 = 1.0;  // as good as the current selection
+
+impl fmt::Display for DestroyOperator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Self::OverloadedTrips => "overloaded_trips",
+            Self::Station => "station",
+            Self::TimeWindow => "time_window",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl fmt::Display for RepairOperator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Self::Greedy => "greedy",
+            Self::Regret => "regret",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// parameters of large_neighborhood_search()
+#[derive(Debug, Clone)]
+pub struct LnsParameters {
+    pub n_iterations: u64,
+    pub n_trips: usize,       // number of Trip edges of the OverloadedTrips operator
+    pub time_window: u64,     // width (in minutes) of the TimeWindow operator
+    pub segment_length: u64,  // iterations between two updates of the operator weights
+    pub reaction_factor: f64, // share of the last segment's scores in the updated weights
+    pub initial_state: InitialState,
+}
+
+/// adaptive weights of the operators of one kind, an operator is selected with probability proportional to its weight
+struct OperatorWeights {
+    weights: Vec<f64>,
+    scores: Vec<f64>, // summed scores in the current segment
+    uses: Vec<u64>,   // number of uses in the current segment
+}
+
+impl OperatorWeights {
+    fn new(n_operators: usize) -> Self {
+        Self {
+            weights: vec![1.0; n_operators],
+            scores: vec![0.0; n_operators],
+            uses: vec![0; n_operators],
+        }
+    }
+
+    /// returns the index of a random operator (roulette wheel selection)
+    fn select(&self, rng: &mut impl Rng) -> usize {
+        let mut random = rng.gen_range(0.0..self.weights.iter().sum::<f64>());
+
+        for (operator, weight) in self.weights.iter().enumerate() {
+            if random < *weight {
+                return operator;
+            }
+            random -= weight;
+        }
+
+        self.weights.len() - 1
+    }
+
+    fn reward(&mut self, operator: usize, score: f64) {
+        self.scores[operator] += score;
+        self.uses[operator] += 1;
+    }
+
+    /// weight = (1 - reaction_factor) * weight + reaction_factor * average score in the segment (unused operators keep their weight)
+    fn update(&mut self, reaction_factor: f64) {
+        for operator in 0..self.weights.len() {
+            if self.uses[operator] > 0 {
+                let average_score = self.scores[operator] / self.uses[operator] as f64;
+
+                // operators never reach zero weight, so they can still be selected
+                self.weights[operator] = ((1.0 - reaction_factor) * self.weights[operator] + reaction_factor * average_score).max(0.01);
+            }
+
+            self.scores[operator] = 0.0;
+            self.uses[operator] = 0;
+        }
+    }
+}
+
+/// adaptive large neighborhood search (ALNS)
+///
+/// starts with the initial state of parameters, in each iteration a destroy operator removes the paths of a set of groups from the current selection
+/// and a repair operator reinserts them, each group with one of its known paths or a fresh detour:
+/// the cheapest path with the overload cost of the remaining groups as additional edge cost (appended to the group's paths if new).
+/// the new selection is accepted if it is not worse than the current one.
+///
+/// the operators are selected randomly with adaptive weights, which are updated every segment_length iterations
+/// with the scores the operators achieved (new best, improved or equal selection)
+///
+/// requires each group to have at least one path (the start node is taken from its first edge), returns the best selection found
+pub fn large_neighborhood_search<'a>(
+    graph: &DiGraph<TimetableNode, TimetableEdge>,
+    inaccessible_stations: &HashSet<u64>, // stations without step-free access (see `GroupConstraints::step_free`)
+    cost_model: &'a CostModel,
+    groups: &'a mut Vec<Group>,
+    filepath: &str,
+    parameters: &LnsParameters,
+    seed: u64,
+) -> SelectionState<'a> {
+    println!("large_neighborhood_search(n_iterations={}, seed={})", parameters.n_iterations, seed);
+
+    let mut writer = BufWriter::new(
+        File::create(format!("{}.{}", filepath, "csv"))
+            .unwrap_or_else(|_| panic!("Could not create file \"{}.csv\"", filepath)),
+    );
+
+    writeln!(
+        writer,
+        "time,destroy,repair,removed_groups,new_paths,cost,best_cost,{},{}",
+        DESTROY_OPERATORS.iter().map(|operator| format!("weight_{}", operator)).collect::<Vec<String>>().join(","),
+        REPAIR_OPERATORS.iter().map(|operator| format!("weight_{}", operator)).collect::<Vec<String>>().join(",")
+    )
+    .unwrap();
+
+    let mut r_writer = BufWriter::new(
+        File::create(format!("{}_{}.{}", filepath, "runtime", "csv"))
+            .unwrap_or_else(|_| panic!("Could not create file \"{}_runtime.csv\"", filepath)),
+    );
+
+    writeln!(r_writer, "runtime,time,seed").unwrap();
+
+    let start_instant = Instant::now();
+
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    // each group starts at the source node of its first path's first edge (None if it only has the virtual "no service" path)
+    let groups_start: Vec<Option<NodeIndex>> = groups
+        .iter()
+        .map(|group| group.paths.iter().find(|path| !path.is_unserved()).map(|path| graph.edge_endpoints(path.edges[0]).unwrap().0))
+        .collect();
+
+    let mut current_groups_path_index = SelectionState::generate_initial_state(graph, cost_model, groups, &parameters.initial_state).groups_path_index;
+    let mut context = EvaluationContext::new(graph, cost_model, groups, &current_groups_path_index);
+    let mut current_cost = selection_cost(groups, &context, &current_groups_path_index);
+
+    let mut best_groups_path_index = current_groups_path_index.clone();
+    let mut best_cost = current_cost;
+
+    let mut destroy_weights = OperatorWeights::new(DESTROY_OPERATORS.len());
+    let mut repair_weights = OperatorWeights::new(REPAIR_OPERATORS.len());
+
+    for time in 1..=parameters.n_iterations {
+        let destroy_operator = destroy_weights.select(&mut rng);
+        let repair_operator = repair_weights.select(&mut rng);
+
+        let removed = destroy(graph, groups, &context, &current_groups_path_index, DESTROY_OPERATORS[destroy_operator], parameters, &mut rng);
+
+        let mut candidate_context = context.clone();
+        for group_index in removed.iter() {
+            candidate_context.relieve_path(graph, &groups[*group_index].paths[current_groups_path_index[*group_index]]);
+        }
+
+        let n_new_paths = append_detours(graph, inaccessible_stations, cost_model, groups, &groups_start, &candidate_context, &removed);
+
+        let mut candidate_groups_path_index = current_groups_path_index.clone();
+        repair(graph, groups, &mut candidate_context, &mut candidate_groups_path_index, &removed, REPAIR_OPERATORS[repair_operator]);
+
+        let candidate_cost = selection_cost(groups, &candidate_context, &candidate_groups_path_index);
+
+        let score = if candidate_cost < best_cost {
+            SCORE_NEW_BEST
+        } else if candidate_cost < current_cost {
+            SCORE_IMPROVED
+        } else if candidate_cost == current_cost {
+            SCORE_ACCEPTED
+        } else {
+            0.0
+        };
+
+        if candidate_cost <= current_cost {
+            context = candidate_context;
+            current_groups_path_index = candidate_groups_path_index;
+            current_cost = candidate_cost;
+
+            if current_cost < best_cost {
+                best_groups_path_index = current_groups_path_index.clone();
+                best_cost = current_cost;
+            }
+        }
+
+        destroy_weights.reward(destroy_operator, score);
+        repair_weights.reward(repair_operator, score);
+
+        if time % parameters.segment_length.max(1) == 0 {
+            destroy_weights.update(parameters.reaction_factor);
+            repair_weights.update(parameters.reaction_factor);
+        }
+
+        println!(
+            "[time={}]: destroy={}, repair={}, removed_groups={}, new_paths={}, cost={}, best_cost={}",
+            time,
+            DESTROY_OPERATORS[destroy_operator],
+            REPAIR_OPERATORS[repair_operator],
+            removed.len(),
+            n_new_paths,
+            current_cost,
+            best_cost
+        );
+
+        writeln!(
+            writer,
+            "{},{},{},{},{},{},{},{},{}",
+            time,
+            DESTROY_OPERATORS[destroy_operator],
+            REPAIR_OPERATORS[repair_operator],
+            removed.len(),
+            n_new_paths,
+            current_cost,
+            best_cost,
+            destroy_weights.weights.iter().map(|weight| weight.to_string()).collect::<Vec<String>>().join(","),
+            repair_weights.weights.iter().map(|weight| weight.to_string()).collect::<Vec<String>>().join(",")
+        )
+        .unwrap();
+    }
+
+    println!("best_cost={} (done in {}s)", best_cost, start_instant.elapsed().as_secs());
+
+    writeln!(r_writer, "{}s,{},{}", start_instant.elapsed().as_secs(), parameters.n_iterations, seed).unwrap();
+
+    SelectionState::from_groups_path_index(graph, cost_model, groups, best_groups_path_index)
+}
+
+/// returns the total cost of the selection, context has to contain exactly the selected paths
+fn selection_cost(groups: &[Group], context: &EvaluationContext, groups_path_index: &[usize]) -> i64 {
+    context.strained_edges_cost()
+        + groups
+            .iter()
+            .zip(groups_path_index.iter())
+            .map(|(group, path_index)| group.paths[*path_index].travel_cost() as i64 + group.paths[*path_index].delay_cost())
+            .sum::<i64>()
+}
+
+/// returns the sorted indices of the groups removed by operator
+fn destroy(
+    graph: &DiGraph<TimetableNode, TimetableEdge>,
+    groups: &[Group],
+    context: &EvaluationContext,
+    groups_path_index: &[usize],
+    operator: DestroyOperator,
+    parameters: &LnsParameters,
+    rng: &mut impl Rng,
+) -> Vec<usize> {
+    // most overloaded edges first
+    let mut overloaded_edges = context.overcrowded_edges(graph);
+    overloaded_edges.sort_by_key(|edge| std::cmp::Reverse(context.utilization_cost(graph, *edge)));
+
+    // without overloaded edges the operators work around a random edge of the selected paths
+    let anchor_edges: Vec<EdgeIndex> = if overloaded_edges.is_empty() {
+        groups
+            .iter()
+            .zip(groups_path_index.iter())
+            .flat_map(|(group, path_index)| group.paths[*path_index].edges.iter().copied())
+            .collect()
+    } else {
+        overloaded_edges.clone()
+    };
+
+    if anchor_edges.is_empty() {
+        // no group is transported
+        return Vec::new();
+    }
+
+    let anchor_edge = anchor_edges[rng.gen_range(0..anchor_edges.len())];
+    let (anchor_source, anchor_target) = graph.edge_endpoints(anchor_edge).unwrap();
+
+    // returns true if the group's selected path has to be removed
+    let is_removed: Box<dyn Fn(&Path) -> bool> = match operator {
+        DestroyOperator::OverloadedTrips => {
+            let edges: HashSet<EdgeIndex> = if overloaded_edges.is_empty() {
+                vec![anchor_edge].into_iter().collect()
+            } else {
+                overloaded_edges.into_iter().take(parameters.n_trips).collect()
+            };
+            Box::new(move |path: &Path| path.edges.iter().any(|edge| edges.contains(edge)))
+        }
+        DestroyOperator::Station => {
+            let stations = [graph[anchor_source].station_id(), graph[anchor_target].station_id()];
+            Box::new(move |path: &Path| {
+                path.edges.iter().any(|edge| {
+                    let (source, target) = graph.edge_endpoints(*edge).unwrap();
+                    stations.contains(&graph[source].station_id()) || stations.contains(&graph[target].station_id())
+                })
+            })
+        }
+        DestroyOperator::TimeWindow => {
+            let from = graph[anchor_source].time().saturating_sub(parameters.time_window / 2);
+            let to = graph[anchor_source].time() + parameters.time_window / 2;
+            Box::new(move |path: &Path| {
+                path.edges.iter().any(|edge| {
+                    let time = graph[graph.edge_endpoints(*edge).unwrap().0].time();
+                    from <= time && time <= to
+                })
+            })
+        }
+    };
+
+    groups
+        .iter()
+        .zip(groups_path_index.iter())
+        .enumerate()
+        .filter(|(_, (group, path_index))| is_removed(&group.paths[**path_index]))
+        .map(|(group_index, _)| group_index)
+        .collect()
+}
+
+/// searches a detour for each removed group and appends it to the group's paths if it is not known yet
+///
+/// the detour is the cheapest path if each Trip edge additionally costs the overload the group would cause
+/// on top of the utilization in context (i.e. of the remaining groups), returns the number of new paths
+fn append_detours(
+    graph: &DiGraph<TimetableNode, TimetableEdge>,
+    inaccessible_stations: &HashSet<u64>,
+    cost_model: &CostModel,
+    groups: &mut [Group],
+    groups_start: &[Option<NodeIndex>],
+    context: &EvaluationContext,
+    removed: &[usize],
+) -> u64 {
+    let mut n_new_paths = 0;
+
+    for group_index in removed.iter() {
+        let group = &groups[*group_index];
+
+        let start = match groups_start[*group_index] {
+            Some(start) => start,
+            None => continue,
+        };
+
+        // overload cost per passenger of the group on each Trip edge
+        let first_class_passengers = if group.first_class { group.passengers } else { 0 };
+        let multipliers: Vec<f64> = graph
+            .edge_indices()
+            .map(|edge| {
+                if !graph[edge].is_trip() {
+                    return 0.0;
+                }
+                let overload_cost = context.utilization_cost_delta(graph, edge, group.passengers as i64, first_class_passengers as i64);
+                overload_cost as f64 / group.passengers as f64
+            })
+            .collect();
+
+        let detour = lagrangian_relaxation::cheapest_path(graph, cost_model, &multipliers, group, start).filter(|(edges, _)| group.admits_path(graph, inaccessible_stations, edges));
+
+        if let Some((edges, _)) = detour {
+            let path = Path::new(graph, cost_model, edges, group.passengers, group.arrival_time).with_first_class(group.first_class);
+
+            if !group.paths.iter().any(|known_path| known_path.edges == path.edges) {
+                groups[*group_index].paths.push(path);
+                n_new_paths += 1;
+            }
+        }
+    }
+
+    n_new_paths
+}
+
+/// inserts the removed groups into context and groups_path_index
+fn repair(
+    graph: &DiGraph<TimetableNode, TimetableEdge>,
+    groups: &[Group],
+    context: &mut EvaluationContext,
+    groups_path_index: &mut [usize],
+    removed: &[usize],
+    operator: RepairOperator,
+) {
+    // insertion cost of each path of the group (sorted, cheapest first, lower path index on ties)
+    let insertion_costs = |context: &EvaluationContext, group_index: usize| -> Vec<(i64, usize)> {
+        let mut costs: Vec<(i64, usize)> = groups[group_index]
+            .paths
+            .iter()
+            .enumerate()
+            .map(|(path_index, path)| {
+                (context.strain_path_cost_delta(graph, path) + path.travel_cost() as i64 + path.delay_cost(), path_index)
+            })
+            .collect();
+        costs.sort_unstable();
+        costs
+    };
+
+    let mut remaining = removed.to_vec();
+
+    if operator == RepairOperator::Greedy {
+        // largest groups first
+        remaining.sort_by_key(|group_index| std::cmp::Reverse(groups[*group_index].passengers));
+    }
+
+    while !remaining.is_empty() {
+        let position = match operator {
+            RepairOperator::Greedy => 0,
+            RepairOperator::Regret => {
+                // the first group with the largest regret (groups with a single path cannot be postponed)
+                let regrets: Vec<i64> = remaining
+                    .iter()
+                    .map(|group_index| {
+                        let costs = insertion_costs(context, *group_index);
+                        costs.get(1).map_or(i64::MAX, |second| second.0 - costs[0].0)
+                    })
+                    .collect();
+                let max_regret = *regrets.iter().max().unwrap();
+                regrets.iter().position(|regret| *regret == max_regret).unwrap()
+            }
+        };
+
+        let group_index = remaining.remove(position);
+        let (_, path_index) = insertion_costs(context, group_index)[0];
+
+        context.strain_path(graph, &groups[group_index].paths[path_index]);
+        groups_path_index[group_index] = path_index;
+    }
+}
@@ -0,0 +1,306 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufWriter, Write},
+};
+
+use petgraph::graph::{DiGraph, EdgeIndex};
+
+use crate::model::{
+    cost_model::CostModel,
+    graph_weight::{TimetableEdge, TimetableNode},
+    group::Group,
+};
+
+/// path-based MIP formulation of the path selection problem, exportable as CPLEX LP or (free) MPS file
+///
+/// variables:
+/// - one binary `x_<group_index>_<path_index>` per path of each group (1 = path is selected)
+/// - continuous overflow segments `o_<edge_index>_<segment>` per Trip edge that can be utilized over its seats
+///
+/// minimize   sum(path.cost() * x) + sum(segment_slope * o)
+///
+/// subject to
+/// - `assign_<group_index>`: each group selects exactly one path
+/// - `cap_<edge_index>`: sum(passengers * x) - sum(o) <= seats of edge
+///
+/// the overflow segments are breakpoints of the (convex) `utilization_cost_with()` of the edge,
+/// with unit segments (see `n_overflow_segments`) and without first class seats the objective equals `SelectionState::cost` of each selection
+pub struct PathSelectionProgram {
+    pub variables: Vec<Variable>,
+    pub constraints: Vec<Constraint>,
+    pub exact: bool, // true if all overflow segments have unit width and no edge has first class seats (objective mirrors utilization_cost_by_class() exactly)
+}
+
+/// variable of the program
+pub struct Variable {
+    pub name: String,
+    pub objective: f64, // coefficient in the objective function (fractional slopes of widened overflow segments)
+    pub upper_bound: Option<u64>, // None for binaries, upper bound of continuous variables otherwise
+}
+
+/// sense of a constraint
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Sense {
+    Equal,
+    LessEqual,
+}
+
+/// linear constraint of the program
+pub struct Constraint {
+    pub name: String,
+    pub sense: Sense,
+    pub rhs: i64,
+    pub terms: Vec<(usize, i64)>, // (variable index, coefficient)
+}
+
+impl PathSelectionProgram {
+
+    /// builds the program for all paths of groups
+    ///
+    /// each Trip edge gets at most n_overflow_segments overflow segments,
+    /// if its maximum overflow is larger, the segments are widened (penalty is only exact at the breakpoints, overestimated in between)
+    pub fn from_groups(
+        graph: &DiGraph<TimetableNode, TimetableEdge>,
+        cost_model: &CostModel,
+        groups: &[Group],
+        n_overflow_segments: u64,
+    ) -> Self {
+        let mut variables = Vec::new();
+        let mut constraints = Vec::with_capacity(groups.len());
+
+        // Trip edge -> (variable index, passengers) of all paths using it
+        let mut edges_terms: HashMap<EdgeIndex, Vec<(usize, i64)>> = HashMap::new();
+
+        // maximum utilization of each Trip edge (every group counts once, as it selects only one path)
+        let mut edges_max_utilization: HashMap<EdgeIndex, u64> = HashMap::new();
+
+        // FIRST: one binary per path of each group
+        for (group_index, group) in groups.iter().enumerate() {
+            let mut assign_terms = Vec::with_capacity(group.paths.len());
+            let mut group_edges = Vec::new();
+
+            for (path_index, path) in group.paths.iter().enumerate() {
+                let variable_index = variables.len();
+                variables.push(Variable {
+                    name: format!("x_{}_{}", group_index, path_index),
+                    objective: path.cost() as f64,
+                    upper_bound: None,
+                });
+                assign_terms.push((variable_index, 1));
+
+                for edge_index in path.edges.iter() {
+                    if graph[*edge_index].is_trip() {
+                        edges_terms
+                            .entry(*edge_index)
+                            .or_default()
+                            .push((variable_index, group.passengers as i64));
+                        group_edges.push(*edge_index);
+                    }
+                }
+            }
+
+            group_edges.sort_unstable();
+            group_edges.dedup();
+            for edge_index in group_edges {
+                *edges_max_utilization.entry(edge_index).or_insert(0) += group.passengers;
+            }
+
+            constraints.push(Constraint {
+                name: format!("assign_{}", group_index),
+                sense: Sense::Equal,
+                rhs: 1,
+                terms: assign_terms,
+            });
+        }
+
+        // SECOND: capacity constraint with overflow segments for each Trip edge that may be overcrowded
+        let mut edge_indices: Vec<EdgeIndex> = edges_terms.keys().cloned().collect();
+        edge_indices.sort_unstable();
+
+        let mut exact = true;
+        for edge_index in edge_indices {
+            let edge = &graph[edge_index];
+            let seats = edge.seats();
+            let max_utilization = edges_max_utilization[&edge_index];
+
+            if max_utilization <= seats {
+                // utilization_cost_with() is zero up to (and including) the seats
+                continue;
+            }
+
+            // overflow over the seats (standing up to the capacity, overload beyond), first class seats are relaxed to seats
+            let max_overflow = max_utilization - seats;
+            let segment_width = max_overflow.div_ceil(n_overflow_segments.max(1));
+            exact &= segment_width == 1 && edge.first_class_seats() == 0;
+
+            let mut terms = edges_terms.remove(&edge_index).unwrap();
+
+            // segment between breakpoints a < b costs (cost(b) - cost(a)) / (b - a) per passenger, slopes are increasing (convex)
+            // (not rounded, so the penalty stays exact at the breakpoints)
+            let mut breakpoint = 0;
+            let mut segment = 0;
+            while breakpoint < max_overflow {
+                let next_breakpoint = (breakpoint + segment_width).min(max_overflow);
+                let slope = (edge.utilization_cost_with(cost_model, seats + next_breakpoint)
+                    - edge.utilization_cost_with(cost_model, seats + breakpoint)) as f64
+                    / (next_breakpoint - breakpoint) as f64;
+
+                terms.push((variables.len(), -1));
+                variables.push(Variable {
+                    name: format!("o_{}_{}", edge_index.index(), segment),
+                    objective: slope,
+                    upper_bound: Some(next_breakpoint - breakpoint),
+                });
+
+                breakpoint = next_breakpoint;
+                segment += 1;
+            }
+
+            constraints.push(Constraint {
+                name: format!("cap_{}", edge_index.index()),
+                sense: Sense::LessEqual,
+                rhs: seats as i64,
+                terms,
+            });
+        }
+
+        println!(
+            "[PathSelectionProgram::from_groups()]: {} variable(s), {} constraint(s), exact={}",
+            variables.len(),
+            constraints.len(),
+            exact
+        );
+
+        Self {
+            variables,
+            constraints,
+            exact,
+        }
+    }
+
+    /// saves the program as CPLEX LP file
+    pub fn save_lp_to_file(&self, filepath: &str) {
+        let mut writer = BufWriter::new(
+            File::create(filepath).unwrap_or_else(|_| panic!("Could not create file \"{}\"", filepath)),
+        );
+
+        writeln!(writer, "\\ path selection problem, exact={}", self.exact).unwrap();
+        writeln!(writer, "Minimize").unwrap();
+        write!(writer, " obj:").unwrap();
+        Self::write_lp_terms(
+            &mut writer,
+            self.variables
+                .iter()
+                .enumerate()
+                .map(|(variable_index, variable)| (variable_index, variable.objective)),
+            &self.variables,
+        );
+        writeln!(writer).unwrap();
+
+        writeln!(writer, "Subject To").unwrap();
+        for constraint in self.constraints.iter() {
+            write!(writer, " {}:", constraint.name).unwrap();
+            Self::write_lp_terms(&mut writer, constraint.terms.iter().map(|(variable_index, coefficient)| (*variable_index, *coefficient as f64)), &self.variables);
+
+            let sense = match constraint.sense {
+                Sense::Equal => "=",
+                Sense::LessEqual => "<=",
+            };
+            writeln!(writer, " {} {}", sense, constraint.rhs).unwrap();
+        }
+
+        writeln!(writer, "Bounds").unwrap();
+        for variable in self.variables.iter() {
+            if let Some(upper_bound) = variable.upper_bound {
+                writeln!(writer, " 0 <= {} <= {}", variable.name, upper_bound).unwrap();
+            }
+        }
+
+        writeln!(writer, "Binaries").unwrap();
+        for variable in self.variables.iter().filter(|variable| variable.upper_bound.is_none()) {
+            writeln!(writer, " {}", variable.name).unwrap();
+        }
+
+        writeln!(writer, "End").unwrap();
+    }
+
+    /// writes terms as ` + 3 x_0_0 - 1 o_12_0 + 2.5 o_12_1 ...` (LP lines are limited in length -> line break after every 8 terms)
+    fn write_lp_terms(
+        writer: &mut BufWriter<File>,
+        terms: impl Iterator<Item = (usize, f64)>,
+        variables: &[Variable],
+    ) {
+        for (term_index, (variable_index, coefficient)) in terms.enumerate() {
+            if term_index != 0 && term_index % 8 == 0 {
+                write!(writer, "\n   ").unwrap();
+            }
+
+            let sign = if coefficient < 0.0 { "-" } else { "+" };
+            write!(writer, " {} {} {}", sign, coefficient.abs(), variables[variable_index].name).unwrap();
+        }
+    }
+
+    /// saves the program as free MPS file
+    pub fn save_mps_to_file(&self, filepath: &str) {
+        let mut writer = BufWriter::new(
+            File::create(filepath).unwrap_or_else(|_| panic!("Could not create file \"{}\"", filepath)),
+        );
+
+        // MPS is column-oriented -> collect (constraint index, coefficient) for each variable
+        let mut variables_terms: Vec<Vec<(usize, i64)>> = vec![Vec::new(); self.variables.len()];
+        for (constraint_index, constraint) in self.constraints.iter().enumerate() {
+            for (variable_index, coefficient) in constraint.terms.iter() {
+                variables_terms[*variable_index].push((constraint_index, *coefficient));
+            }
+        }
+
+        writeln!(writer, "* path selection problem, exact={}", self.exact).unwrap();
+        writeln!(writer, "NAME mcfp_path_selection").unwrap();
+
+        writeln!(writer, "ROWS").unwrap();
+        writeln!(writer, " N obj").unwrap();
+        for constraint in self.constraints.iter() {
+            let sense = match constraint.sense {
+                Sense::Equal => "E",
+                Sense::LessEqual => "L",
+            };
+            writeln!(writer, " {} {}", sense, constraint.name).unwrap();
+        }
+
+        writeln!(writer, "COLUMNS").unwrap();
+        let mut in_integer_block = false;
+        for (variable, variable_terms) in self.variables.iter().zip(variables_terms.iter()) {
+            let is_binary = variable.upper_bound.is_none();
+
+            if is_binary != in_integer_block {
+                let marker = if is_binary { "INTORG" } else { "INTEND" };
+                writeln!(writer, " MARKER 'MARKER' '{}'", marker).unwrap();
+                in_integer_block = is_binary;
+            }
+
+            writeln!(writer, " {} obj {}", variable.name, variable.objective).unwrap();
+            for (constraint_index, coefficient) in variable_terms.iter() {
+                writeln!(writer, " {} {} {}", variable.name, self.constraints[*constraint_index].name, coefficient).unwrap();
+            }
+        }
+        if in_integer_block {
+            writeln!(writer, " MARKER 'MARKER' 'INTEND'").unwrap();
+        }
+
+        writeln!(writer, "RHS").unwrap();
+        for constraint in self.constraints.iter() {
+            writeln!(writer, " rhs {} {}", constraint.name, constraint.rhs).unwrap();
+        }
+
+        writeln!(writer, "BOUNDS").unwrap();
+        for variable in self.variables.iter() {
+            match variable.upper_bound {
+                Some(upper_bound) => writeln!(writer, " UP bnd {} {}", variable.name, upper_bound).unwrap(),
+                None => writeln!(writer, " BV bnd {}", variable.name).unwrap(),
+            }
+        }
+
+        writeln!(writer, "ENDATA").unwrap();
+    }
+}
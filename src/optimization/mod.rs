@@ -5,35 +5,161 @@ use std::{
     io::{BufWriter, Write},
 };
 
-use indexmap::IndexSet;
 use petgraph::{EdgeDirection::Outgoing, graph::{DiGraph, EdgeIndex, NodeIndex}};
-use rand::{prelude::ThreadRng, Rng};
-
-use crate::model::{
-    graph_weight::{TimetableEdge, TimetableNode},
-    group::Group,
-    path::{self, Path},
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    model::{
+        cost_model::CostModel,
+        graph_weight::{TimetableEdge, TimetableNode},
+        group::Group,
+        path::{self, Path},
+    },
+    time,
 };
 
+use evaluation::EvaluationContext;
+
+pub mod cooling_schedule;
+pub mod evaluation;
+pub mod lagrangian_relaxation;
+pub mod large_neighborhood_search;
+pub mod mip_export;
+pub mod parallel_simulated_annealing;
 pub mod randomized_best;
 pub mod randomized_hillclimb;
 pub mod simulated_annealing;
 pub(crate) mod simulated_annealing_on_path;
+pub mod split_assignment;
+pub mod tabu_search;
 
 /// This module contains the implementation of the SelectionState and its neighborhood generation
 /// and different optimization algorithms, trying to find an optimal SelectionState
 
 
+/// order in which generate_state_with_greedy_capacity() assigns the groups
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GroupOrder {
+    Passengers, // largest groups first
+    Departure,  // earliest departure time first
+    Slack,      // smallest slack first (planned arrival time minus earliest arrival of the group's paths)
+}
+
+impl GroupOrder {
+
+    /// parses the CLI name of an order
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "passengers" => Some(Self::Passengers),
+            "departure" => Some(Self::Departure),
+            "slack" => Some(Self::Slack),
+            _ => None,
+        }
+    }
+
+    /// returns the indices of groups in this order (groups with equal keys keep their input order)
+    pub fn sort(&self, groups: &[Group]) -> Vec<usize> {
+        let mut groups_order: Vec<usize> = (0..groups.len()).collect();
+
+        match self {
+            Self::Passengers => groups_order.sort_by_key(|group_index| std::cmp::Reverse(groups[*group_index].passengers)),
+            Self::Departure => groups_order.sort_by_key(|group_index| groups[*group_index].departure_time),
+            Self::Slack => groups_order.sort_by_key(|group_index| {
+                // the earliest arrival has the smallest delay
+                -groups[*group_index].paths.iter().filter(|path| !path.is_unserved()).map(|path| path.travel_delay()).min().unwrap_or(0)
+            }),
+        }
+
+        groups_order
+    }
+}
+
+impl fmt::Display for GroupOrder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Self::Passengers => "passengers",
+            Self::Departure => "departure",
+            Self::Slack => "slack",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// state the optimizers start with
+#[derive(Debug, Clone, PartialEq)]
+pub enum InitialState {
+    BestPath,              // generate_state_with_best_path_per_group()
+    Greedy(GroupOrder),    // generate_state_with_greedy_capacity()
+    Selection(Vec<usize>), // given path index of each group (warm start, e.g. with the selection before a disruption)
+}
+
+impl fmt::Display for InitialState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::BestPath => write!(f, "best_path"),
+            Self::Greedy(order) => write!(f, "greedy_{}", order),
+            Self::Selection(_) => write!(f, "selection"),
+        }
+    }
+}
+
+/// switch of the selected path of a single group, along with the changes of the cost it causes
+///
+/// a move is applied to the SelectionState in place, so an iteration of the optimizers only depends on the edges of both paths
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GroupMove {
+    pub group_index: usize,
+    pub old_path_index: usize,
+    pub new_path_index: usize,
+    pub strained_edges_cost_delta: i64,
+    pub travel_cost_delta: i64,
+    pub travel_delay_cost_delta: i64,
+}
+
+impl GroupMove {
+
+    /// calculates the cost changes of switching group's path from old_path_index to new_path_index
+    ///
+    /// context has to contain the path with old_path_index
+    pub fn new(
+        graph: &DiGraph<TimetableNode, TimetableEdge>,
+        context: &EvaluationContext,
+        groups: &[Group],
+        group_index: usize,
+        old_path_index: usize,
+        new_path_index: usize,
+    ) -> Self {
+        let old_path = &groups[group_index].paths[old_path_index];
+        let new_path = &groups[group_index].paths[new_path_index];
+
+        Self {
+            group_index,
+            old_path_index,
+            new_path_index,
+            strained_edges_cost_delta: context.switch_path_cost_delta(graph, old_path, new_path),
+            travel_cost_delta: new_path.travel_cost() as i64 - old_path.travel_cost() as i64,
+            travel_delay_cost_delta: new_path.delay_cost() - old_path.delay_cost(),
+        }
+    }
+
+    /// returns the change of the total cost
+    pub fn cost_delta(&self) -> i64 {
+        self.strained_edges_cost_delta + self.travel_cost_delta + self.travel_delay_cost_delta
+    }
+}
+
 /// formalizing a system state
 /// by storing the indices of the currently selected path for each group along with this state's cost
 #[derive(Debug, Clone)]
 pub struct SelectionState<'a> {
     pub groups: &'a Vec<Group>,
+    pub cost_model: &'a CostModel, // weights of all cost components
     pub cost: i64, // total cost of this path selection
     pub strained_edges_cost: i64,
     pub travel_cost: i64,
     pub travel_delay_cost: i64,
     pub groups_path_index: Vec<usize>, // array of indices (specifies selected path for each group)
+    pub groups_path_shares: Option<Vec<Vec<u64>>>, // passengers on each path of each group if the groups are split (groups_path_index then specifies the path with most passengers)
 }
 
 impl fmt::Display for SelectionState<'_> {
@@ -47,14 +173,15 @@ impl fmt::Display for SelectionState<'_> {
         for (group, group_path_index) in self.groups.iter().zip(self.groups_path_index.iter()) {
             write!(
                 f,
-                "\n[group_id={}]: {} ({}) -> {} ({}), travel_cost={}, travel_delay={}",
+                "\n[group_id={}]: {} ({}) -> {} ({}), travel_cost={}, travel_delay={}, delay_cost={}",
                 group.id,
                 group.start_station_id,
                 group.departure_time,
                 group.destination_station_id,
                 group.arrival_time,
                 group.paths[*group_path_index].travel_cost(),
-                group.paths[*group_path_index].travel_delay()
+                group.paths[*group_path_index].travel_delay(),
+                group.paths[*group_path_index].delay_cost()
             )?
         }
 
@@ -63,17 +190,6 @@ impl fmt::Display for SelectionState<'_> {
 }
 
 impl<'a> SelectionState<'a> {
-    /// calculate sum of utilization_cost of all strained edges
-    pub fn calculate_cost_of_strained_edges(
-        graph: &mut DiGraph<TimetableNode, TimetableEdge>,
-        strained_edges: &HashSet<EdgeIndex>,
-    ) -> u64 {
-        strained_edges
-            .iter()
-            .map(|e| graph[*e].utilization_cost())
-            .sum()
-    }
-
     /// calculate sum of travel_cost of all group's selected paths
     pub fn calculate_total_travel_cost_paths(
         groups: &Vec<Group>,
@@ -86,7 +202,7 @@ impl<'a> SelectionState<'a> {
             .sum::<u64>() as i64
     }
 
-    /// calculate sum of travel_delay of all group's selected paths
+    /// calculate sum of weighted travel_delay (delay_cost) of all group's selected paths
     pub fn calculate_total_travel_delay_cost_paths(
         groups: &Vec<Group>,
         groups_path_index: &Vec<usize>,
@@ -94,13 +210,14 @@ impl<'a> SelectionState<'a> {
         groups
             .iter()
             .zip(groups_path_index.iter())
-            .map(|(group, group_path_index)| group.paths[*group_path_index].travel_delay())
+            .map(|(group, group_path_index)| group.paths[*group_path_index].delay_cost())
             .sum()
     }
 
     /// saves groups of this SelectionState to csv file
     ///
-    /// creates a pipe-separated CSV with one group per line
+    /// creates a pipe-separated CSV with one group per line (one line per share of split groups)
+    /// groups on the virtual "no service" path have the path "unserved"
     ///
     /// saves all cost metrics of this state
     /// along with the path (as "->" separted string)
@@ -114,41 +231,56 @@ impl<'a> SelectionState<'a> {
         );
 
         writer
-            .write("group_id|planned_time|real_time|travel_cost|delay|delay_in_%|waiting_time|in_trip_time|walks|path\n".as_bytes())
+            .write("group_id|passengers|planned_time|real_time|travel_cost|delay|delay_in_%|waiting_time|in_trip_time|walks|walking_time|transfers|path\n".as_bytes())
             .unwrap();
         for (group_index, group) in self.groups.iter().enumerate() {
-            let path_index = self.groups_path_index[group_index];
-            let path = &group.paths[path_index];
-            let last_node_index = graph.edge_endpoints(*path.edges.last().unwrap()).unwrap().1;
-
-            let planned = group.arrival_time - group.departure_time;
-            let real = graph.node_weight(last_node_index).unwrap().time() - group.departure_time;
-            let travel_cost = path.travel_cost();
-            let delay = real as i64 - planned as i64;
-            let delay_p = 100 * delay / planned as i64;
-            let waiting_time = path.get_waiting_time(graph);
-            let in_trip_time = path.get_in_trip_time(graph);
-            let walks = path.get_walks(graph);
-            let path_string = path.to_string(graph);
-
-            writer
-                .write(
-                    format!(
-                        "{}|{}|{}|{}|{}|{}|{}|{}|{}|{}\n",
-                        group.id,
-                        planned,
-                        real,
-                        travel_cost,
-                        delay,
-                        delay_p,
-                        waiting_time,
-                        in_trip_time,
-                        walks,
-                        path_string
+            for (path_index, passengers) in self.path_shares(group_index) {
+                let path = &group.paths[path_index];
+
+                if path.is_unserved() {
+                    // virtual "no service" path: only its penalty as travel cost
+                    writer
+                        .write_all(format!("{}|{}|{}||{}||||||||unserved\n", group.id, passengers, group.arrival_time as i64 - group.departure_time as i64, path.travel_cost()).as_bytes())
+                        .unwrap();
+                    continue;
+                }
+
+                let last_node_index = graph.edge_endpoints(*path.edges.last().unwrap()).unwrap().1;
+
+                let planned = group.arrival_time - group.departure_time;
+                let real = graph.node_weight(last_node_index).unwrap().time() - group.departure_time;
+                let travel_cost = path.travel_cost();
+                let delay = real as i64 - planned as i64;
+                let delay_p = 100 * delay / planned as i64;
+                let waiting_time = path.get_waiting_time(graph);
+                let in_trip_time = path.get_in_trip_time(graph);
+                let walks = path.get_walks(graph);
+                let walking_time = path.get_walking_time(graph);
+                let transfers = path.get_transfers(graph);
+                let path_string = path.to_string(graph);
+
+                writer
+                    .write(
+                        format!(
+                            "{}|{}|{}|{}|{}|{}|{}|{}|{}|{}|{}|{}|{}\n",
+                            group.id,
+                            passengers,
+                            planned,
+                            real,
+                            travel_cost,
+                            delay,
+                            delay_p,
+                            waiting_time,
+                            in_trip_time,
+                            walks,
+                            walking_time,
+                            transfers,
+                            path_string
+                        )
+                        .as_bytes(),
                     )
-                    .as_bytes(),
-                )
-                .unwrap();
+                    .unwrap();
+            }
         }
     }
 
@@ -159,7 +291,7 @@ impl<'a> SelectionState<'a> {
     /// saves EdgeIndex, duration, capacity, and utilization
     pub fn save_strained_trip_edges_to_csv(
         &self,
-        graph: &mut DiGraph<TimetableNode, TimetableEdge>,
+        graph: &DiGraph<TimetableNode, TimetableEdge>,
         filepath: &str,
     ) {
         let mut writer = BufWriter::new(
@@ -167,123 +299,202 @@ impl<'a> SelectionState<'a> {
         );
 
         writer
-            .write("edge_index|duration|capacity|utilization\n".as_bytes())
+            .write("edge_index|duration|capacity|seats|first_class_seats|utilization|first_class_utilization|standing|overload|trip|from_station|departure|to_station|arrival\n".as_bytes())
             .unwrap();
 
-        let mut strained_edges: HashSet<EdgeIndex> = HashSet::new();
+        // strain all selected paths (or shares) to a new context
+        let context = match &self.groups_path_shares {
+            Some(groups_path_shares) => EvaluationContext::with_shares(graph, self.cost_model, self.groups, groups_path_shares),
+            None => EvaluationContext::new(graph, self.cost_model, self.groups, &self.groups_path_index),
+        };
 
-        // first: strain all selected paths to TimetableGraph
-        for (group_index, path_index) in self.groups_path_index.iter().enumerate() {
-            let path = &self.groups[group_index].paths[*path_index];
-            path.strain_to_graph(graph, &mut strained_edges);
-        }
+        for edge_index in graph.edge_indices() {
+            let edge = &graph[edge_index];
+            let utilization = context.utilization(edge_index);
+            let first_class_utilization = context.first_class_utilization(edge_index);
+
+            if edge.is_trip() && utilization != 0 {
+                let (departure, arrival) = graph.edge_endpoints(edge_index).unwrap();
+                let standing = utilization.min(edge.capacity()).saturating_sub(edge.seated(utilization, first_class_utilization));
 
-        // find a random overcrowded edge
-        for edge_index in &strained_edges {
-            let edge = &graph[*edge_index];
-            if edge.is_trip() {
                 writer
                     .write(
                         format!(
-                            "{:?}|{}|{}|{}\n",
+                            "{:?}|{}|{}|{}|{}|{}|{}|{}|{}|{}|{}|{}|{}|{}\n",
                             edge_index,
                             edge.duration(),
                             edge.capacity(),
-                            edge.utilization()
+                            edge.seats(),
+                            edge.first_class_seats(),
+                            utilization,
+                            first_class_utilization,
+                            standing,
+                            utilization.saturating_sub(edge.capacity()),
+                            graph[departure].trip_id().unwrap(),
+                            graph[departure].station_id(),
+                            time::format_time(graph[departure].time()),
+                            graph[arrival].station_id(),
+                            time::format_time(graph[arrival].time())
                         )
                         .as_bytes(),
                     )
                     .unwrap();
             }
         }
-
-        // third: relieve all selected paths to TimetableGraph
-        for (group_index, path_index) in self.groups_path_index.iter().enumerate() {
-            self.groups[group_index].paths[*path_index]
-                .relieve_from_graph(graph, &mut strained_edges);
-        }
     }
 
-    /// selects a random path for each group, calculates the state's cost and returns it
-    pub fn generate_random_state(
-        graph: &mut DiGraph<TimetableNode, TimetableEdge>,
+    /// creates the state for the given path selection and calculates its cost
+    pub fn from_groups_path_index(
+        graph: &DiGraph<TimetableNode, TimetableEdge>,
+        cost_model: &'a CostModel,
         groups: &'a Vec<Group>,
+        groups_path_index: Vec<usize>,
     ) -> Self {
-        let mut rng = rand::thread_rng();
-
-        let mut groups_path_index = Vec::with_capacity(groups.len());
-
-        for group in groups.iter() {
-            // iterate over all groups and generate a random index (in range of #paths of current group)
-            groups_path_index.push(rng.gen::<usize>() % group.paths.len());
-        }
-
-        let mut strained_edges: HashSet<EdgeIndex> = HashSet::new();
+        let context = EvaluationContext::new(graph, cost_model, groups, &groups_path_index);
 
-        // first: strain all selected paths to TimetableGraph
-        for (group_index, path_index) in groups_path_index.iter().enumerate() {
-            let path = &groups[group_index].paths[*path_index];
-            path.strain_to_graph(graph, &mut strained_edges);
-        }
-
-        let strained_edges_cost =
-            Self::calculate_cost_of_strained_edges(graph, &strained_edges) as i64;
+        let strained_edges_cost = context.strained_edges_cost();
         let travel_cost = Self::calculate_total_travel_cost_paths(groups, &groups_path_index);
         let travel_delay_cost =
             Self::calculate_total_travel_delay_cost_paths(groups, &groups_path_index);
         let cost = strained_edges_cost + travel_cost + travel_delay_cost;
 
-        // third: relieve all selected paths to TimetableGraph
-        for (group_index, path_index) in groups_path_index.iter().enumerate() {
-            groups[group_index].paths[*path_index].relieve_from_graph(graph, &mut strained_edges);
-        }
-
         Self {
             groups,
+            cost_model,
             cost,
             strained_edges_cost,
             travel_cost,
             travel_delay_cost,
             groups_path_index,
+            groups_path_shares: None,
         }
     }
 
-    /// selects the first path (index=0) for each group, calculates the state's cost and returns it
-    pub fn generate_state_with_best_path_per_group(
-        graph: &mut DiGraph<TimetableNode, TimetableEdge>,
+    /// creates the state for split groups (groups_path_shares[group][path] passengers on each path) and calculates its cost
+    ///
+    /// the travel and delay cost of a group is the average cost of its paths weighted by their passengers
+    pub fn from_groups_path_shares(
+        graph: &DiGraph<TimetableNode, TimetableEdge>,
+        cost_model: &'a CostModel,
         groups: &'a Vec<Group>,
+        groups_path_shares: Vec<Vec<u64>>,
     ) -> Self {
-        // each group's path vec is sorted (lowest cost first) -> set all selected indices to zero
-        let groups_path_index = vec![0; groups.len()];
+        let context = EvaluationContext::with_shares(graph, cost_model, groups, &groups_path_shares);
 
-        let mut strained_edges: HashSet<EdgeIndex> = HashSet::new();
-
-        // first: strain all selected paths to TimetableGraph
-        for (group_index, selected_path_index) in groups_path_index.iter().enumerate() {
-            let path = &groups[group_index].paths[*selected_path_index];
-            path.strain_to_graph(graph, &mut strained_edges);
-        }
-
-        let strained_edges_cost =
-            Self::calculate_cost_of_strained_edges(graph, &strained_edges) as i64;
+        // path with most passengers (first path on ties)
+        let groups_path_index = groups_path_shares
+            .iter()
+            .map(|path_shares| (0..path_shares.len()).rev().max_by_key(|path_index| path_shares[*path_index]).unwrap())
+            .collect();
+
+        let weighted_cost = |path_cost: &dyn Fn(&Path) -> i64| -> i64 {
+            groups
+                .iter()
+                .zip(groups_path_shares.iter())
+                .map(|(group, path_shares)| {
+                    group.paths.iter().zip(path_shares.iter()).map(|(path, passengers)| path_cost(path) * *passengers as i64).sum::<i64>()
+                        / group.passengers as i64
+                })
+                .sum()
+        };
 
-        let travel_cost = Self::calculate_total_travel_cost_paths(groups, &groups_path_index);
-        let travel_delay_cost =
-            Self::calculate_total_travel_delay_cost_paths(groups, &groups_path_index);
+        let strained_edges_cost = context.strained_edges_cost();
+        let travel_cost = weighted_cost(&|path| path.travel_cost() as i64);
+        let travel_delay_cost = weighted_cost(&|path| path.delay_cost());
         let cost = strained_edges_cost + travel_cost + travel_delay_cost;
 
-        // third: relieve all selected paths from TimetableGraph
-        for (group_index, path_index) in groups_path_index.iter().enumerate() {
-            groups[group_index].paths[*path_index].relieve_from_graph(graph, &mut strained_edges);
-        }
-
         Self {
             groups,
+            cost_model,
             cost,
             strained_edges_cost,
             travel_cost,
             travel_delay_cost,
             groups_path_index,
+            groups_path_shares: Some(groups_path_shares),
+        }
+    }
+
+    /// returns the passengers of group on each of its paths with at least one passenger
+    pub fn path_shares(&self, group_index: usize) -> Vec<(usize, u64)> {
+        match &self.groups_path_shares {
+            Some(groups_path_shares) => groups_path_shares[group_index]
+                .iter()
+                .enumerate()
+                .filter(|(_, passengers)| **passengers > 0)
+                .map(|(path_index, passengers)| (path_index, *passengers))
+                .collect(),
+            None => vec![(self.groups_path_index[group_index], self.groups[group_index].passengers)],
+        }
+    }
+
+    /// selects a random path for each group, calculates the state's cost and returns it
+    pub fn generate_random_state(
+        graph: &DiGraph<TimetableNode, TimetableEdge>,
+        cost_model: &'a CostModel,
+        groups: &'a Vec<Group>,
+        rng: &mut impl Rng,
+    ) -> Self {
+        let mut groups_path_index = Vec::with_capacity(groups.len());
+
+        for group in groups.iter() {
+            // iterate over all groups and generate a random index (in range of #paths of current group)
+            groups_path_index.push(rng.gen::<usize>() % group.paths.len());
+        }
+
+        Self::from_groups_path_index(graph, cost_model, groups, groups_path_index)
+    }
+
+    /// selects the first path (index=0) for each group, calculates the state's cost and returns it
+    pub fn generate_state_with_best_path_per_group(
+        graph: &DiGraph<TimetableNode, TimetableEdge>,
+        cost_model: &'a CostModel,
+        groups: &'a Vec<Group>,
+    ) -> Self {
+        // each group's path vec is sorted (lowest cost first) -> set all selected indices to zero
+        Self::from_groups_path_index(graph, cost_model, groups, vec![0; groups.len()])
+    }
+
+    /// constructs a state by assigning one group after another (in the given order) to its cheapest path
+    /// regarding the residual capacity of the Trip edges, i.e. the utilization of the groups assigned before
+    ///
+    /// the cost of a path is its travel and delay cost plus the overcrowding cost it would cause additionally,
+    /// so a path exceeding the residual capacity is only selected if all other paths are more expensive (lowest path index on ties)
+    pub fn generate_state_with_greedy_capacity(
+        graph: &DiGraph<TimetableNode, TimetableEdge>,
+        cost_model: &'a CostModel,
+        groups: &'a Vec<Group>,
+        order: GroupOrder,
+    ) -> Self {
+        // no group assigned yet
+        let mut context = EvaluationContext::new(graph, cost_model, &[], &[]);
+        let mut groups_path_index = vec![0; groups.len()];
+
+        for group_index in order.sort(groups) {
+            let paths = &groups[group_index].paths;
+
+            let path_index = (0..paths.len())
+                .min_by_key(|path_index| paths[*path_index].cost() + context.strain_path_cost_delta(graph, &paths[*path_index]))
+                .unwrap();
+
+            context.strain_path(graph, &paths[path_index]);
+            groups_path_index[group_index] = path_index;
+        }
+
+        Self::from_groups_path_index(graph, cost_model, groups, groups_path_index)
+    }
+
+    /// generates the state the optimizers start with
+    pub fn generate_initial_state(
+        graph: &DiGraph<TimetableNode, TimetableEdge>,
+        cost_model: &'a CostModel,
+        groups: &'a Vec<Group>,
+        initial_state: &InitialState,
+    ) -> Self {
+        match initial_state {
+            InitialState::BestPath => Self::generate_state_with_best_path_per_group(graph, cost_model, groups),
+            InitialState::Greedy(order) => Self::generate_state_with_greedy_capacity(graph, cost_model, groups, *order),
+            InitialState::Selection(groups_path_index) => Self::from_groups_path_index(graph, cost_model, groups, groups_path_index.clone()),
         }
     }
 
@@ -294,86 +505,37 @@ impl<'a> SelectionState<'a> {
     /// WARNING: neighborhood quickly becomes VERY large
     pub fn all_group_neighbors(
         &self,
-        graph: &mut DiGraph<TimetableNode, TimetableEdge>,
+        graph: &DiGraph<TimetableNode, TimetableEdge>,
     ) -> Vec<Vec<Self>> {
         let mut neighbors = Vec::with_capacity(self.groups.len());
 
-        // stores all edges currently strained to the graph
-        let mut strained_edges = HashSet::new();
-
-        // for faster cost calculation now strain all actual selected paths to the graph and only switch paths for the groups we are currently working on
-        for (group_index, path_index) in self.groups_path_index.iter().enumerate() {
-            let path = &self.groups[group_index].paths[*path_index];
-            path.strain_to_graph(graph, &mut strained_edges);
-        }
+        // strain all actual selected paths once, each neighbor's cost is calculated from the delta of the switched paths
+        let context = EvaluationContext::new(graph, self.cost_model, self.groups, &self.groups_path_index);
 
         // iterate over all groups_paths_selection
         for group_index in 0..self.groups_path_index.len() {
-            let mut intermediate_neighbors = Vec::with_capacity(self.groups_path_index.len());
-
-            let self_selected_path_index = self.groups_path_index[group_index];
-
-            // relieve the self selected path of current group
-            self.groups[group_index].paths[self_selected_path_index]
-                .relieve_from_graph(graph, &mut strained_edges);
+            let n_paths_of_group = self.groups[group_index].paths.len();
+            let mut intermediate_neighbors = Vec::with_capacity(n_paths_of_group);
 
             // for each group add state with all possible paths for current group
-            let n_paths_of_group = self.groups[group_index].paths.len();
             for path_index in 0..n_paths_of_group {
                 if path_index == self.groups_path_index[group_index] {
                     // skip self selected path_index
                     continue;
                 }
 
-                // strain new path (for current group) to graph
-                self.groups[group_index].paths[path_index]
-                    .strain_to_graph(graph, &mut strained_edges);
-                // calculate cost of all strained edges
-                let strained_edges_cost =
-                    Self::calculate_cost_of_strained_edges(graph, &strained_edges) as i64;
-                // relieve new path from graph
-                self.groups[group_index].paths[path_index]
-                    .relieve_from_graph(graph, &mut strained_edges);
-
-                let mut groups_paths_selection_clone = self.groups_path_index.clone();
-                groups_paths_selection_clone[group_index] = path_index; // set current path_index as selected
-
-                let travel_cost = Self::calculate_total_travel_cost_paths(
-                    &self.groups,
-                    &groups_paths_selection_clone,
-                );
-                let travel_delay_cost = Self::calculate_total_travel_delay_cost_paths(
-                    &self.groups,
-                    &groups_paths_selection_clone,
-                );
-                let cost = strained_edges_cost + travel_cost + travel_delay_cost;
-
-                let selection_state = Self {
-                    groups: self.groups,
-                    cost,
-                    strained_edges_cost,
-                    travel_cost,
-                    travel_delay_cost,
-                    groups_path_index: groups_paths_selection_clone,
-                };
-
-                intermediate_neighbors.push(selection_state);
+                intermediate_neighbors.push(self.group_neighbor_from_group_and_path(
+                    graph,
+                    &context,
+                    self.groups,
+                    group_index,
+                    path_index,
+                ));
             }
 
-            // re-add the actually selected path for current group to graph
-            self.groups[group_index].paths[self_selected_path_index]
-                .strain_to_graph(graph, &mut strained_edges);
-
             neighbors.push(intermediate_neighbors);
         }
 
-        // at the beginning of the function we strained all actual selected paths to the graph
-        // before returning relieve them
-        for (group_index, path_index) in self.groups_path_index.iter().enumerate() {
-            self.groups[group_index].paths[*path_index]
-                .relieve_from_graph(graph, &mut strained_edges);
-        }
-
         neighbors
     }
 
@@ -384,276 +546,186 @@ impl<'a> SelectionState<'a> {
     /// this function also efficiently calculates the cost during creation of path configurations
     pub fn all_direct_group_neighbors(
         &self,
-        graph: &mut DiGraph<TimetableNode, TimetableEdge>,
+        graph: &DiGraph<TimetableNode, TimetableEdge>,
     ) -> Vec<Vec<Self>> {
         let mut neighbors = Vec::with_capacity(self.groups.len());
 
-        // stores all edges currently strained to the graph
-        let mut strained_edges = HashSet::new();
-
-        // for faster cost calculation now strain all self selected paths to the graph and only switch paths for the groups we are currently working on
-        for (group_index, path_index) in self.groups_path_index.iter().enumerate() {
-            self.groups[group_index].paths[*path_index].strain_to_graph(graph, &mut strained_edges);
-        }
+        // strain all actual selected paths once, each neighbor's cost is calculated from the delta of the switched paths
+        let context = EvaluationContext::new(graph, self.cost_model, self.groups, &self.groups_path_index);
 
         // iterate over all groups_paths_selection
         for group_index in 0..self.groups_path_index.len() {
-            let mut intermediate_neighbors = Vec::with_capacity(self.groups_path_index.len());
+            let mut intermediate_neighbors = Vec::with_capacity(2);
 
             // fetch selected path index of current group
             let actual_selected_path_index = self.groups_path_index[group_index];
 
-            // relieve the actual selected path of current group
-            self.groups[group_index].paths[actual_selected_path_index]
-                .relieve_from_graph(graph, &mut strained_edges);
-
             // create state with index decremented by one
             if actual_selected_path_index != 0 {
-                let mut groups_paths_selection_clone = self.groups_path_index.clone();
-                groups_paths_selection_clone[group_index] -= 1;
-
-                // strain new path (for current group) to graph
-                self.groups[group_index].paths[actual_selected_path_index - 1]
-                    .strain_to_graph(graph, &mut strained_edges);
-                // calculate cost of all strained edges
-                let strained_edges_cost =
-                    Self::calculate_cost_of_strained_edges(graph, &strained_edges) as i64;
-                // relieve new path from graph
-                self.groups[group_index].paths[actual_selected_path_index - 1]
-                    .relieve_from_graph(graph, &mut strained_edges);
-
-                let travel_cost = Self::calculate_total_travel_cost_paths(
-                    &self.groups,
-                    &groups_paths_selection_clone,
-                );
-                let travel_delay_cost = Self::calculate_total_travel_delay_cost_paths(
-                    &self.groups,
-                    &groups_paths_selection_clone,
-                );
-                let cost = strained_edges_cost + travel_cost + travel_delay_cost;
-
-                let selection_state = Self {
-                    groups: self.groups,
-                    cost,
-                    strained_edges_cost,
-                    travel_cost,
-                    travel_delay_cost,
-                    groups_path_index: groups_paths_selection_clone,
-                };
-
-                intermediate_neighbors.push(selection_state);
+                intermediate_neighbors.push(self.group_neighbor_from_group_and_path(
+                    graph,
+                    &context,
+                    self.groups,
+                    group_index,
+                    actual_selected_path_index - 1,
+                ));
             }
 
+            // create state with index incremented by one
             if actual_selected_path_index != self.groups[group_index].paths.len() - 1 {
-                let mut groups_paths_selection_clone = self.groups_path_index.clone();
-                groups_paths_selection_clone[group_index] += 1;
-
-                // strain new path (for current group) to graph
-                self.groups[group_index].paths[actual_selected_path_index + 1]
-                    .strain_to_graph(graph, &mut strained_edges);
-                // calculate cost of all strained edges
-                let strained_edges_cost =
-                    Self::calculate_cost_of_strained_edges(graph, &strained_edges) as i64;
-                // relieve new path from graph
-                self.groups[group_index].paths[actual_selected_path_index + 1]
-                    .relieve_from_graph(graph, &mut strained_edges);
-
-                let travel_cost = Self::calculate_total_travel_cost_paths(
-                    &self.groups,
-                    &groups_paths_selection_clone,
-                );
-                let travel_delay_cost = Self::calculate_total_travel_delay_cost_paths(
-                    &self.groups,
-                    &groups_paths_selection_clone,
-                );
-                let cost = strained_edges_cost + travel_cost + travel_delay_cost;
-
-                let selection_state = Self {
-                    groups: self.groups,
-                    cost,
-                    strained_edges_cost,
-                    travel_cost,
-                    travel_delay_cost,
-                    groups_path_index: groups_paths_selection_clone,
-                };
-
-                intermediate_neighbors.push(selection_state);
+                intermediate_neighbors.push(self.group_neighbor_from_group_and_path(
+                    graph,
+                    &context,
+                    self.groups,
+                    group_index,
+                    actual_selected_path_index + 1,
+                ));
             }
 
-            // re-add the actually selected path for current group to graph
-            self.groups[group_index].paths[actual_selected_path_index]
-                .strain_to_graph(graph, &mut strained_edges);
-
             neighbors.push(intermediate_neighbors);
         }
 
-        // at the beginning of the function we strained all actual selected paths to the graph
-        // before returning relieve them
-        for (group_index, path_index) in self.groups_path_index.iter().enumerate() {
-            self.groups[group_index].paths[*path_index]
-                .relieve_from_graph(graph, &mut strained_edges);
-        }
-
         neighbors
     }
 
-    /// generates a single SelectionState neighbor
+    /// describes switching the selected path of a random group (of movable_groups, of all groups if None) to a random path
     ///
-    /// if not specified, select a random path for a random group
-    pub fn group_neighbor(
+    /// context has to contain the selected paths of self
+    pub fn random_group_move(
         &self,
-        graph: &mut DiGraph<TimetableNode, TimetableEdge>,
-        rng: &mut ThreadRng,
-
-        group_index_option: Option<usize>,
-        path_index_option: Option<usize>,
-    ) -> Self {
-        let group_index = match group_index_option {
-            Some(group_index) => group_index,
+        graph: &DiGraph<TimetableNode, TimetableEdge>,
+        context: &EvaluationContext,
+        rng: &mut impl Rng,
+        movable_groups: Option<&[usize]>,
+    ) -> GroupMove {
+        let group_index = match movable_groups {
+            Some(movable_groups) => movable_groups[rng.gen::<usize>() % movable_groups.len()],
             None => rng.gen::<usize>() % self.groups.len(),
         };
+        let path_index = rng.gen::<usize>() % self.groups[group_index].paths.len();
 
-        let path_index = match path_index_option {
-            Some(path_index) => path_index,
-            None => rng.gen::<usize>() % self.groups[group_index].paths.len(),
-        };
+        self.group_move(graph, context, group_index, path_index)
+    }
+
+    /// describes switching the selected path of the group with group_index to path with path_index
+    ///
+    /// only visits the edges of the old and the new path, therefore context has to contain the selected paths of self
+    pub fn group_move(
+        &self,
+        graph: &DiGraph<TimetableNode, TimetableEdge>,
+        context: &EvaluationContext,
+        group_index: usize,
+        path_index: usize,
+    ) -> GroupMove {
+        GroupMove::new(graph, context, self.groups, group_index, self.groups_path_index[group_index], path_index)
+    }
 
-        let mut groups_paths_selection = self.groups_path_index.clone();
-        groups_paths_selection[group_index] = path_index;
+    /// applies group_move to this state and switches the paths in context (which has to contain the selected paths of self)
+    pub fn apply_move(
+        &mut self,
+        graph: &DiGraph<TimetableNode, TimetableEdge>,
+        context: &mut EvaluationContext,
+        group_move: &GroupMove,
+    ) {
+        let paths = &self.groups[group_move.group_index].paths;
+        context.switch_path(graph, &paths[group_move.old_path_index], &paths[group_move.new_path_index]);
+
+        self.change_selection(group_move, 1);
+    }
 
-        let mut strained_edges: HashSet<EdgeIndex> = HashSet::new();
+    /// returns the state before moves were applied (in the given order), without the need of a context
+    pub fn revert_moves(&self, moves: &[GroupMove]) -> Self {
+        let mut state = self.clone();
 
-        // first: strain all selected paths to TimetableGraph
-        for (group_index, path_index) in groups_paths_selection.iter().enumerate() {
-            let path = &self.groups[group_index].paths[*path_index];
-            path.strain_to_graph(graph, &mut strained_edges);
+        for group_move in moves.iter().rev() {
+            state.change_selection(group_move, -1);
         }
 
-        let strained_edges_cost =
-            Self::calculate_cost_of_strained_edges(graph, &strained_edges) as i64;
-        let travel_cost =
-            Self::calculate_total_travel_cost_paths(self.groups, &groups_paths_selection);
-        let travel_delay_cost =
-            Self::calculate_total_travel_delay_cost_paths(self.groups, &groups_paths_selection);
-        let cost = strained_edges_cost + travel_cost + travel_delay_cost;
+        state
+    }
 
-        // third: relieve all selected paths to TimetableGraph
-        for (group_index, path_index) in groups_paths_selection.iter().enumerate() {
-            self.groups[group_index].paths[*path_index]
-                .relieve_from_graph(graph, &mut strained_edges);
-        }
+    /// selects the new path of group_move (sign = 1) or the old path (sign = -1) and updates the cost of this state
+    fn change_selection(&mut self, group_move: &GroupMove, sign: i64) {
+        self.groups_path_index[group_move.group_index] = if sign > 0 {
+            group_move.new_path_index
+        } else {
+            group_move.old_path_index
+        };
 
-        Self {
-            groups: self.groups,
-            cost,
-            strained_edges_cost,
-            travel_cost,
-            travel_delay_cost,
-            groups_path_index: groups_paths_selection,
-        }
+        self.strained_edges_cost += sign * group_move.strained_edges_cost_delta;
+        self.travel_cost += sign * group_move.travel_cost_delta;
+        self.travel_delay_cost += sign * group_move.travel_delay_cost_delta;
+        self.cost += sign * group_move.cost_delta();
     }
 
-    /// generates a single SelectionState neighbor 
+    /// generates a single SelectionState neighbor
     /// by changing the the selected path of the group with group_index to path with path_index
     ///
+    /// the cost is calculated incrementally from the old and the new path of the group,
+    /// therefore context has to contain the selected paths of self
+    ///
     /// returns a new SelectionState
     pub fn group_neighbor_from_group_and_path(
         &self,
-        graph: &mut DiGraph<TimetableNode, TimetableEdge>,
-        groups: &mut Vec<Group>,
+        graph: &DiGraph<TimetableNode, TimetableEdge>,
+        context: &EvaluationContext,
+        groups: &[Group],
         group_index: usize,
         path_index: usize,
     ) -> Self {
-        let mut groups_paths_selection = self.groups_path_index.clone();
-        groups_paths_selection[group_index] = path_index;
-
-        let mut strained_edges: HashSet<EdgeIndex> = HashSet::new();
+        let group_move = GroupMove::new(graph, context, groups, group_index, self.groups_path_index[group_index], path_index);
 
-        // first: strain all selected paths to TimetableGraph
-        for (group_index, path_index) in groups_paths_selection.iter().enumerate() {
-            let path = &groups[group_index].paths[*path_index];
-            path.strain_to_graph(graph, &mut strained_edges);
-        }
-
-        let strained_edges_cost =
-            Self::calculate_cost_of_strained_edges(graph, &strained_edges) as i64;
-        let travel_cost = Self::calculate_total_travel_cost_paths(groups, &groups_paths_selection);
-        let travel_delay_cost =
-            Self::calculate_total_travel_delay_cost_paths(groups, &groups_paths_selection);
-        let cost = strained_edges_cost + travel_cost + travel_delay_cost;
-
-        // third: relieve all selected paths to TimetableGraph
-        for (group_index, path_index) in groups_paths_selection.iter().enumerate() {
-            &groups[group_index].paths[*path_index].relieve_from_graph(graph, &mut strained_edges);
-        }
+        let mut neighbor = self.clone();
+        neighbor.groups_path_shares = None;
+        neighbor.change_selection(&group_move, 1);
 
-        Self {
-            groups: self.groups,
-            cost,
-            strained_edges_cost,
-            travel_cost,
-            travel_delay_cost,
-            groups_path_index: groups_paths_selection,
-        }
+        neighbor
     }
 
     /// selects a randomly selected overcrowded edge and its straining groups
-    /// returns tuple of edge's index and a vector of the straining groups indices
+    /// returns tuple of edge's index and a vector of the straining groups indices (None if no edge is overcrowded)
+    ///
+    /// context has to contain the selected paths of self
     pub fn get_random_overcrowded_edge_with_groups(
         &self,
-        graph: &mut DiGraph<TimetableNode, TimetableEdge>,
+        graph: &DiGraph<TimetableNode, TimetableEdge>,
+        context: &EvaluationContext,
         groups: &mut Vec<Group>,
-        rng: &mut ThreadRng,
-    ) -> (EdgeIndex, Vec<usize>) {
-        let groups_paths_selection = self.groups_path_index.clone();
-
-        let mut strained_edges: HashSet<EdgeIndex> = HashSet::new();
-
-        // first: strain all selected paths to TimetableGraph
-        for (group_index, path_index) in groups_paths_selection.iter().enumerate() {
-            let path = &groups[group_index].paths[*path_index];
-            path.strain_to_graph(graph, &mut strained_edges);
-        }
-
-        let mut edges = Vec::new();
-
+        rng: &mut impl Rng,
+    ) -> Option<(EdgeIndex, Vec<usize>)> {
         // find a random overcrowded edge
-        for edge_index in strained_edges.clone() {
-            if graph[edge_index].utilization_cost() > 0 {
-                edges.push(edge_index);
-            }
-        }
+        let edges = context.overcrowded_edges(graph);
 
         print!("num_edges={}, ", edges.len());
 
+        if edges.is_empty() {
+            return None;
+        }
+
         let random_edge_index = rng.gen::<usize>() % edges.len();
         let random_edge = edges[random_edge_index];
 
         let mut group_indices = Vec::new();
 
-        for (group_index, path_index) in groups_paths_selection.iter().enumerate() {
+        for (group_index, path_index) in self.groups_path_index.iter().enumerate() {
             let path = &groups[group_index].paths[*path_index];
             if path.edges.contains(&random_edge) {
                 group_indices.push(group_index);
             }
         }
 
-        // third: relieve all selected paths to TimetableGraph
-        for (group_index, path_index) in groups_paths_selection.iter().enumerate() {
-            groups[group_index].paths[*path_index].relieve_from_graph(graph, &mut strained_edges);
-        }
-
-        (random_edge, group_indices)
+        Some((random_edge, group_indices))
     }
 
     /// tries to find a detour for a random group straining the given edge
     pub fn find_detour_for_random_group(
         &self,
-        graph: &mut DiGraph<TimetableNode, TimetableEdge>,
+        graph: &DiGraph<TimetableNode, TimetableEdge>,
+        inaccessible_stations: &HashSet<u64>, // stations without step-free access (see `GroupConstraints::step_free`)
         groups: &mut Vec<Group>,
         group_indices: Vec<usize>,
         edge: EdgeIndex,
-        rng: &mut ThreadRng,
+        rng: &mut impl Rng,
     ) -> (usize, Option<Path>) {
 
         // select random group for detour
@@ -664,22 +736,14 @@ impl<'a> SelectionState<'a> {
         let path_index = self.groups_path_index[random_group];
         let path = &groups[random_group].paths[path_index].clone();
 
-        // find all edges before chosen overcrowded edge in path
-        let mut edges_before_edge = IndexSet::new();
-
-        for edge_index in &path.edges {
-            if *edge_index == edge {
-                break;
-            }
-            edges_before_edge.insert(*edge_index);
-        }
-
-        // start with edge before selected overcrowded edge
-        edges_before_edge.reverse();
+        // all edges before chosen overcrowded edge in path
+        let path_edges: Vec<EdgeIndex> = path.edges.iter().copied().collect();
+        let edge_position = path_edges.iter().position(|edge_index| *edge_index == edge).unwrap_or(path_edges.len());
 
         let end = graph.edge_endpoints(*path.edges.last().unwrap()).unwrap().1;
 
-        for edge_index in edges_before_edge.clone() {
+        // start with edge before selected overcrowded edge
+        for (position, edge_index) in path_edges[..edge_position].iter().copied().enumerate().rev() {
 
             // get start node
             let (start, _) = graph.edge_endpoints(edge_index).unwrap();
@@ -710,10 +774,13 @@ impl<'a> SelectionState<'a> {
                 // get possible paths from current start node to end node
                 possible_paths = path::Path::dfs_visitor_search(
                     graph,
+                    self.cost_model,
                     start,
                     groups[random_group].destination_station_id,
                     groups[random_group].passengers as u64,
                     graph[end].time(),
+                    &groups[random_group].constraints,
+                    inaccessible_stations,
                     0,
                 );
                 // }
@@ -725,28 +792,24 @@ impl<'a> SelectionState<'a> {
                     possible_paths.sort_unstable_by_key(|p| p.cost());
                     path_index = 0;
 
-                    let mut new_path = Vec::new();
+                    // build new path completely (edges before edge_index followed by the detour)
+                    let mut new_path = path_edges[..position].to_vec();
+                    new_path.extend(possible_paths[path_index].edges.iter().copied());
 
-                    // build new path completely
-                    edges_before_edge.reverse();
-                    for next_edge_index in edges_before_edge.iter() {
-                        if *next_edge_index == edge_index {
-                            break;
-                        }
-                        new_path.push(*next_edge_index);
-                    }
-                    for next_edge_index in possible_paths[path_index].edges.iter() {
-                        new_path.push(*next_edge_index);
+                    // the detour may violate the constraints of the group along with the edges before it
+                    if !groups[random_group].admits_path(graph, inaccessible_stations, &new_path) {
+                        continue;
                     }
 
                     return (
                         random_group,
                         Some(Path::new(
                             graph,
+                            self.cost_model,
                             new_path,
                             groups[random_group].passengers as u64,
                             groups[random_group].arrival_time,
-                        )),
+                        ).with_first_class(groups[random_group].first_class)),
                     );
                 }
             }
@@ -759,7 +822,9 @@ impl<'a> SelectionState<'a> {
 
 
 /// generates and saves the neighborhood of states for analysis purposes
-pub fn analyze_neighborhood(graph: &mut DiGraph<TimetableNode, TimetableEdge>, groups: &Vec<Group>, folderpath: &str, n_iterations: usize) {
+pub fn analyze_neighborhood(graph: &mut DiGraph<TimetableNode, TimetableEdge>, cost_model: &CostModel, groups: &Vec<Group>, folderpath: &str, n_iterations: usize, seed: u64) {
+
+    let mut rng = StdRng::seed_from_u64(seed);
 
     // use all_group_neighbors
     for iteration in 0..n_iterations {
@@ -772,7 +837,7 @@ pub fn analyze_neighborhood(graph: &mut DiGraph<TimetableNode, TimetableEdge>, g
         writer.write("group_index,path_index,combined_cost,strained_edges_cost,travel_cost,travel_delay,sum_path_len\n".as_bytes()).unwrap();
 
         // write initial state
-        let initial = SelectionState::generate_random_state(graph, groups);
+        let initial = SelectionState::generate_random_state(graph, cost_model, groups, &mut rng);
         writer.write(format!(
             "{},{},{},{},{},{},{}\n",
             0,
@@ -815,7 +880,7 @@ pub fn analyze_neighborhood(graph: &mut DiGraph<TimetableNode, TimetableEdge>, g
         writer.write("group_index,path_index,combined_cost,strained_edges_cost,travel_cost,travel_delay,sum_path_len\n".as_bytes()).unwrap();
 
         // write initial state
-        let initial = SelectionState::generate_random_state(graph, groups);
+        let initial = SelectionState::generate_random_state(graph, cost_model, groups, &mut rng);
         writer.write(format!(
             "{},{},{},{},{},{},{}\n",
             0,
@@ -850,13 +915,14 @@ pub fn analyze_neighborhood(graph: &mut DiGraph<TimetableNode, TimetableEdge>, g
 /// tests the integrity of selection states after running optimization algorithms
 #[cfg(test)]
 mod tests {
-    use std::collections::HashSet;
+    use std::collections::HashMap;
 
     use petgraph::{EdgeDirection::Outgoing, graph::{DiGraph, EdgeIndex, NodeIndex}};
+    use rand::{rngs::StdRng, SeedableRng};
 
-    use crate::model::{Model, graph_weight::{TimetableEdge, TimetableNode}, group::Group};
+    use crate::model::{Model, cost_model::{CostModel, DelayWeight, EdgeKindWeight, OverloadPenalty, PenaltySegment}, graph_weight::{TimetableEdge, TimetableNode}, group::Group, path::PathSearch};
 
-    use super::{SelectionState, randomized_best, randomized_hillclimb, simulated_annealing, simulated_annealing_on_path};
+    use super::{GroupOrder, InitialState, SelectionState, cooling_schedule::{AnnealingParameters, ScheduleKind, StoppingCriteria}, evaluation::EvaluationContext, lagrangian_relaxation, large_neighborhood_search::{self, LnsParameters}, mip_export::{PathSelectionProgram, Sense, Variable}, parallel_simulated_annealing::parallel_simulated_annealing, randomized_best, randomized_hillclimb, simulated_annealing, simulated_annealing_on_path, split_assignment, tabu_search::{self, Neighborhood, TabuParameters}};
 
     /// tests the integrity of the paths of all groups
     #[test]
@@ -865,13 +931,14 @@ mod tests {
         let mut model = Model::load_from_file();
         let groups = Group::load_from_file();
 
+        let cost_model = CostModel::default();
         let mut groups_with_at_least_one_path: Vec<Group> = groups.clone().into_iter().filter(|g| !g.paths.is_empty()).collect();
 
-        let selection_state = simulated_annealing::simulated_annealing(&mut model.graph, &groups_with_at_least_one_path, "eval/simulated_annealing_test", 15000);
+        let selection_state = simulated_annealing::simulated_annealing(&mut model.graph, &cost_model, &groups_with_at_least_one_path, "eval/simulated_annealing_test", &AnnealingParameters::with_iterations(15000), rand::random());
         validate_groups_paths_integrity_state(&mut model, &selection_state);
 
         let mut groups_cloned = groups_with_at_least_one_path.clone();
-        let selection_state = simulated_annealing_on_path::simulated_annealing(&mut model.graph, &mut groups_cloned, selection_state, "eval/simulated_annealing_on_path_test", 500);
+        let selection_state = simulated_annealing_on_path::simulated_annealing(&model.graph, &model.inaccessible_stations, &mut groups_cloned, selection_state, "eval/simulated_annealing_on_path_test", &sample_parameters_on_path(500), rand::random());
         validate_groups_paths_integrity_state(&mut model, &selection_state);
 
         // let mut groups_with_at_least_one_path: Vec<Group> = groups.clone().into_iter().filter(|g| !g.paths.is_empty()).collect();
@@ -961,7 +1028,8 @@ mod tests {
             // test if time of start node is >= groups departure time
             assert!(start_timetable_node.time() >= group.departure_time, "Start node's time is smaller than group's departure time!");
 
-            for path in paths {
+            // the unserved path has no edges
+            for path in paths.iter().filter(|path| !path.is_unserved()) {
 
                 let edges = &path.edges;
 
@@ -995,47 +1063,904 @@ mod tests {
         let mut model = Model::load_from_file();
         let groups = Group::load_from_file();
 
+        let cost_model = CostModel::default();
         let mut groups_with_at_least_one_path: Vec<Group> = groups.clone().into_iter().filter(|g| !g.paths.is_empty()).collect();
 
-        let selection_state = simulated_annealing::simulated_annealing(&mut model.graph, &groups_with_at_least_one_path, "eval/simulated_annealing_test", 15000);
-        validate_cost_metrics_state(&mut model.graph, &selection_state);
+        let selection_state = simulated_annealing::simulated_annealing(&mut model.graph, &cost_model, &groups_with_at_least_one_path, "eval/simulated_annealing_test", &AnnealingParameters::with_iterations(15000), rand::random());
+        validate_cost_metrics_state(&model.graph, &selection_state);
 
         let mut groups_cloned = groups_with_at_least_one_path.clone();
-        let selection_state = simulated_annealing_on_path::simulated_annealing(&mut model.graph, &mut groups_cloned, selection_state, "eval/simulated_annealing_on_path_test", 500);
-        validate_cost_metrics_state(&mut model.graph, &selection_state);
+        let selection_state = simulated_annealing_on_path::simulated_annealing(&model.graph, &model.inaccessible_stations, &mut groups_cloned, selection_state, "eval/simulated_annealing_on_path_test", &sample_parameters_on_path(500), rand::random());
+        validate_cost_metrics_state(&model.graph, &selection_state);
 
         // let mut groups_with_at_least_one_path: Vec<Group> = groups.clone().into_iter().filter(|g| !g.paths.is_empty()).collect();
 
         // let selection_state = randomized_best::randomized_best(&mut model.graph, &groups_with_at_least_one_path, 1000, "eval/randomized_best_test");
-        // validate_cost_metrics_state(&mut model.graph, &selection_state);
+        // validate_cost_metrics_state(&model.graph, &selection_state);
 
         // let mut groups_with_at_least_one_path: Vec<Group> = groups.clone().into_iter().filter(|g| !g.paths.is_empty()).collect();
         // let selection_state = randomized_hillclimb::randomized_hillclimb(&mut model.graph, &groups_with_at_least_one_path, 2,  1000, "eval/randomized_hillclimb_test");
-        // validate_cost_metrics_state(&mut model.graph, &selection_state);
+        // validate_cost_metrics_state(&model.graph, &selection_state);
+    }
+
+    /// tests that incrementally evaluated neighbors have the same cost metrics as fully strained ones
+    #[test]
+    fn validate_incremental_cost_metrics() {
+        let model = Model::with_stations_trips_and_footpaths("sample_data").unwrap();
+        let cost_model = CostModel::default();
+        let groups = model.find_paths_for_groups("sample_data/groups.csv", &cost_model, &sample_path_search(), 1).unwrap();
+
+        let groups_with_at_least_one_path: Vec<Group> = groups.into_iter().filter(|g| !g.paths.is_empty()).collect();
+
+        let mut rng = rand::thread_rng();
+        let mut current = SelectionState::generate_random_state(&model.graph, &cost_model, &groups_with_at_least_one_path, &mut rng);
+        let mut context = EvaluationContext::new(&model.graph, &cost_model, &groups_with_at_least_one_path, &current.groups_path_index);
+        let initial = current.clone();
+        let mut moves = Vec::new();
+
+        for _ in 0..200 {
+            let group_move = current.random_group_move(&model.graph, &context, &mut rng, None);
+            current.apply_move(&model.graph, &mut context, &group_move);
+            moves.push(group_move);
+            validate_cost_metrics_state(&model.graph, &current);
+            assert!(context.strained_edges_cost() == current.strained_edges_cost, "Context edge cost are not equal!");
+        }
+
+        let reverted = current.revert_moves(&moves);
+        assert_eq!(reverted.groups_path_index, initial.groups_path_index, "Reverted selection is not equal to the initial one!");
+        assert_eq!(reverted.cost, initial.cost, "Reverted cost is not equal to the initial one!");
+
+        for neighbor in current.all_group_neighbors(&model.graph).iter().flatten() {
+            validate_cost_metrics_state(&model.graph, neighbor);
+        }
+    }
+
+    /// tests that the objective of the exported program equals the cost of path selections (with unit overflow segments)
+    #[test]
+    fn validate_mip_export() {
+        let mut model = Model::with_stations_trips_and_footpaths("sample_data").unwrap();
+
+        // reduce capacities, so that the sample groups overcrowd some trips
+        for edge in model.graph.edge_weights_mut() {
+            if let TimetableEdge::Trip {duration: _, capacity, seats: _, first_class_seats: _} = edge {
+                *capacity = 3;
+            }
+        }
+
+        let cost_model = CostModel::default();
+        let groups = model.find_paths_for_groups("sample_data/groups.csv", &cost_model, &sample_path_search(), 1).unwrap();
+        let groups_with_at_least_one_path: Vec<Group> = groups.into_iter().filter(|g| !g.paths.is_empty()).collect();
+
+        let program = PathSelectionProgram::from_groups(&model.graph, &cost_model, &groups_with_at_least_one_path, 1000);
+        assert!(program.exact, "Program is not exact!");
+        assert!(program.constraints.iter().any(|constraint| constraint.sense == Sense::LessEqual), "No capacity constraint!");
+
+        let mut rng = rand::thread_rng();
+        for _ in 0..50 {
+            let selection_state = SelectionState::generate_random_state(&model.graph, &cost_model, &groups_with_at_least_one_path, &mut rng);
+            assert_eq!(program_objective_value(&program, &selection_state.groups_path_index), selection_state.cost as f64, "Objective is not equal to cost!");
+        }
+
+        let lp_filepath = std::env::temp_dir().join("mcfp_path_selection.lp");
+        let mps_filepath = std::env::temp_dir().join("mcfp_path_selection.mps");
+        program.save_lp_to_file(lp_filepath.to_str().unwrap());
+        program.save_mps_to_file(mps_filepath.to_str().unwrap());
+
+        let lp = std::fs::read_to_string(lp_filepath).unwrap();
+        assert!(lp.contains("Subject To\n assign_0: + 1 x_0_0") && lp.trim_end().ends_with("End"));
+
+        let mps = std::fs::read_to_string(mps_filepath).unwrap();
+        assert!(mps.contains(" E assign_0\n") && mps.contains(" BV bnd x_0_0\n") && mps.trim_end().ends_with("ENDATA"));
+
+        // one widened segment per edge with a penalty whose slope over the segment is fractional (e.g. (1 + 4) / 2 passengers)
+        let cost_model = CostModel {
+            overload_penalty: OverloadPenalty::Piecewise { segments: vec![PenaltySegment { from: 0, slope: 1 }, PenaltySegment { from: 1, slope: 4 }] },
+            ..CostModel::default()
+        };
+        let program = PathSelectionProgram::from_groups(&model.graph, &cost_model, &groups_with_at_least_one_path, 1);
+        assert!(!program.exact, "Program with widened segments is exact!");
+
+        // the penalty is exact at the breakpoints, i.e. at the maximum overflow of each edge
+        for constraint in program.constraints.iter().filter(|constraint| constraint.sense == Sense::LessEqual) {
+            let edge = &model.graph[EdgeIndex::new(constraint.name["cap_".len()..].parse().unwrap())];
+            let segments: Vec<&Variable> = constraint.terms.iter().filter(|(_, coefficient)| *coefficient < 0).map(|(variable_index, _)| &program.variables[*variable_index]).collect();
+
+            let max_overflow: u64 = segments.iter().map(|segment| segment.upper_bound.unwrap()).sum();
+            let penalty: f64 = segments.iter().map(|segment| segment.objective * segment.upper_bound.unwrap() as f64).sum();
+            assert!((penalty - edge.utilization_cost_with(&cost_model, constraint.rhs as u64 + max_overflow) as f64).abs() < 1e-6, "Penalty of {} is not exact at its maximum overflow!", constraint.name);
+        }
+
+        // ... and overestimated in between
+        for _ in 0..50 {
+            let selection_state = SelectionState::generate_random_state(&model.graph, &cost_model, &groups_with_at_least_one_path, &mut rng);
+            assert!(program_objective_value(&program, &selection_state.groups_path_index) >= selection_state.cost as f64 - 1e-6, "Objective underestimates the cost!");
+        }
     }
 
-    fn validate_cost_metrics_state(graph: &mut DiGraph<TimetableNode, TimetableEdge>, selection_state: &SelectionState) {
-        let mut strained_edges: HashSet<EdgeIndex> = HashSet::new();
+    /// tests that the lagrangian lower bound is below the cost of the found selections and that the new paths are valid
+    #[test]
+    fn validate_lagrangian_relaxation() {
+        let mut model = Model::with_stations_trips_and_footpaths("sample_data").unwrap();
+
+        // reduce capacities, so that the sample groups overcrowd some trips
+        for edge in model.graph.edge_weights_mut() {
+            if let TimetableEdge::Trip {duration: _, capacity, seats: _, first_class_seats: _} = edge {
+                *capacity = 3;
+            }
+        }
+
+        let cost_model = CostModel::default();
+        let groups = model.find_paths_for_groups("sample_data/groups.csv", &cost_model, &sample_path_search(), 1).unwrap();
+        let mut groups_with_at_least_one_path: Vec<Group> = groups.into_iter().filter(|g| !g.paths.is_empty()).collect();
+        let n_paths_before: Vec<usize> = groups_with_at_least_one_path.iter().map(|g| g.paths.len()).collect();
+
+        let filepath = std::env::temp_dir().join("mcfp_lagrangian_relaxation");
+        let filepath = filepath.to_str().unwrap();
+        let selection_state = lagrangian_relaxation::lagrangian_relaxation(&model.graph, &model.inaccessible_stations, &cost_model, &mut groups_with_at_least_one_path, filepath, 100);
+        validate_cost_metrics_state(&model.graph, &selection_state);
 
-        // first: strain all selected paths to TimetableGraph
+        // runtime CSV: runtime,time,lower_bound,upper_bound
+        let runtime = std::fs::read_to_string(format!("{}_runtime.csv", filepath)).unwrap();
+        let lower_bound: f64 = runtime.lines().nth(1).unwrap().split(',').nth(2).unwrap().parse().unwrap();
+
+        assert!(lower_bound <= selection_state.cost as f64, "Lower bound is greater than cost of selection!");
+        for neighbor in selection_state.all_group_neighbors(&model.graph).iter().flatten() {
+            assert!(lower_bound <= neighbor.cost as f64, "Lower bound is greater than cost of neighbor!");
+        }
+
+        let mut rng = rand::thread_rng();        for _ in 0..100 {
+            let random_state = SelectionState::generate_random_state(&model.graph, &cost_model, selection_state.groups, &mut rng);
+            assert!(lower_bound <= random_state.cost as f64, "Lower bound is greater than cost of random state!");
+        }
+
+        // appended paths start at the group's start node and end at its destination station
+        for (group, n_paths) in selection_state.groups.iter().zip(n_paths_before.iter()) {
+            let start = model.graph.edge_endpoints(group.paths[0].edges[0]).unwrap().0;
+            for path in group.paths[*n_paths..].iter() {
+                assert!(model.graph.edge_endpoints(path.edges[0]).unwrap().0 == start, "Path does not start at start node!");
+                let destination = model.graph.edge_endpoints(*path.edges.last().unwrap()).unwrap().1;
+                assert!(model.graph[destination].station_id() == group.destination_station_id, "Path does not end at destination station!");
+            }
+        }
+    }
+
+    /// tests incremental cost metrics, MIP objective and lagrangian lower bound with a non-default cost model
+    #[test]
+    fn validate_cost_model() {
+        let mut model = Model::with_stations_trips_and_footpaths("sample_data").unwrap();
+
+        // reduce capacities, so that the sample groups overcrowd some trips
+        for edge in model.graph.edge_weights_mut() {
+            if let TimetableEdge::Trip {duration: _, capacity, seats: _, first_class_seats: _} = edge {
+                *capacity = 3;
+            }
+        }
+
+        let cost_model = CostModel {
+            trip: EdgeKindWeight { fixed: 1, per_minute: 2 },
+            walk: EdgeKindWeight { fixed: 0, per_minute: 3 },
+            transfer_penalty: 20,
+            delay: DelayWeight { early: 0, late: 5 },
+            overload_penalty: OverloadPenalty::Piecewise {
+                segments: vec![PenaltySegment { from: 0, slope: 2 }, PenaltySegment { from: 2, slope: 10 }],
+            },
+            ..CostModel::default()
+        };
+
+        let groups = model.find_paths_for_groups("sample_data/groups.csv", &cost_model, &sample_path_search(), 1).unwrap();
+        let mut groups_with_at_least_one_path: Vec<Group> = groups.into_iter().filter(|g| !g.paths.is_empty()).collect();
+
+        let mut rng = rand::thread_rng();
+        let mut current = SelectionState::generate_random_state(&model.graph, &cost_model, &groups_with_at_least_one_path, &mut rng);
+        let mut context = EvaluationContext::new(&model.graph, &cost_model, &groups_with_at_least_one_path, &current.groups_path_index);
+
+        for _ in 0..200 {
+            let group_move = current.random_group_move(&model.graph, &context, &mut rng, None);
+            current.apply_move(&model.graph, &mut context, &group_move);
+            validate_cost_metrics_state(&model.graph, &current);
+            assert!(context.strained_edges_cost() == current.strained_edges_cost, "Context edge cost are not equal!");
+        }
+
+        let program = PathSelectionProgram::from_groups(&model.graph, &cost_model, &groups_with_at_least_one_path, 1000);
+        for _ in 0..50 {
+            let selection_state = SelectionState::generate_random_state(&model.graph, &cost_model, &groups_with_at_least_one_path, &mut rng);
+            assert_eq!(program_objective_value(&program, &selection_state.groups_path_index), selection_state.cost as f64, "Objective is not equal to cost!");
+        }
+
+        let filepath = std::env::temp_dir().join("mcfp_lagrangian_relaxation_cost_model");
+        let filepath = filepath.to_str().unwrap();
+        let selection_state = lagrangian_relaxation::lagrangian_relaxation(&model.graph, &model.inaccessible_stations, &cost_model, &mut groups_with_at_least_one_path, filepath, 100);
+        validate_cost_metrics_state(&model.graph, &selection_state);
+
+        let runtime = std::fs::read_to_string(format!("{}_runtime.csv", filepath)).unwrap();
+        let lower_bound: f64 = runtime.lines().nth(1).unwrap().split(',').nth(2).unwrap().parse().unwrap();
+        assert!(lower_bound <= selection_state.cost as f64, "Lower bound is greater than cost of selection!");
+        for _ in 0..100 {
+            let random_state = SelectionState::generate_random_state(&model.graph, &cost_model, selection_state.groups, &mut rng);
+            assert!(lower_bound <= random_state.cost as f64, "Lower bound is greater than cost of random state!");
+        }
+    }
+
+    /// tests that parallel simulated annealing is deterministic for a fixed seed and returns a valid state
+    #[test]
+    fn validate_parallel_simulated_annealing() {
+        let mut model = Model::with_stations_trips_and_footpaths("sample_data").unwrap();
+
+        // reduce capacities, so that the sample groups overcrowd some trips
+        for edge in model.graph.edge_weights_mut() {
+            if let TimetableEdge::Trip {duration: _, capacity, seats: _, first_class_seats: _} = edge {
+                *capacity = 3;
+            }
+        }
+
+        let cost_model = CostModel::default();
+        let groups = model.find_paths_for_groups("sample_data/groups.csv", &cost_model, &sample_path_search(), 1).unwrap();
+        let groups_with_at_least_one_path: Vec<Group> = groups.into_iter().filter(|g| !g.paths.is_empty()).collect();
+
+        let initial = SelectionState::generate_state_with_best_path_per_group(&model.graph, &cost_model, &groups_with_at_least_one_path);
+
+        for exchange_interval in [0, 25] {
+            let filepath = std::env::temp_dir().join(format!("mcfp_parallel_simulated_annealing_{}", exchange_interval));
+            let filepath = filepath.to_str().unwrap();
+
+            let first = parallel_simulated_annealing(&model.graph, &cost_model, &groups_with_at_least_one_path, filepath, &AnnealingParameters::with_iterations(300), 4, exchange_interval, 42);
+            let second = parallel_simulated_annealing(&model.graph, &cost_model, &groups_with_at_least_one_path, filepath, &AnnealingParameters::with_iterations(300), 4, exchange_interval, 42);
+
+            validate_cost_metrics_state(&model.graph, &first);
+            assert_eq!(first.groups_path_index, second.groups_path_index, "Selections of equal seeds are not equal!");
+            assert_eq!(first.cost, second.cost, "Cost of equal seeds are not equal!");
+            assert!(first.cost <= initial.cost, "Best state is worse than initial state!");
+
+            // each chain writes one row per iteration
+            let rows = std::fs::read_to_string(format!("{}.csv", filepath)).unwrap();
+            assert_eq!(rows.lines().count(), 1 + 300 * 4, "Number of CSV rows is not equal to iterations of all chains!");
+            assert!(rows.lines().skip(1).all(|row| row.split(',').nth(3).unwrap().parse::<i64>().unwrap() >= first.cost), "Some chain visited a state better than the returned one!");
+        }
+    }
+
+    /// tests that runs of the randomized optimization algorithms with equal seeds are equal
+    #[test]
+    fn validate_seeded_optimization() {
+        let mut model = Model::with_stations_trips_and_footpaths("sample_data").unwrap();
+
+        // reduce capacities, so that the sample groups overcrowd some trips
+        for edge in model.graph.edge_weights_mut() {
+            if let TimetableEdge::Trip {duration: _, capacity, seats: _, first_class_seats: _} = edge {
+                *capacity = 3;
+            }
+        }
+
+        let cost_model = CostModel::default();
+        let groups = model.find_paths_for_groups("sample_data/groups.csv", &cost_model, &sample_path_search(), 1).unwrap();
+        let groups_with_at_least_one_path: Vec<Group> = groups.into_iter().filter(|g| !g.paths.is_empty()).collect();
+
+        // returns the selection and the written CSV rows of a run with seed
+        let run = |seed: u64| {
+            let filepath = std::env::temp_dir().join(format!("mcfp_seeded_{}", seed));
+            let filepath = filepath.to_str().unwrap();
+
+            let selection_state = simulated_annealing::simulated_annealing(&model.graph, &cost_model, &groups_with_at_least_one_path, filepath, &AnnealingParameters::with_iterations(500), seed);
+            let mut groups_cloned = groups_with_at_least_one_path.clone();
+            let selection_state = simulated_annealing_on_path::simulated_annealing(&model.graph, &model.inaccessible_stations, &mut groups_cloned, selection_state, &format!("{}_on_path", filepath), &sample_parameters_on_path(50), seed);
+            let groups_path_index = selection_state.groups_path_index.clone();
+
+            let best = randomized_best::randomized_best(&model.graph, &cost_model, &groups_with_at_least_one_path, 200, &format!("{}_randomized_best", filepath), seed);
+            let hillclimb = randomized_hillclimb::randomized_hillclimb(&model.graph, &cost_model, &groups_with_at_least_one_path, 2, 5, &format!("{}_randomized_hillclimb", filepath), seed);
+
+            let rows = std::fs::read_to_string(format!("{}.csv", filepath)).unwrap()
+                + &std::fs::read_to_string(format!("{}_on_path.csv", filepath)).unwrap();
+            let runtime = std::fs::read_to_string(format!("{}_runtime.csv", filepath)).unwrap();
+            assert_eq!(runtime.lines().nth(1).unwrap().split(',').nth(2).unwrap(), seed.to_string(), "Seed is not recorded in runtime CSV!");
+
+            (groups_path_index, best.groups_path_index, hillclimb.groups_path_index, rows)
+        };
+
+        assert_eq!(run(7), run(7), "Runs with equal seeds are not equal!");
+    }
+
+    /// tests the temperatures of the cooling schedules and the stopping criteria of simulated annealing
+    #[test]
+    fn validate_cooling_schedules() {
+        let kinds = [ScheduleKind::Hyperbolic, ScheduleKind::Geometric, ScheduleKind::Linear, ScheduleKind::Logarithmic, ScheduleKind::Adaptive];
+
+        for kind in kinds.iter() {
+            let parameters = AnnealingParameters {
+                schedule: *kind,
+                initial_temperature: 1000.0,
+                final_temperature: 1.0,
+                ..AnnealingParameters::with_iterations(1000)
+            };
+
+            let mut schedule = parameters.build_schedule();
+            assert!((schedule.temperature() - 1000.0).abs() < 1e-9, "{} does not start at the initial temperature!", kind);
+
+            let mut previous = schedule.temperature();
+            for time in 1..1000 {
+                schedule.cool(false, false);
+                assert!(schedule.temperature() > 0.0, "{} is not positive!", kind);
+
+                // the adaptive schedule raises the temperature if too few neighbors are accepted
+                if *kind != ScheduleKind::Adaptive {
+                    assert!(schedule.temperature() <= previous, "{} increases at time {}!", kind, time);
+                }
+                previous = schedule.temperature();
+            }
+
+            // temperature of iteration 1000
+            match kind {
+                ScheduleKind::Hyperbolic | ScheduleKind::Geometric | ScheduleKind::Linear => {
+                    assert!((schedule.temperature() - 1.0).abs() < 1e-6, "{} does not reach the final temperature!", kind)
+                }
+                _ => assert!(schedule.temperature() > 1.0, "{} reaches the final temperature!", kind),
+            }
+
+            schedule.reset();
+            assert!((schedule.temperature() - 1000.0).abs() < 1e-9, "{} does not reset to the initial temperature!", kind);
+        }
+
+        // reheating restarts the schedule at half the temperature after the window
+        let parameters = AnnealingParameters {
+            schedule: ScheduleKind::Geometric,
+            reheat_window: Some(10),
+            ..AnnealingParameters::with_iterations(100)
+        };
+        let mut schedule = parameters.build_schedule();
+        for _ in 0..10 {
+            schedule.cool(false, false);
+        }
+        assert!((schedule.temperature() - 50.0).abs() < 1e-9, "Reheating does not restart at half the initial temperature!");
+
+        // simulated annealing stops after the no-improvement window
+        let model = Model::with_stations_trips_and_footpaths("sample_data").unwrap();
+        let cost_model = CostModel::default();
+        let groups = model.find_paths_for_groups("sample_data/groups.csv", &cost_model, &sample_path_search(), 1).unwrap();
+        let groups_with_at_least_one_path: Vec<Group> = groups.into_iter().filter(|g| !g.paths.is_empty()).collect();
+
+        let parameters = AnnealingParameters {
+            schedule: ScheduleKind::Linear,
+            stopping: StoppingCriteria {
+                max_iterations: 100000,
+                max_duration: None,
+                no_improvement_window: Some(20),
+            },
+            ..AnnealingParameters::with_iterations(100000)
+        };
+
+        let filepath = std::env::temp_dir().join("mcfp_no_improvement_window");
+        let filepath = filepath.to_str().unwrap();
+        let selection_state = simulated_annealing::simulated_annealing(&model.graph, &cost_model, &groups_with_at_least_one_path, filepath, &parameters, 3);
+        validate_cost_metrics_state(&model.graph, &selection_state);
+
+        let rows = std::fs::read_to_string(format!("{}.csv", filepath)).unwrap();
+        assert!(rows.starts_with("time,temperature,"), "CSV does not start with its header!");
+
+        let runtime = std::fs::read_to_string(format!("{}_runtime.csv", filepath)).unwrap();
+        assert!(runtime.starts_with("runtime,time,seed,stopped_by,schedule,"), "Parameters are not recorded in runtime CSV header!");
+        let result: Vec<&str> = runtime.lines().nth(1).unwrap().split(',').collect();
+        assert_eq!(result.len(), runtime.lines().next().unwrap().split(',').count(), "Runtime CSV row does not match its header!");
+        assert_eq!(result[4], "linear", "Parameters are not recorded in runtime CSV!");
+        assert_eq!(result[3], "no_improvement_window", "Simulated annealing is not stopped by the no-improvement window!");
+        assert!(result[1].parse::<u64>().unwrap() < 100000, "Simulated annealing did not stop early!");
+    }
+
+    /// tests that both simulated annealing variants and randomized hillclimb return their best visited state
+    #[test]
+    fn validate_incumbent() {
+        let mut model = Model::with_stations_trips_and_footpaths("sample_data").unwrap();
+
+        // reduce capacities, so that the sample groups overcrowd some trips
+        for edge in model.graph.edge_weights_mut() {
+            if let TimetableEdge::Trip {duration: _, capacity, seats: _, first_class_seats: _} = edge {
+                *capacity = 3;
+            }
+        }
+
+        let cost_model = CostModel::default();
+        let groups = model.find_paths_for_groups("sample_data/groups.csv", &cost_model, &sample_path_search(), 1).unwrap();
+        let groups_with_at_least_one_path: Vec<Group> = groups.into_iter().filter(|g| !g.paths.is_empty()).collect();
+
+        // returns (minimum of cost column, last value of best_cost column) of an output CSV
+        let csv_costs = |filepath: &str, cost_column: usize| {
+            let rows = std::fs::read_to_string(filepath).unwrap();
+            let rows: Vec<Vec<i64>> = rows
+                .lines()
+                .skip(1)
+                .map(|row| row.split(',').map(|value| value.trim().parse::<f64>().unwrap() as i64).collect())
+                .collect();
+            let min_cost = rows.iter().map(|row| row[cost_column]).min().unwrap();
+            (min_cost, *rows.last().unwrap().last().unwrap())
+        };
+
+        // hot schedule, so that many worse neighbors are accepted until the end
+        let parameters = AnnealingParameters {
+            schedule: ScheduleKind::Linear,
+            initial_temperature: 100.0,
+            final_temperature: 50.0,
+            ..AnnealingParameters::with_iterations(1000)
+        };
+
+        let filepath = std::env::temp_dir().join("mcfp_incumbent");
+        let filepath = filepath.to_str().unwrap();
+
+        let selection_state = simulated_annealing::simulated_annealing(&model.graph, &cost_model, &groups_with_at_least_one_path, filepath, &parameters, 5);
+        validate_cost_metrics_state(&model.graph, &selection_state);
+        assert_eq!(csv_costs(&format!("{}.csv", filepath), 2), (selection_state.cost, selection_state.cost), "Simulated annealing does not return its best state!");
+
+        let mut groups_cloned = groups_with_at_least_one_path.clone();
+        let selection_state = simulated_annealing_on_path::simulated_annealing(&model.graph, &model.inaccessible_stations, &mut groups_cloned, selection_state, &format!("{}_on_path", filepath), &sample_parameters_on_path(100), 5);
+        validate_cost_metrics_state(&model.graph, &selection_state);
+        validate_groups_paths_integrity_state(&model, &selection_state);
+        assert_eq!(csv_costs(&format!("{}_on_path.csv", filepath), 2), (selection_state.cost, selection_state.cost), "Simulated annealing on path does not return its best state!");
+
+        let selection_state = randomized_hillclimb::randomized_hillclimb(&model.graph, &cost_model, &groups_with_at_least_one_path, 3, 10, &format!("{}_hillclimb", filepath), 5);
+        validate_cost_metrics_state(&model.graph, &selection_state);
+        assert_eq!(csv_costs(&format!("{}_hillclimb.csv", filepath), 2), (selection_state.cost, selection_state.cost), "Randomized hillclimb does not return its best state!");
+    }
+
+    /// tests that tabu search is deterministic and not worse than the initial state and hillclimb from it
+    #[test]
+    fn validate_tabu_search() {
+        let mut model = Model::with_stations_trips_and_footpaths("sample_data").unwrap();
+
+        // reduce capacities, so that the sample groups overcrowd some trips
+        for edge in model.graph.edge_weights_mut() {
+            if let TimetableEdge::Trip {duration: _, capacity, seats: _, first_class_seats: _} = edge {
+                *capacity = 3;
+            }
+        }
+
+        let cost_model = CostModel::default();
+        let groups = model.find_paths_for_groups("sample_data/groups.csv", &cost_model, &sample_path_search(), 1).unwrap();
+        let groups_with_at_least_one_path: Vec<Group> = groups.into_iter().filter(|g| !g.paths.is_empty()).collect();
+
+        let initial = SelectionState::generate_state_with_best_path_per_group(&model.graph, &cost_model, &groups_with_at_least_one_path);
+
+        // first improvement of the initial state (the first move of tabu search without tabu moves)
+        let first_move = initial.all_group_neighbors(&model.graph).into_iter().flatten().min_by_key(|neighbor| neighbor.cost).unwrap();
+
+        for neighborhood in [Neighborhood::All, Neighborhood::Direct] {
+            let parameters = TabuParameters {
+                n_iterations: 200,
+                tabu_tenure: 5,
+                diversification_window: 30,
+                neighborhood,
+                initial_state: InitialState::BestPath,
+            };
+
+            let filepath = std::env::temp_dir().join(format!("mcfp_tabu_search_{:?}", neighborhood));
+            let filepath = filepath.to_str().unwrap();
+
+            let first = tabu_search::tabu_search(&model.graph, &cost_model, &groups_with_at_least_one_path, filepath, &parameters);
+            let rows = std::fs::read_to_string(format!("{}.csv", filepath)).unwrap();
+            let second = tabu_search::tabu_search(&model.graph, &cost_model, &groups_with_at_least_one_path, filepath, &parameters);
+
+            validate_cost_metrics_state(&model.graph, &first);
+            assert_eq!(first.groups_path_index, second.groups_path_index, "Tabu search is not deterministic!");
+            assert_eq!(rows, std::fs::read_to_string(format!("{}.csv", filepath)).unwrap(), "Tabu search is not deterministic!");
+            assert!(first.cost <= initial.cost, "Tabu search is worse than its initial state!");
+
+            if neighborhood == Neighborhood::All {
+                assert!(first.cost <= first_move.cost, "Tabu search is worse than its first move!");
+            }
+
+            let best_costs: Vec<i64> = rows.lines().skip(1).map(|row| row.split(',').nth(5).unwrap().parse().unwrap()).collect();
+            assert!(best_costs.windows(2).all(|costs| costs[1] <= costs[0]), "Best cost increases!");
+            assert!(*best_costs.last().unwrap() >= first.cost, "Best cost column is lower than the returned state!");
+        }
+    }
+
+    /// tests that the greedy initial state assigns each group its cheapest path regarding the groups assigned before
+    #[test]
+    fn validate_greedy_initial_state() {
+        let mut model = Model::with_stations_trips_and_footpaths("sample_data").unwrap();
+
+        // reduce capacities, so that the sample groups overcrowd some trips
+        for edge in model.graph.edge_weights_mut() {
+            if let TimetableEdge::Trip {duration: _, capacity, seats: _, first_class_seats: _} = edge {
+                *capacity = 3;
+            }
+        }
+
+        let cost_model = CostModel::default();
+        let groups = model.find_paths_for_groups("sample_data/groups.csv", &cost_model, &sample_path_search(), 1).unwrap();
+        let groups_with_at_least_one_path: Vec<Group> = groups.into_iter().filter(|g| !g.paths.is_empty()).collect();
+
+        let best_path = SelectionState::generate_state_with_best_path_per_group(&model.graph, &cost_model, &groups_with_at_least_one_path);
+
+        for order in [GroupOrder::Passengers, GroupOrder::Departure, GroupOrder::Slack] {
+            let greedy = SelectionState::generate_initial_state(&model.graph, &cost_model, &groups_with_at_least_one_path, &InitialState::Greedy(order));
+            validate_cost_metrics_state(&model.graph, &greedy);
+            assert!(greedy.cost <= best_path.cost, "Greedy initial state ({}) is worse than the best path of each group!", order);
+
+            // the group assigned last selects its cheapest path regarding all other groups
+            let last = match order {
+                GroupOrder::Passengers => (0..groups_with_at_least_one_path.len()).rev().min_by_key(|g| groups_with_at_least_one_path[*g].passengers).unwrap(),
+                GroupOrder::Departure => (0..groups_with_at_least_one_path.len()).max_by_key(|g| groups_with_at_least_one_path[*g].departure_time).unwrap(),
+                GroupOrder::Slack => continue,
+            };
+
+            let mut context = EvaluationContext::new(&model.graph, &cost_model, &groups_with_at_least_one_path, &greedy.groups_path_index);
+            let paths = &groups_with_at_least_one_path[last].paths;
+            context.relieve_path(&model.graph, &paths[greedy.groups_path_index[last]]);
+
+            let selected_cost = paths[greedy.groups_path_index[last]].cost() + context.strain_path_cost_delta(&model.graph, &paths[greedy.groups_path_index[last]]);
+            for path in paths.iter() {
+                assert!(selected_cost <= path.cost() + context.strain_path_cost_delta(&model.graph, path), "Last group does not select its cheapest path!");
+            }
+        }
+
+        // the optimizers start with the greedy state
+        let parameters = AnnealingParameters {
+            initial_state: InitialState::Greedy(GroupOrder::Passengers),
+            ..AnnealingParameters::with_iterations(0)
+        };
+
+        let filepath = std::env::temp_dir().join("mcfp_greedy_initial_state");
+        let filepath = filepath.to_str().unwrap();
+
+        let selection_state = simulated_annealing::simulated_annealing(&model.graph, &cost_model, &groups_with_at_least_one_path, filepath, &parameters, 5);
+        let greedy = SelectionState::generate_state_with_greedy_capacity(&model.graph, &cost_model, &groups_with_at_least_one_path, GroupOrder::Passengers);
+        assert_eq!(selection_state.groups_path_index, greedy.groups_path_index, "Simulated annealing does not start with the greedy state!");
+
+        // warm start with a given selection (e.g. after a disruption)
+        let groups_path_index: Vec<usize> = groups_with_at_least_one_path.iter().map(|g| g.paths.len() - 1).collect();
+        let parameters = AnnealingParameters {
+            initial_state: InitialState::Selection(groups_path_index.clone()),
+            ..AnnealingParameters::with_iterations(0)
+        };
+
+        let selection_state = simulated_annealing::simulated_annealing(&model.graph, &cost_model, &groups_with_at_least_one_path, filepath, &parameters, 5);
+        validate_cost_metrics_state(&model.graph, &selection_state);
+        assert_eq!(selection_state.groups_path_index, groups_path_index, "Simulated annealing does not start with the given selection!");
+
+        // with enough seats the greedy state strains no trip, so simulated annealing on path has nothing to detour
+        for edge in model.graph.edge_weights_mut() {
+            if let TimetableEdge::Trip {duration: _, capacity, seats, first_class_seats: _} = edge {
+                *capacity = 1000;
+                *seats = 1000;
+            }
+        }
+
+        let greedy = SelectionState::generate_initial_state(&model.graph, &cost_model, &groups_with_at_least_one_path, &InitialState::Greedy(GroupOrder::Passengers));
+        let greedy_groups_path_index = greedy.groups_path_index.clone();
+        let mut groups_cloned = groups_with_at_least_one_path.clone();
+        let selection_state = simulated_annealing_on_path::simulated_annealing(&model.graph, &model.inaccessible_stations, &mut groups_cloned, greedy, filepath, &sample_parameters_on_path(50), 5);
+        assert_eq!(selection_state.groups_path_index, greedy_groups_path_index);
+
+        let runtime = std::fs::read_to_string(format!("{}_runtime.csv", filepath)).unwrap();
+        assert!(runtime.lines().nth(1).unwrap().split(',').nth(3) == Some("no_overcrowded_edges"), "Simulated annealing on path does not stop without overcrowded edges!");
+    }
+
+    /// tests that the hard capacity mode distributes all passengers without exceeding capacities
+    #[test]
+    fn validate_split_assignment() {
+        let mut model = Model::with_stations_trips_and_footpaths("sample_data").unwrap();
+
+        // reduce capacities, so that the sample groups overcrowd some trips
+        for edge in model.graph.edge_weights_mut() {
+            if let TimetableEdge::Trip {duration: _, capacity, seats: _, first_class_seats: _} = edge {
+                *capacity = 3;
+            }
+        }
+
+        let cost_model = CostModel::default();
+        let groups = model.find_paths_for_groups("sample_data/groups.csv", &cost_model, &sample_path_search(), 1).unwrap();
+        let groups_with_at_least_one_path: Vec<Group> = groups.into_iter().filter(|g| !g.paths.is_empty()).collect();
+
+        // unsplit shares have the cost of the path selection
+        let best_path = SelectionState::generate_state_with_best_path_per_group(&model.graph, &cost_model, &groups_with_at_least_one_path);
+        let shares = groups_with_at_least_one_path
+            .iter()
+            .map(|group| (0..group.paths.len()).map(|path_index| if path_index == 0 { group.passengers } else { 0 }).collect())
+            .collect();
+        let unsplit = SelectionState::from_groups_path_shares(&model.graph, &cost_model, &groups_with_at_least_one_path, shares);
+        assert_eq!(unsplit.cost, best_path.cost, "Cost of unsplit shares is not equal to cost of the path selection!");
+        assert_eq!(unsplit.groups_path_index, best_path.groups_path_index, "Selected paths of unsplit shares are not equal!");
+
+        let filepath = std::env::temp_dir().join("mcfp_split_assignment");
+        let filepath = filepath.to_str().unwrap();
+        let selection_state = split_assignment::split_assignment(&model.graph, &cost_model, &groups_with_at_least_one_path, filepath, GroupOrder::Passengers);
+        let groups_path_shares = selection_state.groups_path_shares.as_ref().unwrap();
+
+        let mut utilization = vec![0; model.graph.edge_count()];
+        for (group, path_shares) in groups_with_at_least_one_path.iter().zip(groups_path_shares.iter()) {
+            assert_eq!(path_shares.iter().sum::<u64>(), group.passengers, "Shares do not add up to the passengers of the group!");
+
+            for (path, passengers) in group.paths.iter().zip(path_shares.iter()) {
+                for edge in path.edges.iter() {
+                    utilization[edge.index()] += passengers;
+                }
+            }
+        }
+
+        // runtime CSV: runtime,passes,split_groups,overflow
+        let runtime = std::fs::read_to_string(format!("{}_runtime.csv", filepath)).unwrap();
+        let overflow: u64 = runtime.lines().nth(1).unwrap().split(',').nth(3).unwrap().parse().unwrap();
+        let expected_overflow: u64 = model.graph.edge_indices()
+            .filter(|edge| model.graph[*edge].is_trip())
+            .map(|edge| utilization[edge.index()].saturating_sub(model.graph[edge].capacity()))
+            .sum();
+        assert_eq!(overflow, expected_overflow, "Overflow is not equal to the utilization over capacity!");
+        assert_eq!(selection_state.cost, selection_state.strained_edges_cost + selection_state.travel_cost + selection_state.travel_delay_cost, "Total cost are not equal!");
+
+        // the passengers on the best paths exceed the capacities more often
+        let best_path_context = EvaluationContext::new(&model.graph, &cost_model, &groups_with_at_least_one_path, &best_path.groups_path_index);
+        assert!(overflow <= best_path_context.overflow(&model.graph), "Overflow is greater than the one of the best path of each group!");
+
+        // the groups CSV lists each share
+        let groups_filepath = format!("{}_groups.csv", filepath);
+        selection_state.save_groups_to_csv(&model.graph, &groups_filepath);
+        let rows = std::fs::read_to_string(&groups_filepath).unwrap();
+        let n_shares = groups_path_shares.iter().flatten().filter(|passengers| **passengers > 0).count();
+        assert_eq!(rows.lines().count(), 1 + n_shares, "Groups CSV does not list each share!");
+        assert_eq!(
+            rows.lines().skip(1).map(|row| row.split('|').nth(1).unwrap().parse::<u64>().unwrap()).sum::<u64>(),
+            groups_with_at_least_one_path.iter().map(|group| group.passengers).sum::<u64>(),
+            "Passengers of the groups CSV do not add up!"
+        );
+
+        // with enough capacity (but less than all passengers) no trip is overcrowded
+        for edge in model.graph.edge_weights_mut() {
+            if let TimetableEdge::Trip {duration: _, capacity, seats, first_class_seats: _} = edge {
+                *capacity = 10;
+                *seats = 10;
+            }
+        }
+
+        let selection_state = split_assignment::split_assignment(&model.graph, &cost_model, &groups_with_at_least_one_path, filepath, GroupOrder::Passengers);
+        let context = EvaluationContext::with_shares(&model.graph, &cost_model, &groups_with_at_least_one_path, selection_state.groups_path_shares.as_ref().unwrap());
+        assert_eq!(context.overflow(&model.graph), 0, "Trips are overcrowded in hard capacity mode!");
+        assert_eq!(selection_state.strained_edges_cost, 0, "Edge cost without overflow is not zero!");
+    }
+
+    /// tests that large neighborhood search is deterministic for a seed and not worse than its initial state
+    #[test]
+    fn validate_large_neighborhood_search() {
+        let mut model = Model::with_stations_trips_and_footpaths("sample_data").unwrap();
+
+        // reduce capacities, so that the sample groups overcrowd some trips
+        for edge in model.graph.edge_weights_mut() {
+            if let TimetableEdge::Trip {duration: _, capacity, seats: _, first_class_seats: _} = edge {
+                *capacity = 3;
+            }
+        }
+
+        let cost_model = CostModel::default();
+        let groups = model.find_paths_for_groups("sample_data/groups.csv", &cost_model, &sample_path_search(), 1).unwrap();
+        let groups_with_at_least_one_path: Vec<Group> = groups.into_iter().filter(|g| !g.paths.is_empty()).collect();
+
+        let initial = SelectionState::generate_state_with_best_path_per_group(&model.graph, &cost_model, &groups_with_at_least_one_path);
+
+        let parameters = LnsParameters {
+            n_iterations: 200,
+            n_trips: 3,
+            time_window: 30,
+            segment_length: 20,
+            reaction_factor: 0.1,
+            initial_state: InitialState::BestPath,
+        };
+
+        let filepath = std::env::temp_dir().join("mcfp_large_neighborhood_search");
+        let filepath = filepath.to_str().unwrap();
+
+        let mut first_groups = groups_with_at_least_one_path.clone();
+        let first = large_neighborhood_search::large_neighborhood_search(&model.graph, &model.inaccessible_stations, &cost_model, &mut first_groups, filepath, &parameters, 5);
+        let rows = std::fs::read_to_string(format!("{}.csv", filepath)).unwrap();
+
+        validate_cost_metrics_state(&model.graph, &first);
+        validate_groups_paths_integrity_state(&model, &first);
+        assert!(first.cost <= initial.cost, "Large neighborhood search is worse than its initial state!");
+
+        let best_costs: Vec<i64> = rows.lines().skip(1).map(|row| row.split(',').nth(6).unwrap().parse().unwrap()).collect();
+        assert_eq!(*best_costs.last().unwrap(), first.cost, "Large neighborhood search does not return its best state!");
+
+        let first_groups_path_index = first.groups_path_index.clone();
+
+        let mut second_groups = groups_with_at_least_one_path.clone();
+        let second = large_neighborhood_search::large_neighborhood_search(&model.graph, &model.inaccessible_stations, &cost_model, &mut second_groups, filepath, &parameters, 5);
+        assert_eq!(first_groups_path_index, second.groups_path_index, "Large neighborhood search is not deterministic!");
+        assert_eq!(rows, std::fs::read_to_string(format!("{}.csv", filepath)).unwrap(), "Large neighborhood search is not deterministic!");
+    }
+
+    /// tests that the optimizers may leave groups on the virtual "no service" path and price it with the unserved_penalty
+    #[test]
+    fn validate_unserved_path() {
+        let mut model = Model::with_stations_trips_and_footpaths("sample_data").unwrap();
+
+        // reduce capacities, so that the sample groups overcrowd some trips
+        for edge in model.graph.edge_weights_mut() {
+            if let TimetableEdge::Trip {duration: _, capacity, seats: _, first_class_seats: _} = edge {
+                *capacity = 3;
+            }
+        }
+
+        let mut cost_model = CostModel::default();
+        let groups = model.find_paths_for_groups("sample_data/groups.csv", &cost_model, &sample_path_search(), 1).unwrap();
+        let groups_with_at_least_one_path: Vec<Group> = groups.into_iter().filter(|g| !g.paths.is_empty()).collect();
+
+        let filepath = std::env::temp_dir().join("mcfp_unserved_path");
+        let filepath = filepath.to_str().unwrap();
+
+        // free unserved path -> nobody is transported
+        cost_model.unserved_penalty = Some(0);
+        let mut groups = groups_with_at_least_one_path.clone();
+        groups.iter_mut().for_each(|g| g.add_unserved_path(&cost_model));
+
+        let greedy = SelectionState::generate_initial_state(&model.graph, &cost_model, &groups, &InitialState::Greedy(GroupOrder::Passengers));
+        validate_cost_metrics_state(&model.graph, &greedy);
+        // only paths arriving early enough to get a negative delay cost are cheaper than the unserved path
+        let unserved: Vec<usize> = (0..groups.len()).filter(|g| groups[*g].paths[greedy.groups_path_index[*g]].is_unserved()).collect();
+        for (group_index, group) in groups.iter().enumerate() {
+            if group.paths.iter().all(|path| path.is_unserved() || path.cost() > 0) {
+                assert!(unserved.contains(&group_index), "Group is transported although not transporting it is free!");
+            }
+        }
+
+        // the unserved path neither transfers, walks, waits nor rides
+        let unserved_path = groups[0].paths.iter().find(|path| path.is_unserved()).unwrap();
+        assert_eq!(
+            [unserved_path.get_transfers(&model.graph), unserved_path.get_walks(&model.graph), unserved_path.get_walking_time(&model.graph), unserved_path.get_waiting_time(&model.graph), unserved_path.get_in_trip_time(&model.graph)],
+            [0; 5]
+        );
+        assert!(unserved_path.to_location_time_and_type(&model.graph).is_empty());
+
+        greedy.save_groups_to_csv(&model.graph, filepath);
+        let rows = std::fs::read_to_string(filepath).unwrap();
+        assert_eq!(rows.lines().filter(|row| row.ends_with("|unserved")).count(), unserved.len(), "Unserved groups are not saved as unserved!");
+
+        // small penalty -> the optimizers leave some groups untransported instead of overcrowding trips
+        cost_model.unserved_penalty = Some(20);
+        let mut groups = groups_with_at_least_one_path.clone();
+        groups.iter_mut().for_each(|g| g.add_unserved_path(&cost_model));
+
+        let best_path = SelectionState::generate_state_with_best_path_per_group(&model.graph, &cost_model, &groups_with_at_least_one_path);
+
+        let parameters = TabuParameters {
+            n_iterations: 100,
+            tabu_tenure: 5,
+            diversification_window: 0,
+            neighborhood: Neighborhood::All,
+            initial_state: InitialState::BestPath,
+        };
+        let tabu = tabu_search::tabu_search(&model.graph, &cost_model, &groups, filepath, &parameters);
+        validate_cost_metrics_state(&model.graph, &tabu);
+        validate_groups_paths_integrity_state(&model, &tabu);
+        assert!(tabu.cost < best_path.cost, "Unserved path does not lower the cost!");
+        assert!((0..groups.len()).any(|g| groups[g].paths[tabu.groups_path_index[g]].is_unserved()), "No group is unserved!");
+
+        let mut lagrangian_groups = groups.clone();
+        let lagrangian = lagrangian_relaxation::lagrangian_relaxation(&model.graph, &model.inaccessible_stations, &cost_model, &mut lagrangian_groups, filepath, 50);
+        validate_cost_metrics_state(&model.graph, &lagrangian);
+        validate_groups_paths_integrity_state(&model, &lagrangian);
+
+        let lns_parameters = LnsParameters {
+            n_iterations: 50,
+            n_trips: 3,
+            time_window: 30,
+            segment_length: 20,
+            reaction_factor: 0.1,
+            initial_state: InitialState::Greedy(GroupOrder::Passengers),
+        };
+        let mut lns_groups = groups.clone();
+        let lns = large_neighborhood_search::large_neighborhood_search(&model.graph, &model.inaccessible_stations, &cost_model, &mut lns_groups, filepath, &lns_parameters, 5);
+        validate_cost_metrics_state(&model.graph, &lns);
+        validate_groups_paths_integrity_state(&model, &lns);
+    }
+
+    /// returns the default parameters of simulated annealing on path of the CLI
+    /// tests that a detour violating the constraints of the group is skipped and the returned detour is a connected path
+    #[test]
+    fn validate_detour_constraints() {
+        let folder_path = std::env::temp_dir().join("mcfp_detour_constraints");
+        std::fs::create_dir_all(&folder_path).unwrap();
+        std::fs::write(folder_path.join("stations.csv"), "id,transfer,name\n1,1,A\n2,1,B\n3,1,C\n4,1,D\n6,1,F\n").unwrap();
+        std::fs::write(folder_path.join("trips.csv"), "id,from_station,departure,to_station,arrival,capacity\n\
+            1,1,10,2,20,5\n2,3,30,4,40,5\n2,4,41,6,100,5\n10,4,45,6,102,5\n11,4,47,6,104,5\n\
+            12,1,11,6,101,5\n13,1,12,6,102,5\n14,1,13,6,103,5\n15,1,14,6,105,5\n16,6,50,1,60,5\n").unwrap();
+        std::fs::write(folder_path.join("footpaths.csv"), "from_station,to_station,duration\n2,3,3\n4,6,1\n").unwrap();
+        std::fs::write(folder_path.join("groups.csv"), "id,start,departure,destination,arrival,passengers,in_trip,max_walking\n1,1,0,6,100,1,,3\n").unwrap();
+
+        let folder_path = folder_path.to_str().unwrap();
+        let model = Model::with_stations_trips_and_footpaths(folder_path).unwrap();
+        let cost_model = CostModel::default();
+        let path_search = PathSearch::Iddfs { search_budgets: vec![500], min_paths: 1 };
+        let mut groups = model.find_paths_for_groups(&format!("{}/groups.csv", folder_path), &cost_model, &path_search, 1).unwrap();
+
+        // the path walking from 2 to 3 and taking trip 2 to the destination, overcrowded on its last edge
+        let trip_ids = |edges: &[EdgeIndex]| -> Vec<u64> {
+            edges.iter().filter(|edge| model.graph[**edge].is_trip()).map(|edge| model.graph[model.graph.edge_endpoints(*edge).unwrap().0].trip_id().unwrap()).collect()
+        };
+        let path_index = groups[0].paths.iter().position(|path| trip_ids(&path.edges.iter().copied().collect::<Vec<EdgeIndex>>()) == vec![1, 2, 2]).unwrap();
+        let path = groups[0].paths[path_index].clone();
+        let edge = *path.edges.last().unwrap();
+
+        // the cheapest detour from the arrival at 4 walks to 6, exceeding max_walking along with the walk from 2 to 3
+        let groups_cloned = groups.clone();
+        let selection_state = SelectionState::from_groups_path_index(&model.graph, &cost_model, &groups_cloned, vec![path_index]);
+        let (_, detour) = selection_state.find_detour_for_random_group(&model.graph, &model.inaccessible_stations, &mut groups, vec![0], edge, &mut StdRng::seed_from_u64(0));
+        let detour = detour.expect("No detour found!");
+        let detour_edges: Vec<EdgeIndex> = detour.edges.iter().copied().collect();
+
+        for edge_pair in detour_edges.windows(2) {
+            assert_eq!(model.graph.edge_endpoints(edge_pair[0]).unwrap().1, model.graph.edge_endpoints(edge_pair[1]).unwrap().0, "Detour is not connected!");
+        }
+        assert_eq!(model.graph.edge_endpoints(detour_edges[0]).unwrap().0, model.graph.edge_endpoints(path.edges[0]).unwrap().0, "Detour does not start at the start of the group!");
+        assert_eq!(model.graph[model.graph.edge_endpoints(*detour_edges.last().unwrap()).unwrap().1].station_id(), 6, "Detour does not reach the destination!");
+        assert!(groups[0].admits_path(&model.graph, &model.inaccessible_stations, &detour_edges), "Detour violates the constraints of the group!");
+    }
+
+    fn sample_parameters_on_path(n_iterations: u64) -> AnnealingParameters {
+        AnnealingParameters {
+            initial_temperature: 50.0 * n_iterations as f64,
+            final_temperature: 50.0,
+            ..AnnealingParameters::with_iterations(n_iterations)
+        }
+    }
+
+    /// returns the default IDDFS path search of the CLI
+    fn sample_path_search() -> PathSearch {
+        PathSearch::Iddfs {
+            search_budgets: vec![30, 35, 40, 45, 50, 55, 60],
+            min_paths: 50,
+        }
+    }
+
+    /// returns the objective value of the program for the given path selection (overflow segments are filled cheapest first)
+    fn program_objective_value(program: &PathSelectionProgram, groups_path_index: &[usize]) -> f64 {
+        let mut values = vec![0; program.variables.len()];
+
+        // set the binaries of the selected paths
+        for (group_index, constraint) in program.constraints.iter().filter(|constraint| constraint.sense == Sense::Equal).enumerate() {
+            values[constraint.terms[groups_path_index[group_index]].0] = 1;
+        }
+
+        // fill the overflow segments of each capacity constraint in order (slopes are increasing)
+        for constraint in program.constraints.iter().filter(|constraint| constraint.sense == Sense::LessEqual) {
+            let utilization: i64 = constraint
+                .terms
+                .iter()
+                .filter(|(_, coefficient)| *coefficient > 0)
+                .map(|(variable_index, coefficient)| values[*variable_index] * coefficient)
+                .sum();
+
+            let mut overflow = (utilization - constraint.rhs).max(0);
+            for (variable_index, coefficient) in constraint.terms.iter() {
+                if *coefficient < 0 {
+                    let segment_value = overflow.min(program.variables[*variable_index].upper_bound.unwrap() as i64);
+                    values[*variable_index] = segment_value;
+                    overflow -= segment_value;
+                }
+            }
+        }
+
+        program.variables.iter().zip(values.iter()).map(|(variable, value)| variable.objective * *value as f64).sum()
+    }
+
+    fn validate_cost_metrics_state(graph: &DiGraph<TimetableNode, TimetableEdge>, selection_state: &SelectionState) {
+        // first: sum up the (first class) utilization of all selected paths per edge, independent of the EvaluationContext
+        let mut edges_utilization: HashMap<EdgeIndex, (u64, u64)> = HashMap::new();
         for (group_index, selected_path_index) in selection_state.groups_path_index.iter().enumerate() {
             let path = &selection_state.groups[group_index].paths[*selected_path_index];
-            path.strain_to_graph(graph, &mut strained_edges);
+            for edge in path.edges.iter() {
+                let (utilization, first_class_utilization) = edges_utilization.entry(*edge).or_insert((0, 0));
+                *utilization += path.utilization();
+                *first_class_utilization += path.first_class_utilization();
+            }
         }
 
-        let strained_edges_cost =
-            SelectionState::calculate_cost_of_strained_edges(graph, &strained_edges) as i64;
+        // second: sum up the utilization cost of all utilized edges
+        let strained_edges_cost: i64 = edges_utilization
+            .iter()
+            .map(|(edge, (utilization, first_class_utilization))| {
+                graph[*edge].utilization_cost_by_class(selection_state.cost_model, *utilization, *first_class_utilization) as i64
+            })
+            .sum();
 
         let travel_cost = SelectionState::calculate_total_travel_cost_paths(selection_state.groups, &selection_state.groups_path_index);
         let travel_delay_cost =
             SelectionState::calculate_total_travel_delay_cost_paths(selection_state.groups, &selection_state.groups_path_index);
         let cost = strained_edges_cost + travel_cost + travel_delay_cost;
 
-        // third: relieve all selected paths from TimetableGraph
-        for (group_index, path_index) in selection_state.groups_path_index.iter().enumerate() {
-            selection_state.groups[group_index].paths[*path_index].relieve_from_graph(graph, &mut strained_edges);
-        }
-
         assert!(strained_edges_cost == selection_state.strained_edges_cost, "Edge cost are not equal!");
         assert!(travel_cost == selection_state.travel_cost, "Travel cost are not equal!");
         assert!(travel_delay_cost == selection_state.travel_delay_cost, "Delay cost are not equal!");
@@ -0,0 +1,263 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    time::Instant,
+};
+
+use crossbeam_utils::thread;
+use petgraph::graph::DiGraph;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use super::{
+    cooling_schedule::{AnnealingParameters, CoolingSchedule},
+    evaluation::EvaluationContext,
+    GroupMove, SelectionState,
+};
+use crate::model::{
+    cost_model::CostModel,
+    graph_weight::{TimetableEdge, TimetableNode},
+    group::Group,
+};
+
+/// maximum number of iterations of a round, the CSV rows the chains buffered during a round are written after it
+/// (even if the chains are independent or exchange less often)
+const FLUSH_INTERVAL: u64 = 10_000;
+
+/// single chain of parallel_simulated_annealing()
+///
+/// owns its selection and utilization (the graph is shared immutable between all chains)
+struct Chain<'a> {
+    current: SelectionState<'a>,
+    context: EvaluationContext, // keeps the utilization of current's selected paths
+    best: SelectionState<'a>,                 // best state this chain has visited (only up to date if moves_since_best is None)
+    best_cost: i64,                           // cost of the best state this chain has visited
+    moves_since_best: Option<Vec<GroupMove>>, // if set, the best state is current with these moves reverted
+    rng: StdRng,
+    schedule: Box<dyn CoolingSchedule>,
+    temperature_factor: f64, // multiplies the temperature of the schedule (1 = coldest chain)
+    time_of_last_improvement: u64,
+    stopped_by: Option<&'static str>, // set as soon as the chain is frozen or a stopping criterion is met
+    rows: Vec<String>,                // CSV rows of the current round
+}
+
+impl<'a> Chain<'a> {
+
+    /// returns the current temperature of this chain
+    fn temperature(&self) -> f64 {
+        self.temperature_factor * self.schedule.temperature()
+    }
+
+    /// returns the best state this chain has visited
+    fn best(&self) -> SelectionState<'a> {
+        match &self.moves_since_best {
+            Some(moves_since_best) => self.current.revert_moves(moves_since_best),
+            None => self.best.clone(),
+        }
+    }
+
+    /// performs the iterations from time `from` up to (excluding) `to`, stops early if the chain is stopped
+    fn anneal(&mut self, graph: &DiGraph<TimetableNode, TimetableEdge>, chain_index: usize, from: u64, to: u64, parameters: &AnnealingParameters, start_instant: Instant) {
+        for time in from..to {
+            let temperature = self.temperature();
+
+            self.stopped_by = if temperature < self.temperature_factor * parameters.final_temperature {
+                Some("final_temperature")
+            } else if parameters.movable_groups.as_ref().is_some_and(|movable_groups| movable_groups.is_empty()) {
+                Some("no_movable_groups")
+            } else {
+                parameters.stopping.met(time, start_instant, self.time_of_last_improvement)
+            };
+
+            if self.stopped_by.is_some() {
+                return;
+            }
+
+            let group_move = self.current.random_group_move(graph, &self.context, &mut self.rng, parameters.movable_groups.as_deref());
+
+            // positive if the neighbor is better than current
+            let delta_cost = -group_move.cost_delta();
+
+            let accepted = delta_cost > 0 || self.rng.gen_range(0.0..1.0) < (delta_cost as f64 / temperature).exp();
+            if accepted {
+                self.current.apply_move(graph, &mut self.context, &group_move);
+                if let Some(moves_since_best) = &mut self.moves_since_best {
+                    moves_since_best.push(group_move);
+                }
+            }
+
+            let improved = self.current.cost < self.best_cost;
+            if improved {
+                self.best_cost = self.current.cost;
+                self.moves_since_best = Some(Vec::new());
+                self.time_of_last_improvement = time;
+            }
+
+            self.schedule.cool(accepted, improved);
+
+            self.rows.push(format!(
+                "{},{},{},{},{},{},{},{}\n",
+                time,
+                chain_index,
+                temperature,
+                self.current.cost,
+                self.current.strained_edges_cost,
+                self.current.travel_cost,
+                self.current.travel_delay_cost,
+                self.best_cost
+            ));
+        }
+    }
+}
+
+/// runs n_chains simulated annealing chains in parallel (one thread per chain), returns the best state of all chains
+///
+/// all chains start with the initial state of parameters, their random number generators are derived from seed,
+/// so the result only depends on the seed (not on the thread scheduling)
+///
+/// if exchange_interval is zero, the chains are independent and share the cooling schedule of parameters,
+/// otherwise the temperature of chain k is multiplied by 2^k (parallel tempering) and every exchange_interval iterations
+/// the states of neighboring chains are swapped with probability min(1, exp((1/T_k - 1/T_k+1) * (cost_k - cost_k+1)))
+///
+/// a chain stops as soon as it is frozen or a stopping criterion of parameters is met, the search stops if all chains stopped
+pub fn parallel_simulated_annealing<'a>(
+    graph: &DiGraph<TimetableNode, TimetableEdge>,
+    cost_model: &'a CostModel,
+    groups: &'a Vec<Group>,
+    filepath: &str,
+    parameters: &AnnealingParameters,
+    n_chains: usize,
+    exchange_interval: u64,
+    seed: u64,
+) -> SelectionState<'a> {
+    println!(
+        "parallel_simulated_annealing(n_chains={}, exchange_interval={}, seed={})",
+        n_chains, exchange_interval, seed
+    );
+
+    let mut writer = BufWriter::new(
+        File::create(format!("{}.{}", filepath, "csv"))
+            .unwrap_or_else(|_| panic!("Could not create file \"{}.csv\"", filepath)),
+    );
+
+    writeln!(writer, "time,chain,temperature,cost,edge_cost,travel_cost,delay_cost,best_cost").unwrap();
+
+    let mut r_writer = BufWriter::new(
+        File::create(format!("{}_{}.{}", filepath, "runtime", "csv"))
+            .unwrap_or_else(|_| panic!("Could not create file \"{}_runtime.csv\"", filepath)),
+    );
+
+    writeln!(r_writer, "runtime,time,chains,exchanges,best_chain,seed,stopped_by,{}", AnnealingParameters::CSV_HEADER).unwrap();
+
+    let start_instant = Instant::now();
+
+    // decides the exchanges and seeds the chains
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let initial = SelectionState::generate_initial_state(graph, cost_model, groups, &parameters.initial_state);
+    let initial_context = EvaluationContext::new(graph, cost_model, groups, &initial.groups_path_index);
+
+    let mut chains: Vec<Chain> = (0..n_chains.max(1))
+        .map(|chain_index| Chain {
+            current: initial.clone(),
+            context: initial_context.clone(),
+            best: initial.clone(),
+            best_cost: initial.cost,
+            moves_since_best: Some(Vec::new()),
+            rng: StdRng::seed_from_u64(rng.gen()),
+            schedule: parameters.build_schedule(),
+            temperature_factor: if exchange_interval == 0 { 1.0 } else { 2f64.powi(chain_index as i32) },
+            time_of_last_improvement: 0,
+            stopped_by: None,
+            rows: Vec::new(),
+        })
+        .collect();
+
+    // the chains run in parallel for a round of iterations, exchanges are performed between two rounds
+    // (every exchange_interval iterations, rounds are split to write the CSV rows at least every FLUSH_INTERVAL iterations)
+    let n_iterations = parameters.stopping.max_iterations;
+    let mut n_exchanges = 0;
+    let mut time = 1;
+
+    while time <= n_iterations && chains.iter().any(|chain| chain.stopped_by.is_none()) {
+        let mut round_end = (time + FLUSH_INTERVAL).min(n_iterations + 1);
+        if exchange_interval != 0 {
+            round_end = round_end.min(time + exchange_interval - (time - 1) % exchange_interval);
+        }
+
+        thread::scope(|s| {
+            for (chain_index, chain) in chains.iter_mut().enumerate() {
+                s.spawn(move |_| chain.anneal(graph, chain_index, time, round_end, parameters, start_instant));
+            }
+        })
+        .unwrap();
+
+        for chain in chains.iter_mut() {
+            for row in chain.rows.drain(..) {
+                writer.write_all(row.as_bytes()).unwrap();
+            }
+        }
+
+        time = round_end;
+
+        if exchange_interval != 0 && (time - 1) % exchange_interval == 0 && time <= n_iterations {
+            for chain_index in 0..chains.len() - 1 {
+                let (colder, hotter) = (&chains[chain_index], &chains[chain_index + 1]);
+                if colder.stopped_by.is_some() || hotter.stopped_by.is_some() {
+                    continue;
+                }
+
+                let beta_difference = 1.0 / colder.temperature() - 1.0 / hotter.temperature();
+                let probability = (beta_difference * (colder.current.cost - hotter.current.cost) as f64).exp();
+
+                if rng.gen_range(0.0..1.0) < probability {
+                    let (left, right) = chains.split_at_mut(chain_index + 1);
+                    let (colder, hotter) = (&mut left[chain_index], &mut right[0]);
+
+                    // the best states stay with the chains, so they are no longer derived from current
+                    for chain in [&mut *colder, &mut *hotter].iter_mut() {
+                        chain.best = chain.best();
+                        chain.moves_since_best = None;
+                    }
+
+                    std::mem::swap(&mut colder.current, &mut hotter.current);
+                    std::mem::swap(&mut colder.context, &mut hotter.context);
+                    n_exchanges += 1;
+                }
+            }
+        }
+
+        let best_cost = chains.iter().map(|chain| chain.best_cost).min().unwrap();
+        println!("[time={}]: best_cost={}, exchanges={}", time - 1, best_cost, n_exchanges);
+    }
+
+    // best state of all chains (first chain on ties)
+    let (best_chain, best, stopped_by) = chains
+        .into_iter()
+        .enumerate()
+        .min_by_key(|(_, chain)| chain.best_cost)
+        .map(|(chain_index, chain)| (chain_index, chain.best(), chain.stopped_by.unwrap_or("max_iterations")))
+        .unwrap();
+
+    println!(
+        "best_cost={} of chain {} (done in {}s)",
+        best.cost,
+        best_chain,
+        start_instant.elapsed().as_secs()
+    );
+
+    writeln!(
+        r_writer,
+        "{}s,{},{},{},{},{},{},{}",
+        start_instant.elapsed().as_secs(),
+        time - 1,
+        n_chains,
+        n_exchanges,
+        best_chain,
+        seed,
+        stopped_by,
+        parameters
+    )
+    .unwrap();
+
+    best
+}
@@ -2,18 +2,19 @@ use std::{fs::File, io::{BufWriter, Write}, time::Instant};
 
 use colored::Colorize;
 use petgraph::graph::DiGraph;
+use rand::{rngs::StdRng, SeedableRng};
 
-use super::SelectionState;
-use crate::model::{graph_weight::{TimetableEdge, TimetableNode}, group::Group};
+use super::{evaluation::EvaluationContext, SelectionState};
+use crate::model::{cost_model::CostModel, graph_weight::{TimetableEdge, TimetableNode}, group::Group};
 
 /// in each iteration generate a random state
 ///
 /// if new state is better than current -> replace current with new
-pub fn randomized_best<'a>(graph: &mut DiGraph<TimetableNode, TimetableEdge>, groups: &'a Vec<Group>, iterations: u64, filepath: &str) -> SelectionState<'a> {
+pub fn randomized_best<'a>(graph: &DiGraph<TimetableNode, TimetableEdge>, cost_model: &'a CostModel, groups: &'a Vec<Group>, iterations: u64, filepath: &str, seed: u64) -> SelectionState<'a> {
 
-    println!("randomized_best()");
+    println!("randomized_best(seed={})", seed);
 
-    let mut rng = rand::thread_rng();
+    let mut rng = StdRng::seed_from_u64(seed);
 
     let mut writer = BufWriter::new(
         File::create(format!("{}.{}", filepath, "csv")).expect(&format!("Could not create file \"{}.csv\"", filepath))
@@ -28,13 +29,14 @@ pub fn randomized_best<'a>(graph: &mut DiGraph<TimetableNode, TimetableEdge>, gr
     );
 
     r_writer
-        .write("runtime,time\n".as_bytes())
+        .write("runtime,time,seed\n".as_bytes())
         .unwrap();
 
     let start_instant = Instant::now();
 
     // let mut current = SelectionState::generate_random_state(graph, groups)
-    let mut current = SelectionState::generate_state_with_best_path_per_group(graph, groups);
+    let mut current = SelectionState::generate_state_with_best_path_per_group(graph, cost_model, groups);
+    let mut context = EvaluationContext::new(graph, cost_model, groups, &current.groups_path_index);
 
     for time in 0..iterations {        
         print!(
@@ -67,9 +69,10 @@ pub fn randomized_best<'a>(graph: &mut DiGraph<TimetableNode, TimetableEdge>, gr
             r_writer
             .write(
                 format!(
-                    "{}s,{}\n",
+                    "{}s,{},{}\n",
                     start_instant.elapsed().as_secs(),
-                    time
+                    time,
+                    seed
                 )
                 .as_bytes(),
             )
@@ -78,10 +81,10 @@ pub fn randomized_best<'a>(graph: &mut DiGraph<TimetableNode, TimetableEdge>, gr
             return current;
         }
 
-        let next = current.group_neighbor(graph, &mut rng, None, None);
+        let group_move = current.random_group_move(graph, &context, &mut rng, None);
 
-        if  next.cost < current.cost {
-            current = next;
+        if group_move.cost_delta() < 0 {
+            current.apply_move(graph, &mut context, &group_move);
             println!("{}", format!("-> replacing current state").green());
         } else {
             println!("-> keep current")
@@ -94,9 +97,10 @@ pub fn randomized_best<'a>(graph: &mut DiGraph<TimetableNode, TimetableEdge>, gr
     r_writer
     .write(
         format!(
-            "{}s,{}\n",
+            "{}s,{},{}\n",
             start_instant.elapsed().as_secs(),
-            iterations
+            iterations,
+            seed
         )
         .as_bytes(),
     )
@@ -1,8 +1,10 @@
 use std::{fs::File, io::{BufWriter, Write}, time::Instant};
 
 use petgraph::graph::DiGraph;
+use rand::{rngs::StdRng, SeedableRng};
 
 use crate::model::{
+    cost_model::CostModel,
     group::Group,
     graph_weight::{TimetableEdge, TimetableNode},
 };
@@ -12,25 +14,29 @@ use super::SelectionState;
 /// random-restart hillclimb implementation
 ///
 /// algorithm starts with `n_restarts` random SelectionStates
-/// and chooses the best neighbor in each iteration
+/// and chooses the best neighbor in each iteration, returns the best local minimum of all restarts
 pub fn randomized_hillclimb<'a>(
-    graph: &mut DiGraph<TimetableNode, TimetableEdge>,
+    graph: &DiGraph<TimetableNode, TimetableEdge>,
+    cost_model: &'a CostModel,
     groups: &'a Vec<Group>,
     n_restarts: u64,       // number of "parallel" hill-climb searches
     max_n_iterations: u64, // number of iterations to improve result
     filepath: &str,
+    seed: u64,
 ) -> SelectionState<'a> {
     println!(
-        "randomized_hillclimb(n_runs={}, n_iterations={})",
-        n_restarts, max_n_iterations
+        "randomized_hillclimb(n_runs={}, n_iterations={}, seed={})",
+        n_restarts, max_n_iterations, seed
     );
 
+    let mut rng = StdRng::seed_from_u64(seed);
+
     let mut writer = BufWriter::new(
         File::create(format!("{}.{}", filepath, "csv")).expect(&format!("Could not create file \"{}.csv\"", filepath))
     );
 
     writer
-        .write("run,iteration,cost,edge_cost,travel_cost,delay_cost\n".as_bytes())
+        .write("run,iteration,cost,edge_cost,travel_cost,delay_cost,best_cost\n".as_bytes())
         .unwrap();
 
     let mut r_writer = BufWriter::new(
@@ -38,17 +44,17 @@ pub fn randomized_hillclimb<'a>(
     );
 
     r_writer
-        .write("runtime,runs,iterations\n".as_bytes())
+        .write("runtime,runs,iterations,seed\n".as_bytes())
         .unwrap();
 
     let start_instant = Instant::now();
 
-    // from each parallel state save the resulting local maximum as (cost, state)
-    let mut local_minima: Vec<SelectionState> = Vec::with_capacity(n_restarts as usize);
+    // best local minimum of all finished restarts (incumbent)
+    let mut best: Option<SelectionState> = None;
 
     for run in 0..n_restarts {
         // choose random configuration as initial state
-        let mut local_minimum = SelectionState::generate_random_state(graph, groups);
+        let mut local_minimum = SelectionState::generate_random_state(graph, cost_model, groups, &mut rng);
 
         println!(
             "[restart={}/{}]: initial_cost={}, edge_cost={}, travel_cost={}, delay_cost={}",
@@ -63,13 +69,14 @@ pub fn randomized_hillclimb<'a>(
         writer
             .write(
                 format!(
-                    "{}, {},{},{},{},{}\n",
+                    "{}, {},{},{},{},{},{}\n",
                     run + 1,
                     n_restarts,
                     local_minimum.cost,
                     local_minimum.strained_edges_cost,
                     local_minimum.travel_cost,
                     local_minimum.travel_delay_cost,
+                    best.as_ref().map_or(local_minimum.cost, |best| best.cost.min(local_minimum.cost)),
                 )
                 .as_bytes(),
             )
@@ -95,13 +102,14 @@ pub fn randomized_hillclimb<'a>(
                 writer
                 .write(
                     format!(
-                        "{}, {},{},{},{},{}\n",
+                        "{}, {},{},{},{},{},{}\n",
                         run + 1,
                         n_restarts,
                         local_minimum.cost,
                         local_minimum.strained_edges_cost,
                         local_minimum.travel_cost,
                         local_minimum.travel_delay_cost,
+                        best.as_ref().map_or(local_minimum.cost, |best| best.cost.min(local_minimum.cost)),
                     )
                     .as_bytes(),
                 )
@@ -111,6 +119,9 @@ pub fn randomized_hillclimb<'a>(
                 break;
             }
 
+            // set as new local minimum
+            local_minimum = best_neighbor;
+
             println!(
                 "\t[iteration={}]: cost={}, edge_cost={}, travel_cost={}, delay_cost={}",
                 j + 1,
@@ -122,45 +133,42 @@ pub fn randomized_hillclimb<'a>(
             writer
                 .write(
                     format!(
-                        "{}, {},{},{},{},{}\n",
+                        "{}, {},{},{},{},{},{}\n",
                         run + 1,
                         n_restarts,
                         local_minimum.cost,
                         local_minimum.strained_edges_cost,
                         local_minimum.travel_cost,
                         local_minimum.travel_delay_cost,
+                        best.as_ref().map_or(local_minimum.cost, |best| best.cost.min(local_minimum.cost)),
                     )
                     .as_bytes(),
                 )
                 .unwrap();
-
-            // set as new local minimum
-            local_minimum = best_neighbor
         }
 
-        local_minima.push(local_minimum);
+        if best.as_ref().is_none_or(|best| local_minimum.cost < best.cost) {
+            best = Some(local_minimum);
+        }
     }
 
-    local_minima.sort_unstable_by_key(|s| s.cost);
-    println!("lowest local minimum: {:?}", local_minima[0].cost);
-
-
-    // move miminum to end of vec and pop this element
-    local_minima.reverse();
+    let best = best.expect("randomized_hillclimb needs at least one restart");
+    println!("lowest local minimum: {:?}", best.cost);
 
     r_writer
         .write(
             format!(
-                "{}s,{},{}\n",
+                "{}s,{},{},{}\n",
                 start_instant.elapsed().as_secs(),
                 n_restarts,
-                max_n_iterations
+                max_n_iterations,
+                seed
             )
             .as_bytes(),
         )
         .unwrap();
 
-    return local_minima.pop().unwrap()
+    best
 
     // // stores the index of the currently selected path in each group
     // let mut selected_groups: Vec<usize> = Vec::with_capacity(groups.len());
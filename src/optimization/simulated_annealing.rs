@@ -6,26 +6,35 @@ use std::{
 
 use colored::Colorize;
 use petgraph::graph::DiGraph;
-use rand::Rng;
+use rand::{rngs::StdRng, Rng, SeedableRng};
 
-use super::SelectionState;
+use super::{cooling_schedule::AnnealingParameters, evaluation::EvaluationContext, SelectionState};
 use crate::model::{
+    cost_model::CostModel,
     graph_weight::{TimetableEdge, TimetableNode},
     group::Group,
 };
 
 /// Implementation of Simulated Annealing Search
 ///
-/// Algorithm starts on SelectionState with all first paths selected, generates a random neighbor and uses cost-delta to decide whether to apply this state as next state
+/// Algorithm starts on the initial SelectionState of parameters (by default all first paths selected), generates a random neighbor and uses cost-delta to decide whether to apply this state as next state
+///
+/// the temperature follows the cooling schedule of parameters, the search stops as soon as the schedule is frozen or a stopping criterion is met
+///
+/// only the paths of the movable groups of parameters are changed (e.g. the groups affected by a realtime update)
+///
+/// returns the best state visited (which may be better than the last state, as worse neighbors are accepted)
 pub fn simulated_annealing<'a>(
-    graph: &mut DiGraph<TimetableNode, TimetableEdge>,
+    graph: &DiGraph<TimetableNode, TimetableEdge>,
+    cost_model: &'a CostModel,
     groups: &'a Vec<Group>,
     filepath: &str,
-    n_iterations: u64,
+    parameters: &AnnealingParameters,
+    seed: u64,
 ) -> SelectionState<'a> {
-    println!("simulated_annealing()");
+    println!("simulated_annealing(seed={})", seed);
 
-    let mut rng = rand::thread_rng();
+    let mut rng = StdRng::seed_from_u64(seed);
 
     let mut writer = BufWriter::new(
         File::create(format!("{}.{}", filepath, "csv"))
@@ -33,7 +42,7 @@ pub fn simulated_annealing<'a>(
     );
 
     writer
-        .write("time,temperature,cost,edge_cost,travel_cost,delay_cost\n".as_bytes())
+        .write("time,temperature,cost,edge_cost,travel_cost,delay_cost,best_cost\n".as_bytes())
         .unwrap();
 
     let mut r_writer = BufWriter::new(
@@ -43,16 +52,24 @@ pub fn simulated_annealing<'a>(
         )),
     );
 
-    r_writer.write("runtime,time\n".as_bytes()).unwrap();
+    r_writer.write(format!("runtime,time,seed,stopped_by,{}\n", AnnealingParameters::CSV_HEADER).as_bytes()).unwrap();
 
     //let mut current = SelectionState::generate_random_state(graph, groups);
-    let mut current = SelectionState::generate_state_with_best_path_per_group(graph, groups);
+    let mut current = SelectionState::generate_initial_state(graph, cost_model, groups, &parameters.initial_state);
     let mut time = 1;
 
+    let mut schedule = parameters.build_schedule();
+    let mut best_cost = current.cost; // cost of the incumbent
+    let mut moves_since_best = Vec::new(); // the incumbent is current with these moves reverted
+    let mut time_of_last_improvement = 0;
+
+    // keeps the utilization of current's selected paths, so each neighbor is evaluated incrementally
+    let mut context = EvaluationContext::new(graph, cost_model, groups, &current.groups_path_index);
+
     let start_instant = Instant::now();
 
     loop {
-        let temperature = n_iterations as f64 / time as f64; // time-to-temperature mapping
+        let temperature = schedule.temperature();
 
         print!(
             "[time={}]: cost={}, edge_cost={}, travel_cost={}, delay_cost={}, temp={:.2}, ",
@@ -66,41 +83,49 @@ pub fn simulated_annealing<'a>(
         writer
             .write(
                 format!(
-                    "{},{},{},{},{},{}\n",
+                    "{},{},{},{},{},{},{}\n",
                     time,
                     temperature,
                     current.cost,
                     current.strained_edges_cost,
                     current.travel_cost,
-                    current.travel_delay_cost
+                    current.travel_delay_cost,
+                    best_cost
                 )
                 .as_bytes(),
             )
             .unwrap();
 
-        // actually exactly zero, but difficult with float
-        if temperature < 1.0 {
-            print!("-> return");
+        let stopped_by = if temperature < parameters.final_temperature {
+            Some("final_temperature")
+        } else if parameters.movable_groups.as_ref().is_some_and(|movable_groups| movable_groups.is_empty()) {
+            Some("no_movable_groups")
+        } else {
+            parameters.stopping.met(time, start_instant, time_of_last_improvement)
+        };
+
+        if let Some(stopped_by) = stopped_by {
+            print!("-> return best_cost={} ({})", best_cost, stopped_by);
             println!(" (done in {}s)", start_instant.elapsed().as_secs());
 
             r_writer
-                .write(format!("{}s,{}\n", start_instant.elapsed().as_secs(), time).as_bytes())
+                .write(format!("{}s,{},{},{},{}\n", start_instant.elapsed().as_secs(), time, seed, stopped_by, parameters).as_bytes())
                 .unwrap();
 
-            return current;
+            return current.revert_moves(&moves_since_best);
         }
 
-        let next = current.group_neighbor(graph, &mut rng, None, None);
+        let group_move = current.random_group_move(graph, &context, &mut rng, parameters.movable_groups.as_deref());
 
         // if next_state is better than current_state -> delta positive
         // if next_state is worse than current_state -> delta negative
-        let delta_cost = current.cost as i64 - next.cost as i64;
+        let delta_cost = -group_move.cost_delta();
 
         print!("delta_cost={}, ", delta_cost);
 
-        if delta_cost > 0 {
-            current = next.clone();
+        let accepted = if delta_cost > 0 {
             println!("{}", format!("-> replacing current state").green());
+            true
         } else {
             let probability = (delta_cost as f64 / temperature as f64).exp();
             let random = rng.gen_range(0.0..1.0);
@@ -109,12 +134,26 @@ pub fn simulated_annealing<'a>(
 
             if random < probability {
                 println!("{}", format!("-> choosing worse neighbor").red());
-                current = next.clone();
+                true
             } else {
-                println!("-> skipping")
+                println!("-> skipping");
+                false
             }
+        };
+
+        if accepted {
+            current.apply_move(graph, &mut context, &group_move);
+            moves_since_best.push(group_move);
+        }
+
+        let improved = current.cost < best_cost;
+        if improved {
+            best_cost = current.cost;
+            moves_since_best.clear();
+            time_of_last_improvement = time;
         }
 
+        schedule.cool(accepted, improved);
         time += 1;
     }
 }
@@ -1,4 +1,5 @@
 use std::{
+    collections::HashSet,
     fs::File,
     io::{BufWriter, Write},
     time::Instant,
@@ -6,9 +7,9 @@ use std::{
 
 use colored::Colorize;
 use petgraph::graph::DiGraph;
-use rand::Rng;
+use rand::{rngs::StdRng, Rng, SeedableRng};
 
-use super::SelectionState;
+use super::{cooling_schedule::AnnealingParameters, evaluation::EvaluationContext, SelectionState};
 use crate::model::{
     graph_weight::{TimetableEdge, TimetableNode},
     group::Group,
@@ -18,23 +19,30 @@ use crate::model::{
 ///
 /// first selects a random overcrowded edge, second selects one of its occupying groups and 
 /// third changes the last part of the selected path of the group to detour the overcrowded edge
+///
+/// the temperature follows the cooling schedule of parameters, the search stops as soon as the schedule is frozen, a stopping criterion is met
+/// or no edge is overcrowded anymore
+///
+/// returns the best state visited (which may be better than the last state, as worse neighbors are accepted)
 pub fn simulated_annealing<'a>(
-    graph: &mut DiGraph<TimetableNode, TimetableEdge>,
+    graph: &DiGraph<TimetableNode, TimetableEdge>,
+    inaccessible_stations: &HashSet<u64>, // stations without step-free access (see `GroupConstraints::step_free`)
     groups: &'a mut Vec<Group>,
     state: SelectionState<'a>,
     filepath: &str,
-    n_iterations: u64
+    parameters: &AnnealingParameters,
+    seed: u64,
 ) -> SelectionState<'a> {
-    println!("simulated_annealing_on_path()");
+    println!("simulated_annealing_on_path(seed={})", seed);
 
-    let mut rng = rand::thread_rng();
+    let mut rng = StdRng::seed_from_u64(seed);
 
     let mut writer = BufWriter::new(
         File::create(format!("{}.{}", filepath, "csv")).expect(&format!("Could not create file \"{}.csv\"", filepath)),
     );
 
     writer
-        .write("time,temperature,cost,edge_cost,travel_cost,delay_cost\n".as_bytes())
+        .write("time,temperature,cost,edge_cost,travel_cost,delay_cost,best_cost\n".as_bytes())
         .unwrap();
 
     let mut r_writer = BufWriter::new(
@@ -42,17 +50,24 @@ pub fn simulated_annealing<'a>(
     );
 
     r_writer
-        .write("runtime,time\n".as_bytes())
+        .write(format!("runtime,time,seed,stopped_by,{}\n", AnnealingParameters::CSV_HEADER).as_bytes())
         .unwrap();
 
     let mut current_state = state;
     let mut time: u64 = 1;
 
+    let mut schedule = parameters.build_schedule();
+    let mut best = current_state.clone(); // incumbent
+    let mut time_of_last_improvement = 0;
+
+    // keeps the utilization of current_state's selected paths, so each neighbor is evaluated incrementally
+    let mut context = EvaluationContext::new(graph, current_state.cost_model, groups, &current_state.groups_path_index);
+
     let start_instant = Instant::now();
 
     loop {
         // get new temperature
-        let temperature = n_iterations as f64 / time as f64;
+        let temperature = schedule.temperature();
 
         print!(
             "[time={}]: cost={}, edge_cost={}, travel_cost={}, delay_cost={}, temp={:.2}, ",
@@ -66,29 +81,44 @@ pub fn simulated_annealing<'a>(
         writer
             .write(
                 format!(
-                    "{},{},{},{},{},{}\n",
+                    "{},{},{},{},{},{},{}\n",
                     time,
                     temperature,
                     current_state.cost,
                     current_state.strained_edges_cost,
                     current_state.travel_cost,
-                    current_state.travel_delay_cost
+                    current_state.travel_delay_cost,
+                    best.cost
                 )
                 .as_bytes(),
             )
             .unwrap();
 
-        // actually exactly zero, but difficult with float
-        if temperature < 1.0 {
-            print!("-> return");
+        // get one random overcrowded edge and its occupying groups by index
+        let overcrowded_edge = current_state.get_random_overcrowded_edge_with_groups(graph, &context, groups, &mut rng);
+
+        let stopped_by = if temperature < parameters.final_temperature {
+            Some("final_temperature")
+        } else if overcrowded_edge.is_none() {
+            // no detour can relieve a capacity-feasible selection
+            Some("no_overcrowded_edges")
+        } else {
+            parameters.stopping.met(time, start_instant, time_of_last_improvement)
+        };
+
+        if let Some(stopped_by) = stopped_by {
+            print!("-> return best_cost={} ({})", best.cost, stopped_by);
             println!(" (done in {}s)", start_instant.elapsed().as_secs());
 
             r_writer
             .write(
                 format!(
-                    "{}s,{}\n",
+                    "{}s,{},{},{},{}\n",
                     start_instant.elapsed().as_secs(),
-                    time
+                    time,
+                    seed,
+                    stopped_by,
+                    parameters
                 )
                 .as_bytes(),
             )
@@ -96,24 +126,24 @@ pub fn simulated_annealing<'a>(
             
             return SelectionState {
                 groups: groups,
-                cost: current_state.cost,
-                strained_edges_cost: current_state.strained_edges_cost,
-                travel_cost: current_state.travel_cost,
-                travel_delay_cost: current_state.travel_delay_cost,
-                groups_path_index: current_state.groups_path_index,
+                cost_model: best.cost_model,
+                cost: best.cost,
+                strained_edges_cost: best.strained_edges_cost,
+                travel_cost: best.travel_cost,
+                travel_delay_cost: best.travel_delay_cost,
+                groups_path_index: best.groups_path_index,
+                groups_path_shares: None,
             };
         }
 
-        // get one random overcrowded edge and its occupying groups by index
-        let (edge, group_indices) =
-            current_state.get_random_overcrowded_edge_with_groups(graph, groups, &mut rng);
+        let (edge, group_indices) = overcrowded_edge.unwrap();
 
         // find a detour for a random group in previously found groups
         let (group_index, path) =
-            current_state.find_detour_for_random_group(graph, groups, group_indices, edge, &mut rng);
+            current_state.find_detour_for_random_group(graph, inaccessible_stations, groups, group_indices, edge, &mut rng);
     
         
-        match path {
+        let accepted = match path {
             // another path was found
             Some(path) => {
                 let old_path_index = current_state.groups_path_index[group_index];
@@ -121,10 +151,13 @@ pub fn simulated_annealing<'a>(
                 // add path to paths of group
                 groups[group_index].paths.insert(0, path.clone());
 
+                // previously selected path was shifted by the insertion (also in the incumbent)
+                current_state.groups_path_index[group_index] = old_path_index + 1;
+                best.groups_path_index[group_index] += 1;
 
                 // create new state
                 let next =
-                    current_state.group_neighbor_from_group_and_path(graph, groups, group_index, 0);
+                    current_state.group_neighbor_from_group_and_path(graph, &context, groups, group_index, 0);
 
                 // if next_state is better than current_state -> delta positive
                 // if next_state is worse than current_state -> delta negative
@@ -133,31 +166,43 @@ pub fn simulated_annealing<'a>(
                 print!("delta_cost={:4.}, ", delta_cost);
 
                 if delta_cost > 0 {
+                    context.switch_path(graph, &groups[group_index].paths[old_path_index + 1], &groups[group_index].paths[0]);
                     current_state = next.clone();
                     println!("{}", format!("-> replacing current state").green());
+                    true
                 } else {
-                    let probability = (delta_cost as f64 / 50.0 / temperature as f64).exp();
+                    let probability = (delta_cost as f64 / temperature as f64).exp();
                     let random = rng.gen_range(0.0..1.0);
 
                     print!("probability={:.2}, random={:.2} ", probability, random);
 
                     if random < probability {
                         println!("{}", format!("-> choosing worse neighbor").red());
+                        context.switch_path(graph, &groups[group_index].paths[old_path_index + 1], &groups[group_index].paths[0]);
                         current_state = next.clone();
+                        true
                     } else {
                         println!("-> skipping");
-                        current_state.groups_path_index[group_index] = old_path_index + 1;
+                        false
                     }
                 }
             },
             // No other path was found
             None => {
-                println!("-> skipping")
+                println!("-> skipping");
+                false
             }
-        }
+        };
 
         // print!("next_state={:?}, ", next_state.groups_paths_selection);
 
+        let improved = current_state.cost < best.cost;
+        if improved {
+            best = current_state.clone();
+            time_of_last_improvement = time;
+        }
+
+        schedule.cool(accepted, improved);
         time += 1;
     }
 }
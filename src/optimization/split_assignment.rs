@@ -0,0 +1,176 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    time::Instant,
+};
+
+use petgraph::graph::DiGraph;
+
+use super::{evaluation::EvaluationContext, GroupOrder, SelectionState};
+use crate::model::{
+    cost_model::CostModel,
+    graph_weight::{TimetableEdge, TimetableNode},
+    group::Group,
+};
+
+/// hard capacity mode: distributes the passengers of each group over its paths in integer shares,
+/// so that no Trip edge is utilized over its capacity
+///
+/// first assigns one group after another (in the given order) by filling its paths in ascending order of cost
+/// up to their residual capacity, then moves passengers of a group from one of its paths to another one with residual capacity,
+/// if the other path is cheaper or the former path exceeds a capacity (until no passenger can be moved)
+///
+/// passengers that do not fit on any path of their group are assigned to its best path,
+/// so the returned state only exceeds capacities if the paths of the groups do not suffice (see overflow in the CSV outputs)
+pub fn split_assignment<'a>(
+    graph: &DiGraph<TimetableNode, TimetableEdge>,
+    cost_model: &'a CostModel,
+    groups: &'a Vec<Group>,
+    filepath: &str,
+    order: GroupOrder,
+) -> SelectionState<'a> {
+    println!("split_assignment(order={})", order);
+
+    let mut writer = BufWriter::new(
+        File::create(format!("{}.{}", filepath, "csv"))
+            .unwrap_or_else(|_| panic!("Could not create file \"{}.csv\"", filepath)),
+    );
+
+    writeln!(writer, "pass,moved_passengers,cost,overflow").unwrap();
+
+    let mut r_writer = BufWriter::new(
+        File::create(format!("{}_{}.{}", filepath, "runtime", "csv"))
+            .unwrap_or_else(|_| panic!("Could not create file \"{}_runtime.csv\"", filepath)),
+    );
+
+    writeln!(r_writer, "runtime,passes,split_groups,overflow").unwrap();
+
+    let start_instant = Instant::now();
+
+    let groups_order = order.sort(groups);
+
+    // paths of each group in ascending order of cost (lowest path index on ties)
+    let groups_paths_by_cost: Vec<Vec<usize>> = groups
+        .iter()
+        .map(|group| {
+            let mut path_indices: Vec<usize> = (0..group.paths.len()).collect();
+            path_indices.sort_by_key(|path_index| group.paths[*path_index].cost());
+            path_indices
+        })
+        .collect();
+
+    // no group assigned yet
+    let mut context = EvaluationContext::new(graph, cost_model, &[], &[]);
+    let mut groups_path_shares: Vec<Vec<u64>> = groups.iter().map(|group| vec![0; group.paths.len()]).collect();
+
+    for group_index in groups_order.iter() {
+        let group = &groups[*group_index];
+        let mut remaining = group.passengers;
+
+        for path_index in groups_paths_by_cost[*group_index].iter() {
+            let path = &group.paths[*path_index];
+            let passengers = remaining.min(context.residual_capacity(graph, path.edges.iter()));
+
+            context.strain_path_share(graph, path, passengers);
+            groups_path_shares[*group_index][*path_index] += passengers;
+            remaining -= passengers;
+        }
+
+        // overflow: no path has residual capacity left
+        let best_path_index = groups_paths_by_cost[*group_index][0];
+        context.strain_path_share(graph, &group.paths[best_path_index], remaining);
+        groups_path_shares[*group_index][best_path_index] += remaining;
+    }
+
+    let state = SelectionState::from_groups_path_shares(graph, cost_model, groups, groups_path_shares.clone());
+    writeln!(writer, "0,0,{},{}", state.cost, context.overflow(graph)).unwrap();
+    println!("[pass=0]: cost={}, overflow={}", state.cost, context.overflow(graph));
+
+    // each move decreases the overflow or keeps it and decreases the cost, so the passes terminate
+    let mut pass = 0;
+
+    loop {
+        pass += 1;
+        let mut moved_passengers = 0;
+
+        for group_index in groups_order.iter() {
+            let paths = &groups[*group_index].paths;
+            let path_shares = &mut groups_path_shares[*group_index];
+
+            for from in 0..paths.len() {
+                for to in groups_paths_by_cost[*group_index].iter().copied() {
+                    if path_shares[from] == 0 {
+                        break;
+                    }
+
+                    if to == from {
+                        continue;
+                    }
+
+                    // only the edges of one of both paths change their utilization
+                    let from_exceeds_capacity = paths[from]
+                        .edges
+                        .iter()
+                        .filter(|edge| !paths[to].edges.contains(*edge))
+                        .any(|edge| graph[*edge].is_trip() && context.utilization(*edge) > graph[*edge].capacity());
+
+                    if paths[to].cost() >= paths[from].cost() && !from_exceeds_capacity {
+                        continue;
+                    }
+
+                    let residual_capacity = context.residual_capacity(
+                        graph,
+                        paths[to].edges.iter().filter(|edge| !paths[from].edges.contains(*edge)),
+                    );
+                    let passengers = path_shares[from].min(residual_capacity);
+
+                    if passengers > 0 {
+                        context.relieve_path_share(graph, &paths[from], passengers);
+                        context.strain_path_share(graph, &paths[to], passengers);
+                        path_shares[from] -= passengers;
+                        path_shares[to] += passengers;
+                        moved_passengers += passengers;
+                    }
+                }
+            }
+        }
+
+        let state = SelectionState::from_groups_path_shares(graph, cost_model, groups, groups_path_shares.clone());
+        writeln!(writer, "{},{},{},{}", pass, moved_passengers, state.cost, context.overflow(graph)).unwrap();
+        println!(
+            "[pass={}]: moved_passengers={}, cost={}, overflow={}",
+            pass,
+            moved_passengers,
+            state.cost,
+            context.overflow(graph)
+        );
+
+        if moved_passengers == 0 {
+            break;
+        }
+    }
+
+    let n_split_groups = groups_path_shares
+        .iter()
+        .filter(|path_shares| path_shares.iter().filter(|passengers| **passengers > 0).count() > 1)
+        .count();
+
+    println!(
+        "split {} groups with overflow={} (done in {}s)",
+        n_split_groups,
+        context.overflow(graph),
+        start_instant.elapsed().as_secs()
+    );
+
+    writeln!(
+        r_writer,
+        "{}s,{},{},{}",
+        start_instant.elapsed().as_secs(),
+        pass,
+        n_split_groups,
+        context.overflow(graph)
+    )
+    .unwrap();
+
+    SelectionState::from_groups_path_shares(graph, cost_model, groups, groups_path_shares)
+}
@@ -0,0 +1,190 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    time::Instant,
+};
+
+use petgraph::graph::DiGraph;
+
+use super::{InitialState, SelectionState};
+use crate::model::{
+    cost_model::CostModel,
+    graph_weight::{TimetableEdge, TimetableNode},
+    group::Group,
+};
+
+/// neighborhood of a state searched in each iteration of tabu_search()
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Neighborhood {
+    All,    // all_group_neighbors(): each other path of each group
+    Direct, // all_direct_group_neighbors(): the previous and the next path of each group
+}
+
+/// parameters of tabu_search()
+#[derive(Debug, Clone)]
+pub struct TabuParameters {
+    pub n_iterations: u64,
+    pub tabu_tenure: u64,           // number of iterations a group must not return to its former path
+    pub diversification_window: u64, // restart from the least selected paths after this many iterations without a new best state (0 = no restarts)
+    pub neighborhood: Neighborhood,
+    pub initial_state: InitialState,
+}
+
+/// tabu search implementation
+///
+/// starts with the initial state of parameters and moves to the best neighbor in each iteration, even if it is worse.
+/// after moving a group away from a path, selecting this path again is tabu for tabu_tenure iterations,
+/// unless the neighbor is better than the best state found so far (aspiration)
+///
+/// if no better state was found for diversification_window iterations, the search restarts with the path
+/// selected least often of each group (frequency memory), so it explores other regions of the search space
+///
+/// the search is deterministic (ties are broken by the order of the neighborhood), returns the best state found
+pub fn tabu_search<'a>(
+    graph: &DiGraph<TimetableNode, TimetableEdge>,
+    cost_model: &'a CostModel,
+    groups: &'a Vec<Group>,
+    filepath: &str,
+    parameters: &TabuParameters,
+) -> SelectionState<'a> {
+    println!(
+        "tabu_search(n_iterations={}, tabu_tenure={}, diversification_window={}, neighborhood={:?})",
+        parameters.n_iterations, parameters.tabu_tenure, parameters.diversification_window, parameters.neighborhood
+    );
+
+    let mut writer = BufWriter::new(
+        File::create(format!("{}.{}", filepath, "csv"))
+            .unwrap_or_else(|_| panic!("Could not create file \"{}.csv\"", filepath)),
+    );
+
+    writeln!(writer, "time,cost,edge_cost,travel_cost,delay_cost,best_cost,restarts").unwrap();
+
+    let mut r_writer = BufWriter::new(
+        File::create(format!("{}_{}.{}", filepath, "runtime", "csv"))
+            .unwrap_or_else(|_| panic!("Could not create file \"{}_runtime.csv\"", filepath)),
+    );
+
+    writeln!(r_writer, "runtime,time,restarts").unwrap();
+
+    let start_instant = Instant::now();
+
+    let mut current = SelectionState::generate_initial_state(graph, cost_model, groups, &parameters.initial_state);
+    let mut best = current.clone(); // incumbent
+
+    // iteration until which selecting path p for group g is tabu
+    let mut tabu_until: Vec<Vec<u64>> = groups.iter().map(|group| vec![0; group.paths.len()]).collect();
+
+    // number of iterations path p was selected for group g
+    let mut frequencies: Vec<Vec<u64>> = groups.iter().map(|group| vec![0; group.paths.len()]).collect();
+
+    let mut time_of_last_improvement = 0;
+    let mut n_restarts = 0;
+    let mut time = 0;
+
+    while time < parameters.n_iterations {
+        time += 1;
+
+        for (group_index, path_index) in current.groups_path_index.iter().enumerate() {
+            frequencies[group_index][*path_index] += 1;
+        }
+
+        writeln!(
+            writer,
+            "{},{},{},{},{},{},{}",
+            time,
+            current.cost,
+            current.strained_edges_cost,
+            current.travel_cost,
+            current.travel_delay_cost,
+            best.cost,
+            n_restarts
+        )
+        .unwrap();
+
+        let neighbors = match parameters.neighborhood {
+            Neighborhood::All => current.all_group_neighbors(graph),
+            Neighborhood::Direct => current.all_direct_group_neighbors(graph),
+        };
+
+        // best admissible neighbor: not tabu or better than the incumbent (aspiration)
+        let next = neighbors
+            .into_iter()
+            .enumerate()
+            .flat_map(|(group_index, group_neighbors)| group_neighbors.into_iter().map(move |neighbor| (group_index, neighbor)))
+            .filter(|(group_index, neighbor)| {
+                tabu_until[*group_index][neighbor.groups_path_index[*group_index]] < time || neighbor.cost < best.cost
+            })
+            .min_by_key(|(_, neighbor)| neighbor.cost);
+
+        let (group_index, next) = match next {
+            Some(next) => next,
+            None => {
+                println!("[time={}]: all neighbors are tabu -> return", time);
+                break;
+            }
+        };
+
+        // moving back to the former path is tabu
+        tabu_until[group_index][current.groups_path_index[group_index]] = time + parameters.tabu_tenure;
+        current = next;
+
+        if current.cost < best.cost {
+            best = current.clone();
+            time_of_last_improvement = time;
+        }
+
+        println!(
+            "[time={}]: cost={}, edge_cost={}, travel_cost={}, delay_cost={}, best_cost={}",
+            time,
+            current.cost,
+            current.strained_edges_cost,
+            current.travel_cost,
+            current.travel_delay_cost,
+            best.cost
+        );
+
+        // diversification: restart with the least frequently selected paths (first path on ties)
+        if parameters.diversification_window != 0 && time - time_of_last_improvement >= parameters.diversification_window {
+            let groups_path_index = frequencies
+                .iter()
+                .map(|group_frequencies| {
+                    (0..group_frequencies.len())
+                        .min_by_key(|path_index| group_frequencies[*path_index])
+                        .unwrap()
+                })
+                .collect();
+
+            current = SelectionState::from_groups_path_index(graph, cost_model, groups, groups_path_index);
+            time_of_last_improvement = time;
+            n_restarts += 1;
+
+            if current.cost < best.cost {
+                best = current.clone();
+            }
+
+            for group_tabu_until in tabu_until.iter_mut() {
+                group_tabu_until.iter_mut().for_each(|until| *until = 0);
+            }
+
+            println!("-> diversification restart {} with cost={}", n_restarts, current.cost);
+        }
+    }
+
+    println!(
+        "best_cost={} after {} restarts (done in {}s)",
+        best.cost,
+        n_restarts,
+        start_instant.elapsed().as_secs()
+    );
+
+    writeln!(
+        r_writer,
+        "{}s,{},{}",
+        start_instant.elapsed().as_secs(),
+        time,
+        n_restarts
+    )
+    .unwrap();
+
+    best
+}
@@ -0,0 +1,120 @@
+/// minutes of a day (times are minutes after midnight of day 0)
+pub const MINUTES_PER_DAY: u64 = 24 * 60;
+
+/// parses a time into minutes after midnight of day 0
+///
+/// accepts minutes (`480`), `HH:MM[:SS]` with an optional day offset (`00:10+1` is 10 minutes after midnight of day 1,
+/// hours may exceed 23 like in GTFS) and ISO-8601 timestamps (`2021-05-04T00:10[:SS]` or with a space instead of `T`,
+/// local time without time zone), which are relative to reference_date (day 0, in days since 1970-01-01)
+///
+/// seconds are truncated
+pub fn parse_time(value: &str, reference_date: Option<i64>) -> Option<u64> {
+    let value = value.trim();
+
+    if let Ok(minutes) = value.parse() {
+        return Some(minutes);
+    }
+
+    if let Some(date) = parse_date(value) {
+        let days = date - reference_date?;
+        let time_of_day = parse_time_of_day(value.get(11..)?)?;
+
+        if days < 0 || !matches!(&value[10..11], "T" | " ") || time_of_day >= MINUTES_PER_DAY {
+            return None;
+        }
+
+        return Some(days as u64 * MINUTES_PER_DAY + time_of_day);
+    }
+
+    let (time_of_day, days) = match value.split_once('+') {
+        Some((time_of_day, days)) => (time_of_day, days.parse::<u64>().ok()?),
+        None => (value, 0),
+    };
+
+    Some(days * MINUTES_PER_DAY + parse_time_of_day(time_of_day)?)
+}
+
+/// returns true if value is a time of day (`HH:MM[:SS]` without day offset), which lies on a later day if it would be before
+/// the previous time of a trip (see `roll_over()`)
+pub fn is_time_of_day(value: &str) -> bool {
+    let value = value.trim();
+    value.contains(':') && !value.contains('+') && parse_date(value).is_none()
+}
+
+/// moves a time of day (on day 0) to the first day it is not before previous_time (e.g. the arrival of a trip crossing midnight)
+pub fn roll_over(time: u64, previous_time: u64) -> u64 {
+    if time >= previous_time {
+        time
+    } else {
+        time + (previous_time - time).div_ceil(MINUTES_PER_DAY) * MINUTES_PER_DAY
+    }
+}
+
+/// parses `HH:MM[:SS]` into minutes after midnight (hours may exceed 23 like in GTFS)
+pub(crate) fn parse_time_of_day(value: &str) -> Option<u64> {
+    let mut parts = value.split(':');
+
+    let hours: u64 = parts.next()?.parse().ok()?;
+    let minutes: u64 = parts.next()?.parse().ok()?;
+    let seconds: u64 = parts.next().map_or(Some(0), |seconds| seconds.parse().ok())?;
+
+    if parts.next().is_some() || minutes > 59 || seconds > 59 {
+        return None;
+    }
+
+    Some(hours * 60 + minutes)
+}
+
+/// returns the date (in days since 1970-01-01) of an ISO-8601 timestamp or date (starting with `YYYY-MM-DD`)
+pub fn parse_date(value: &str) -> Option<i64> {
+    let date = value.trim().get(..10)?;
+    let mut parts = date.split('-');
+
+    let year = parts.next().filter(|year| year.len() == 4)?.parse().ok()?;
+    let month = parts.next().filter(|month| month.len() == 2)?.parse().ok()?;
+    let day = parts.next().filter(|day| day.len() == 2)?.parse().ok()?;
+
+    days_from_civil(year, month, day)
+}
+
+/// returns the earliest date (in days since 1970-01-01) of the ISO-8601 timestamps in values, used as day 0
+pub fn earliest_date<'a>(values: impl Iterator<Item = &'a str>) -> Option<i64> {
+    values.filter_map(parse_date).min()
+}
+
+/// returns the days since 1970-01-01 of a date of the (proleptic) gregorian calendar, None if the date does not exist
+pub fn days_from_civil(year: i64, month: u32, day: u32) -> Option<i64> {
+    let is_leap_year = (year % 4 == 0 && year % 100 != 0) || year % 400 == 0;
+    let days_in_month = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if is_leap_year => 29,
+        2 => 28,
+        _ => return None,
+    };
+
+    if day == 0 || day > days_in_month {
+        return None;
+    }
+
+    // years start in march, so the leap day is the last day of a year
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month as i64 + 9) % 12) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    Some(era * 146097 + day_of_era - 719468)
+}
+
+/// formats minutes after midnight of day 0 as `HH:MM`, with the day offset for later days (`00:10+1`)
+pub fn format_time(minutes: u64) -> String {
+    let days = minutes / MINUTES_PER_DAY;
+    let time_of_day = minutes % MINUTES_PER_DAY;
+
+    if days == 0 {
+        format!("{:02}:{:02}", time_of_day / 60, time_of_day % 60)
+    } else {
+        format!("{:02}:{:02}+{}", time_of_day / 60, time_of_day % 60, days)
+    }
+}